@@ -0,0 +1,258 @@
+//! Per-jewel configuration, read from `/.emerald/config.toml`.
+//!
+//! ```toml
+//! ignore = ["*.tmp", "/archive"]
+//!
+//! [markdown]
+//! gfm = true
+//! math = false
+//! html = true
+//!
+//! [daily_notes]
+//! folder = "/journal"
+//! date_format = "%Y-%m-%d"
+//!
+//! [scripts]
+//! dirs = ["/.emerald/scripts"]
+//! memory_limit = 67108864
+//! instruction_limit = 10000000
+//!
+//! [history]
+//! enabled = true
+//! keep = 50
+//! max_age = 2592000
+//!
+//! [[mounts]]
+//! path = "/shared"
+//! target = "/home/user/shared-jewel"
+//!
+//! [[mounts]]
+//! path = "/archive"
+//! target = "../archive"
+//! read_only = true
+//! ```
+//!
+//! Every key is optional. Unknown keys, and unknown tables, are preserved in the
+//! `extra` table of their section, for scripts and extensions.
+use std::{fmt::Write, str::FromStr};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    path::Path,
+    storage::Storage,
+};
+
+/// The path of the configuration file, from the jewel's root.
+pub const CONFIG_FILE: &str = "/.emerald/config.toml";
+
+/// The configuration file written by [crate::Emerald::init], equivalent to the default configuration.
+pub(crate) const DEFAULT_CONFIG: &str = r#"# Ignore patterns, with gitignore semantics.
+ignore = []
+
+[markdown]
+gfm = true
+math = false
+html = true
+
+[daily_notes]
+folder = "/"
+date_format = "%Y-%m-%d"
+
+[scripts]
+dirs = []
+# memory_limit = 67108864
+# instruction_limit = 10000000
+
+[history]
+enabled = true
+# The retention policy applied when pruning the history.
+# keep = 50
+# max_age = 2592000
+
+# Directories of the storage mounted in the jewel, the target may be relative to the jewel's root.
+# [[mounts]]
+# path = "/archive"
+# target = "../archive"
+# read_only = true
+"#;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+/// The configuration of a jewel.
+pub struct Config {
+    /// Ignore patterns applied from the jewel's root, with gitignore semantics.
+    ///
+    /// The `.emeraldignore` files can re-include the ignored paths.
+    pub ignore: Vec<String>,
+    pub markdown: MarkdownConfig,
+    pub daily_notes: DailyNotesConfig,
+    pub scripts: ScriptsConfig,
+    pub history: HistoryConfig,
+    /// The directories mounted in the jewel.
+    pub mounts: Vec<Mount>,
+    /// The unknown keys.
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+impl FromStr for Config {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(toml::from_str(s)?)
+    }
+}
+
+impl Config {
+    /// Read the configuration file of the jewel stored in the storage,
+    /// or the default configuration if it does not exist.
+    pub fn load(storage: &dyn Storage) -> Result<Self> {
+        let canon = CONFIG_FILE
+            .split('/')
+            .fold(storage.root().to_path_buf(), |canon, part| canon.join(part));
+
+        if !storage.exists(&canon) {
+            return Ok(Self::default());
+        }
+
+        let content = String::from_utf8(storage.read(&canon)?)
+            .map_err(|err| Error::InvalidInput(format!("{}: {}", CONFIG_FILE, err)))?;
+
+        content.parse()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+/// The markdown constructs enabled when parsing the shards, besides CommonMark.
+pub struct MarkdownConfig {
+    /// GitHub flavored markdown: autolinks, footnotes, strikethrough, tables and task lists.
+    pub gfm: bool,
+    /// `$math$` and `$$` math blocks.
+    pub math: bool,
+    /// Raw HTML.
+    pub html: bool,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            gfm: true,
+            math: false,
+            html: true,
+            extra: toml::Table::default(),
+        }
+    }
+}
+
+impl MarkdownConfig {
+    /// The constructs of the markdown parser, the frontmatter is always enabled.
+    pub fn constructs(&self) -> markdown::Constructs {
+        let mut constructs = if self.gfm {
+            markdown::Constructs::gfm()
+        } else {
+            markdown::Constructs::default()
+        };
+
+        constructs.frontmatter = true;
+        constructs.math_flow = self.math;
+        constructs.math_text = self.math;
+        constructs.html_flow = self.html;
+        constructs.html_text = self.html;
+        constructs
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DailyNotesConfig {
+    /// The folder of the daily notes.
+    pub folder: Path,
+    /// The name of a daily note, with the chrono format syntax.
+    pub date_format: String,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+impl Default for DailyNotesConfig {
+    fn default() -> Self {
+        Self {
+            folder: Path::root(),
+            date_format: "%Y-%m-%d".to_string(),
+            extra: toml::Table::default(),
+        }
+    }
+}
+
+impl DailyNotesConfig {
+    /// The path of the daily note of the date, None if the date format is invalid.
+    pub fn path(&self, date: NaiveDate) -> Option<Path> {
+        let mut name = String::default();
+        write!(name, "{}.md", date.format(&self.date_format)).ok()?;
+        self.folder.join(&name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct ScriptsConfig {
+    /// The folders searched by `require`.
+    pub dirs: Vec<Path>,
+    /// The maximum memory of a script instance, in bytes.
+    pub memory_limit: Option<usize>,
+    /// The maximum number of instructions run by a single execution.
+    pub instruction_limit: Option<u64>,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Record a snapshot of the shards written through [crate::fs].
+    pub enabled: bool,
+    /// The number of versions kept per shard.
+    pub keep: Option<usize>,
+    /// The maximum age of a version, in seconds.
+    pub max_age: Option<u64>,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            keep: None,
+            max_age: None,
+            extra: toml::Table::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// A directory of the storage, such as another jewel, mounted at a path of the jewel.
+///
+/// The mount point does not need to exist in the jewel, it is listed by its parent directory,
+/// and shadows any entry of the same name.
+pub struct Mount {
+    /// The mount point in the jewel.
+    pub path: Path,
+    /// The mounted directory, absolute or relative to the jewel's root.
+    pub target: std::path::PathBuf,
+    /// Reject any write within the mount.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl Mount {
+    /// The mounted directory on the storage.
+    pub fn canon(&self, root: &std::path::Path) -> std::path::PathBuf {
+        root.join(&self.target)
+    }
+}
@@ -1,29 +1,228 @@
-use std::{error::Error, ffi::OsStr, sync::Arc};
+use std::{
+    ffi::OsStr,
+    io::{Read, Write},
+    sync::Arc,
+};
+
+use crate::{
+    config::{Config, CONFIG_FILE, DEFAULT_CONFIG},
+    error::{Error, Result},
+    fs::{Access, ExportOptions, ExportReport, ImportOptions, ImportReport, Watcher},
+    path::Path,
+    storage::{DiskStorage, Storage},
+};
+
+/// The directory marking the root of a jewel, and holding its internal data.
+pub const MARKER_DIR: &str = ".emerald";
+
+/// The shard written by [Emerald::init].
+const INDEX_SHARD: &str = "/index.md";
 
 struct Inner {
-    pub(crate) root: std::path::PathBuf,
+    pub(crate) storage: Box<dyn Storage>,
+    config: Config,
 }
 
 #[derive(Clone)]
 /// Main object
-pub struct Emerald(Arc<Inner>);
+///
+/// The clones share the same jewel, a handle can be restricted with [Emerald::read_only],
+/// [Emerald::scoped_to], [Emerald::allow] and [Emerald::deny] before being handed out.
+pub struct Emerald {
+    inner: Arc<Inner>,
+    access: Arc<Access>,
+}
 
 impl Emerald {
-    /// Open an emerald
-    pub fn open<S>(root: &S) -> Result<Self, Box<dyn Error>>
+    /// Open the jewel rooted at the directory.
+    ///
+    /// Any directory can be opened, see [Emerald::discover] to find the root of a jewel.
+    pub fn open<S>(root: &S) -> Result<Self>
     where
         S: AsRef<OsStr> + ?Sized,
     {
         let root = std::path::Path::new(root);
 
         if !std::fs::metadata(root)?.is_dir() {
-            panic!("not a directory")
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotADirectory,
+                format!("{} is not a directory", root.display()),
+            )
+            .into());
+        }
+
+        Self::load(DiskStorage::new(root))
+    }
+
+    /// Scaffold a new jewel in the directory, and open it.
+    ///
+    /// The directory is created if it does not exist, and receives the `.emerald` marker
+    /// directory, the default configuration file and an index shard, which is kept if it exists.
+    /// Fails if the directory is already a jewel.
+    pub fn init<S>(root: &S) -> Result<Self>
+    where
+        S: AsRef<OsStr> + ?Sized,
+    {
+        let root = std::path::Path::new(root);
+        std::fs::create_dir_all(root)?;
+
+        let marker = Path::new(MARKER_DIR).expect("valid constant");
+        let jewel = Self::from_storage(DiskStorage::new(root));
+
+        if crate::fs::metadata(&jewel, &marker).is_ok() {
+            return Err(Error::AlreadyExists(marker));
+        }
+
+        crate::fs::create_dir_all(&jewel, &marker)?;
+        crate::fs::write(
+            &jewel,
+            &Path::new(CONFIG_FILE).expect("valid constant"),
+            DEFAULT_CONFIG,
+        )?;
+
+        let index = Path::new(INDEX_SHARD).expect("valid constant");
+
+        if crate::fs::metadata(&jewel, &index).is_err() {
+            let name = std::fs::canonicalize(root)?
+                .file_name()
+                .and_then(OsStr::to_str)
+                .unwrap_or("Index")
+                .to_string();
+            crate::fs::write(&jewel, &index, format!("# {}\n", name))?;
         }
 
-        Ok(Emerald(Arc::new(Inner { root: root.into() })))
+        Self::open(root)
+    }
+
+    /// Open the nearest jewel containing the directory, walking up its parents like git does.
+    ///
+    /// A jewel is marked by its `.emerald` directory.
+    pub fn discover<S>(cwd: &S) -> Result<Self>
+    where
+        S: AsRef<OsStr> + ?Sized,
+    {
+        let cwd = std::fs::canonicalize(std::path::Path::new(cwd))?;
+
+        cwd.ancestors()
+            .find(|dir| dir.join(MARKER_DIR).is_dir())
+            .map(Self::open)
+            .unwrap_or_else(|| Err(Error::NotAJewel(cwd.clone())))
+    }
+
+    /// Create an emerald backed by the storage, with the default configuration.
+    ///
+    /// See [Emerald::load] to read the configuration file of the jewel.
+    pub fn from_storage<S: Storage + 'static>(storage: S) -> Self {
+        Self::with_config(storage, Config::default())
+    }
+
+    /// Create an emerald backed by the storage, with its configuration file.
+    pub fn load<S: Storage + 'static>(storage: S) -> Result<Self> {
+        let config = Config::load(&storage)?;
+        Ok(Self::with_config(storage, config))
+    }
+
+    /// Create an emerald backed by the storage, with the configuration.
+    pub fn with_config<S: Storage + 'static>(storage: S, config: Config) -> Self {
+        Emerald {
+            inner: Arc::new(Inner {
+                storage: Box::new(storage),
+                config,
+            }),
+            access: Arc::default(),
+        }
     }
 
     pub fn get_root(&self) -> &std::path::Path {
-        &self.0.root
+        self.inner.storage.root()
+    }
+
+    pub fn storage(&self) -> &dyn Storage {
+        self.inner.storage.as_ref()
+    }
+
+    /// The configuration of the jewel.
+    pub fn config(&self) -> &Config {
+        &self.inner.config
+    }
+
+    /// The restrictions of the handle.
+    pub fn access(&self) -> &Access {
+        &self.access
+    }
+
+    /// Derive a handle which cannot create, modify nor remove any file.
+    pub fn read_only(&self) -> Self {
+        self.restricted(self.access().clone().read_only())
+    }
+
+    /// Derive a handle restricted to the path and its descendants.
+    pub fn scoped_to(&self, path: &Path) -> Self {
+        self.restricted(self.access().clone().scoped_to(path))
+    }
+
+    /// Derive a handle restricted to the paths and their descendants.
+    ///
+    /// Unlike a scope, the parent directories of the paths can be read,
+    /// and list the allowed entries only.
+    pub fn allow(&self, paths: &[Path]) -> Self {
+        self.restricted(self.access().clone().allow(paths))
+    }
+
+    /// Derive a handle denied the paths and their descendants.
+    pub fn deny(&self, paths: &[Path]) -> Self {
+        self.restricted(self.access().clone().deny(paths))
+    }
+
+    /// Derive a handle which can write through the symbolic links targeting the storage,
+    /// out of the jewel. A restricted handle never follows such links.
+    pub fn allow_storage_writes(&self) -> Self {
+        self.restricted(self.access().clone().storage_writes())
+    }
+
+    fn restricted(&self, access: Access) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            access: Arc::new(access),
+        }
+    }
+
+    /// A handle without restrictions, for the internal bookkeeping of the jewel.
+    pub(crate) fn unrestricted(&self) -> Self {
+        self.restricted(Access::default())
+    }
+
+    /// Watch the changes within the jewel, with the default options.
+    ///
+    /// See [crate::fs::WatchBuilder] to configure the watcher.
+    pub fn watch(&self) -> Result<Watcher> {
+        crate::fs::watch(self)
+    }
+
+    /// Write a tar archive of the jewel, see [crate::fs::export_archive].
+    pub fn export_archive<W: Write>(
+        &self,
+        writer: W,
+        options: &ExportOptions,
+    ) -> Result<ExportReport> {
+        crate::fs::export_archive(self, writer, options)
+    }
+
+    /// Unpack a tar archive into the directory, see [crate::fs::import_archive].
+    pub fn import_archive<R: Read>(
+        &self,
+        reader: R,
+        into: &Path,
+        options: ImportOptions,
+    ) -> Result<ImportReport> {
+        crate::fs::import_archive(self, reader, into, options)
+    }
+
+    /// Parse in parallel the shards of the jewel matching the pattern, such as `/projects/**`,
+    /// with one thread per CPU, see [crate::fs::load_all].
+    #[cfg(feature = "parallel")]
+    pub fn load_all(&self, filter: &str) -> Result<Vec<crate::fs::LoadedShard>> {
+        let builder = crate::fs::WalkBuilder::new(self, &Path::root()).include(filter);
+        crate::fs::load_all(builder, 0)
     }
 }
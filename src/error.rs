@@ -0,0 +1,199 @@
+use markdown::unist::Point;
+
+use crate::path::{InvalidPath, Path};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+/// The errors of the crate.
+pub enum Error {
+    /// The path does not exist in the jewel.
+    NotFound(Path),
+    /// The path is not a directory, nor a symbolic link to a directory.
+    NotADirectory(Path),
+    /// The path is a directory, where a file was expected.
+    IsADirectory(Path),
+    AlreadyExists(Path),
+    /// The path is malformed, or escapes the jewel's root.
+    InvalidPath(String),
+    NotASymlink(Path),
+    /// The symbolic link targets a path which does not exist.
+    BrokenSymlink {
+        link: Path,
+        target: String,
+    },
+    /// Too many levels of symbolic links were followed while resolving the path.
+    SymlinkLoop(Path),
+    /// The glob pattern is malformed.
+    Pattern(globset::Error),
+    /// The markdown document cannot be parsed.
+    Parse {
+        message: String,
+        position: Option<Point>,
+    },
+    /// The frontmatter of the shard is malformed, or cannot be represented as a [crate::shard::Value].
+    FrontMatter {
+        message: String,
+        position: Option<Point>,
+    },
+    /// Neither the directory, nor any of its parents, is a jewel.
+    NotAJewel(std::path::PathBuf),
+    /// The configuration file of the jewel is malformed.
+    Config(toml::de::Error),
+    Io(std::io::Error),
+    Script(mlua::Error),
+    /// The operation is not valid on its arguments.
+    InvalidInput(String),
+    /// The operation is not permitted on the path, such as a write within a read-only mount.
+    PermissionDenied(Path),
+    /// The advisory lock of the path is held by another handle, or another process.
+    Locked(Path),
+    /// The file changed since it was read.
+    Conflict(Path),
+    /// The shard was moved, but one of the documents linking to it could not be rewritten.
+    PartialMove {
+        /// The new location of the shard.
+        moved: Path,
+        /// The documents rewritten before the failure, the others still link to the old path.
+        rewritten: Vec<Path>,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Map the I/O error of the storage onto the jewel path.
+    pub(crate) fn from_io(err: std::io::Error, path: &Path) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::InvalidInput => Error::from(err),
+            std::io::ErrorKind::NotFound => Error::NotFound(path.clone()),
+            std::io::ErrorKind::NotADirectory => Error::NotADirectory(path.clone()),
+            std::io::ErrorKind::IsADirectory => Error::IsADirectory(path.clone()),
+            std::io::ErrorKind::AlreadyExists => Error::AlreadyExists(path.clone()),
+            std::io::ErrorKind::PermissionDenied => Error::PermissionDenied(path.clone()),
+            _ => Error::Io(err),
+        }
+    }
+
+    pub(crate) fn frontmatter(message: impl ToString) -> Self {
+        Error::FrontMatter {
+            message: message.to_string(),
+            position: None,
+        }
+    }
+}
+
+fn fmt_position(f: &mut std::fmt::Formatter<'_>, position: &Option<Point>) -> std::fmt::Result {
+    match position {
+        Some(point) => write!(f, "{}:{}: ", point.line, point.column),
+        None => Ok(()),
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(path) => write!(f, "{} does not exist", path),
+            Error::NotADirectory(path) => write!(f, "{} is not a directory", path),
+            Error::IsADirectory(path) => write!(f, "{} is a directory", path),
+            Error::AlreadyExists(path) => write!(f, "{} already exists", path),
+            Error::InvalidPath(path) => write!(f, "invalid jewel path: {}", path),
+            Error::NotASymlink(path) => write!(f, "{} is not a symlink", path),
+            Error::BrokenSymlink { link, target } => {
+                write!(f, "{} targets {}, which does not exist", link, target)
+            }
+            Error::SymlinkLoop(path) => write!(f, "too many levels of symbolic links: {}", path),
+            Error::Pattern(err) => write!(f, "invalid glob pattern: {}", err),
+            Error::Parse { message, position } => {
+                fmt_position(f, position)?;
+                write!(f, "{}", message)
+            }
+            Error::FrontMatter { message, position } => {
+                fmt_position(f, position)?;
+                write!(f, "invalid frontmatter: {}", message)
+            }
+            Error::NotAJewel(dir) => write!(
+                f,
+                "{} is not a jewel, nor any of its parents",
+                dir.display()
+            ),
+            Error::Config(err) => write!(f, "invalid configuration: {}", err),
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Script(err) => write!(f, "{}", err),
+            Error::InvalidInput(message) => write!(f, "{}", message),
+            Error::PermissionDenied(path) => write!(f, "permission denied: {}", path),
+            Error::Locked(path) => write!(f, "{} is locked", path),
+            Error::Conflict(path) => write!(f, "{} changed since it was read", path),
+            Error::PartialMove {
+                moved,
+                rewritten,
+                source,
+            } => write!(
+                f,
+                "moved to {}, but the links could not all be rewritten ({} rewritten): {}",
+                moved,
+                rewritten.len(),
+                source
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Pattern(err) => Some(err),
+            Error::Config(err) => Some(err),
+            Error::Io(err) => Some(err),
+            Error::Script(err) => Some(err),
+            Error::PartialMove { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// The storage backends report the malformed canonical paths as an [InvalidPath] I/O error.
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        if let Some(invalid) = value
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<InvalidPath>())
+        {
+            return Error::InvalidPath(invalid.0.clone());
+        }
+
+        Error::Io(value)
+    }
+}
+
+impl From<globset::Error> for Error {
+    fn from(value: globset::Error) -> Self {
+        Error::Pattern(value)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(value: toml::de::Error) -> Self {
+        Error::Config(value)
+    }
+}
+
+impl From<mlua::Error> for Error {
+    fn from(value: mlua::Error) -> Self {
+        Error::Script(value)
+    }
+}
+
+impl From<InvalidPath> for Error {
+    fn from(value: InvalidPath) -> Self {
+        Error::InvalidPath(value.0)
+    }
+}
+
+impl From<Error> for mlua::Error {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::Script(err) => err,
+            err => mlua::Error::external(err),
+        }
+    }
+}
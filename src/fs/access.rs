@@ -0,0 +1,130 @@
+use super::Intent;
+use crate::{
+    error::{Error, Result},
+    path::Path,
+};
+
+#[derive(Clone, Debug, Default)]
+/// The restrictions of an [crate::Emerald] handle, checked by every [crate::fs] operation.
+///
+/// A restriction can only be added: a handle derived from a restricted handle
+/// is at least as restricted.
+///
+/// ```
+/// use emerald::{path::Path, storage::MemoryStorage, Emerald, Error};
+///
+/// let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+///     ("/projects/alpha.md", "# Alpha"),
+///     ("/private/diary.md", "# Diary"),
+/// ]));
+///
+/// let projects = Path::new("/projects").unwrap();
+/// let reader = jewel.read_only().scoped_to(&projects);
+///
+/// assert!(emerald::fs::read_dir(&reader, &projects).is_ok());
+/// assert!(matches!(
+///     emerald::fs::write(&reader, &Path::new("/projects/alpha.md").unwrap(), "# Beta"),
+///     Err(Error::PermissionDenied(_))
+/// ));
+/// assert!(matches!(
+///     emerald::fs::open(&reader, &Path::new("/private/diary.md").unwrap()),
+///     Err(Error::PermissionDenied(_))
+/// ));
+/// ```
+pub struct Access {
+    read_only: bool,
+    /// The path must be within every scope.
+    scopes: Vec<Path>,
+    /// The path must be within a path of every allow list.
+    allows: Vec<Vec<Path>>,
+    /// The path cannot be within a denied path.
+    denies: Vec<Path>,
+    /// The links to the storage, out of the jewel, can be written through.
+    storage_writes: bool,
+}
+
+impl Access {
+    /// No restriction.
+    pub fn is_unrestricted(&self) -> bool {
+        !self.read_only
+            && self.scopes.is_empty()
+            && self.allows.is_empty()
+            && self.denies.is_empty()
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub(crate) fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    pub(crate) fn scoped_to(mut self, path: &Path) -> Self {
+        self.scopes.push(path.clone());
+        self
+    }
+
+    pub(crate) fn allow(mut self, paths: &[Path]) -> Self {
+        self.allows.push(paths.to_vec());
+        self
+    }
+
+    pub(crate) fn deny(mut self, paths: &[Path]) -> Self {
+        self.denies.extend_from_slice(paths);
+        self
+    }
+
+    pub(crate) fn storage_writes(mut self) -> Self {
+        self.storage_writes = true;
+        self
+    }
+
+    /// Returns true if the path can be read.
+    ///
+    /// The parent directories of the allowed paths can be read, to reach them,
+    /// but only list the allowed entries.
+    pub fn can_read(&self, path: &Path) -> bool {
+        self.is_permitted(path, |allowed| {
+            path.starts_with(allowed) || allowed.starts_with(path)
+        })
+    }
+
+    /// Returns true if the path can be created, modified or removed.
+    pub fn can_write(&self, path: &Path) -> bool {
+        !self.read_only && self.is_permitted(path, |allowed| path.starts_with(allowed))
+    }
+
+    fn is_permitted(&self, path: &Path, allowed: impl Fn(&Path) -> bool) -> bool {
+        self.scopes.iter().all(|scope| path.starts_with(scope))
+            && self.allows.iter().all(|paths| paths.iter().any(&allowed))
+            && !self.denies.iter().any(|denied| path.starts_with(denied))
+    }
+
+    pub(crate) fn check_read(&self, path: &Path) -> Result<()> {
+        if !self.can_read(path) {
+            return Err(Error::PermissionDenied(path.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// A restricted handle cannot follow a link out of the jewel, where its restrictions
+    /// cannot apply, and a handle writes through such a link only if explicitly allowed.
+    pub(crate) fn check_storage(&self, link: &Path, intent: Intent) -> Result<()> {
+        if !self.is_unrestricted() || (intent == Intent::Write && !self.storage_writes) {
+            return Err(Error::PermissionDenied(link.clone()));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn check_write(&self, path: &Path) -> Result<()> {
+        if !self.can_write(path) {
+            return Err(Error::PermissionDenied(path.clone()));
+        }
+
+        Ok(())
+    }
+}
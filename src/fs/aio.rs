@@ -0,0 +1,214 @@
+//! Async equivalents of the [crate::fs] functions, for tokio applications.
+//!
+//! The operations run the sync API on the tokio blocking pool, so they share its path
+//! resolution, symbolic links, mounts and restrictions. The directory entries are streamed
+//! from the blocking pool as they are read.
+//!
+//! ```
+//! use emerald::{fs::aio, path::Path, storage::MemoryStorage, Emerald};
+//!
+//! let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+//!     ("/index.md", "# Index"),
+//!     ("/projects/alpha.md", "# Alpha"),
+//! ]));
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//!
+//! runtime.block_on(async {
+//!     let mut walk = aio::walk(&jewel, &Path::root()).await.unwrap();
+//!     let mut paths = vec![];
+//!
+//!     while let Some(entry) = walk.next().await {
+//!         paths.push(entry.unwrap().path().to_string());
+//!     }
+//!
+//!     assert_eq!(paths, vec!["/index.md", "/projects", "/projects/alpha.md"]);
+//!
+//!     let shard = aio::read_shard(&jewel, &Path::new("/index.md").unwrap()).await.unwrap();
+//!     assert_eq!(shard.title().as_deref(), Some("Index"));
+//! });
+//! ```
+use std::{
+    io::Read,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+use super::{DirEntry, Metadata, WalkBuilder};
+use crate::{
+    error::{Error, Result},
+    path::Path,
+    shard::Shard,
+    Emerald,
+};
+
+/// The number of entries read ahead of the stream.
+const READ_AHEAD: usize = 64;
+
+/// Run the operation on the blocking pool.
+async fn blocking<T, F>(operation: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(operation)
+        .await
+        .map_err(|err| Error::Io(std::io::Error::other(err)))?
+}
+
+/// Stream of directory entries, read on the blocking pool.
+///
+/// The reading stops when the stream is dropped.
+pub struct EntryStream {
+    receiver: mpsc::Receiver<Result<DirEntry>>,
+}
+
+impl EntryStream {
+    /// Iterate the entries on the blocking pool, `build` fails before any entry is read.
+    async fn spawn<I, F>(build: F) -> Result<Self>
+    where
+        I: Iterator<Item = Result<DirEntry>>,
+        F: FnOnce() -> Result<I> + Send + 'static,
+    {
+        let (ready, built) = tokio::sync::oneshot::channel();
+        let (sender, receiver) = mpsc::channel(READ_AHEAD);
+
+        tokio::task::spawn_blocking(move || {
+            let entries = match build() {
+                Ok(entries) => {
+                    let _ = ready.send(Ok(()));
+                    entries
+                }
+                Err(err) => {
+                    let _ = ready.send(Err(err));
+                    return;
+                }
+            };
+
+            for entry in entries {
+                if sender.blocking_send(entry).is_err() {
+                    break;
+                }
+            }
+        });
+
+        built
+            .await
+            .map_err(|err| Error::Io(std::io::Error::other(err)))??;
+
+        Ok(Self { receiver })
+    }
+
+    /// Returns the next entry, or None at the end of the stream.
+    pub async fn next(&mut self) -> Option<Result<DirEntry>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Stream for EntryStream {
+    type Item = Result<DirEntry>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// Returns a stream over the entries within a directory, see [crate::fs::read_dir].
+pub async fn read_dir(jewel: &Emerald, path: &Path) -> Result<EntryStream> {
+    let (jewel, path) = (jewel.clone(), path.clone());
+    EntryStream::spawn(move || super::read_dir(&jewel, &path)).await
+}
+
+/// Walk from the directory to all its descendants, with the default options, see [crate::fs::walk].
+pub async fn walk(jewel: &Emerald, path: &Path) -> Result<EntryStream> {
+    walk_with(WalkBuilder::new(jewel, path)).await
+}
+
+/// Walk with the options of the builder, see [WalkBuilder].
+pub async fn walk_with(builder: WalkBuilder) -> Result<EntryStream> {
+    EntryStream::spawn(move || builder.build()).await
+}
+
+/// Returns the metadata of the file, see [crate::fs::metadata].
+pub async fn metadata(jewel: &Emerald, path: &Path) -> Result<Metadata> {
+    let (jewel, path) = (jewel.clone(), path.clone());
+    blocking(move || super::metadata(&jewel, &path)).await
+}
+
+/// Reads the entire contents of a file.
+pub async fn read(jewel: &Emerald, path: &Path) -> Result<Vec<u8>> {
+    let (jewel, path) = (jewel.clone(), path.clone());
+    blocking(move || {
+        let mut contents = Vec::default();
+        super::open(&jewel, &path)?.read_to_end(&mut contents)?;
+        Ok(contents)
+    })
+    .await
+}
+
+/// Reads and parses the shard, with the markdown configuration of the jewel.
+pub async fn read_shard(jewel: &Emerald, path: &Path) -> Result<Shard> {
+    let (jewel, path) = (jewel.clone(), path.clone());
+    blocking(move || {
+        let mut content = String::default();
+        super::open(&jewel, &path)?.read_to_string(&mut content)?;
+        Shard::parse(&content, &jewel.config().markdown)
+    })
+    .await
+}
+
+/// Writes the entire contents of a file atomically, see [crate::fs::write].
+pub async fn write<C: AsRef<[u8]> + Send + 'static>(
+    jewel: &Emerald,
+    path: &Path,
+    contents: C,
+) -> Result<()> {
+    let (jewel, path) = (jewel.clone(), path.clone());
+    blocking(move || super::write(&jewel, &path, contents)).await
+}
+
+/// Recursively creates a directory, see [crate::fs::create_dir_all].
+pub async fn create_dir_all(jewel: &Emerald, path: &Path) -> Result<()> {
+    let (jewel, path) = (jewel.clone(), path.clone());
+    blocking(move || super::create_dir_all(&jewel, &path)).await
+}
+
+/// Renames a file or directory, see [crate::fs::rename].
+pub async fn rename(jewel: &Emerald, from: &Path, to: &Path) -> Result<()> {
+    let (jewel, from, to) = (jewel.clone(), from.clone(), to.clone());
+    blocking(move || super::rename(&jewel, &from, &to)).await
+}
+
+/// Copies the contents of a file to another, see [crate::fs::copy].
+pub async fn copy(jewel: &Emerald, from: &Path, to: &Path) -> Result<u64> {
+    let (jewel, from, to) = (jewel.clone(), from.clone(), to.clone());
+    blocking(move || super::copy(&jewel, &from, &to)).await
+}
+
+/// Moves a file into the trash, see [crate::fs::remove_file].
+pub async fn remove_file(jewel: &Emerald, path: &Path) -> Result<()> {
+    let (jewel, path) = (jewel.clone(), path.clone());
+    blocking(move || super::remove_file(&jewel, &path)).await
+}
+
+/// Moves a directory and all its contents into the trash, see [crate::fs::remove_dir_all].
+pub async fn remove_dir_all(jewel: &Emerald, path: &Path) -> Result<()> {
+    let (jewel, path) = (jewel.clone(), path.clone());
+    blocking(move || super::remove_dir_all(&jewel, &path)).await
+}
+
+/// Removes a file permanently, see [crate::fs::remove_file_permanently].
+pub async fn remove_file_permanently(jewel: &Emerald, path: &Path) -> Result<()> {
+    let (jewel, path) = (jewel.clone(), path.clone());
+    blocking(move || super::remove_file_permanently(&jewel, &path)).await
+}
+
+/// Removes a directory and all its contents permanently,
+/// see [crate::fs::remove_dir_all_permanently].
+pub async fn remove_dir_all_permanently(jewel: &Emerald, path: &Path) -> Result<()> {
+    let (jewel, path) = (jewel.clone(), path.clone());
+    blocking(move || super::remove_dir_all_permanently(&jewel, &path)).await
+}
@@ -0,0 +1,411 @@
+use std::io::{ErrorKind, Read, Write};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use tar::{Archive, Builder, EntryType, Header};
+
+use super::{create_dir_all, metadata, open, remove_file, symlink, write, Symlink, WalkBuilder};
+use crate::{
+    emerald::MARKER_DIR,
+    error::{Error, Result},
+    path::Path,
+    Emerald,
+};
+
+/// The magic bytes starting a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The maximum number of names tried by [ConflictPolicy::Rename].
+const MAX_RENAMES: usize = 1000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+/// The options of [export_archive], the entries are filtered like a [WalkBuilder].
+pub struct ExportOptions {
+    /// Compress the archive with gzip.
+    pub gzip: bool,
+    /// Archive the hidden files. The `.emerald` directory, internal to the jewel,
+    /// is never archived.
+    pub hidden: bool,
+    /// Only archive the files matching one of the patterns, all of them if empty.
+    pub include: Vec<String>,
+    /// Neither archive, nor walk through, the entries matching one of the patterns.
+    pub exclude: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+/// What to do when an archived file already exists in the jewel.
+pub enum ConflictPolicy {
+    /// Keep the existing file.
+    #[default]
+    Skip,
+    /// Replace the existing file, which is moved into the trash. A directory is never replaced.
+    Overwrite,
+    /// Unpack the file next to the existing one, as `name (1).md`.
+    Rename,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+/// The options of [import_archive].
+pub struct ImportOptions {
+    pub conflict: ConflictPolicy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+/// The outcome of [export_archive].
+pub struct ExportReport {
+    /// The number of archived entries.
+    pub archived: usize,
+    /// The entries which cannot be read, and are not archived, described by their error.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+/// The outcome of [import_archive].
+pub struct ImportReport {
+    /// The files and symbolic links unpacked, at their location in the jewel.
+    pub imported: Vec<Path>,
+    /// The entries kept out by the conflict policy, or of an unsupported type.
+    pub skipped: Vec<Path>,
+    /// The entries escaping the destination directory, or within the `.emerald` directory,
+    /// as written in the archive.
+    pub rejected: Vec<String>,
+}
+
+/// Writes a tar archive of the jewel.
+///
+/// The entries which cannot be read are skipped, and listed in the report.
+/// The symbolic link files are archived as symbolic links to their target,
+/// as written in the link file, rather than followed.
+/// The ignore files of the jewel are honored.
+///
+/// ```
+/// use emerald::{fs::ExportOptions, path::Path, storage::MemoryStorage, Emerald};
+///
+/// let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+///     ("/index.md", "# Index"),
+///     ("/projects/alpha.md", "# Alpha"),
+/// ]));
+///
+/// let mut archive = Vec::default();
+/// let report = jewel.export_archive(&mut archive, &ExportOptions::default()).unwrap();
+/// assert_eq!(report.archived, 3);
+///
+/// let copy = Emerald::from_storage(MemoryStorage::new());
+/// let report = copy
+///     .import_archive(archive.as_slice(), &Path::root(), Default::default())
+///     .unwrap();
+/// assert_eq!(report.imported.len(), 2);
+/// ```
+pub fn export_archive<W: Write>(
+    jewel: &Emerald,
+    writer: W,
+    options: &ExportOptions,
+) -> Result<ExportReport> {
+    if options.gzip {
+        let mut builder = Builder::new(GzEncoder::new(writer, Compression::default()));
+        let report = append_jewel(jewel, &mut builder, options)?;
+        builder.into_inner()?.finish()?;
+        Ok(report)
+    } else {
+        let mut builder = Builder::new(writer);
+        let report = append_jewel(jewel, &mut builder, options)?;
+        builder.into_inner()?;
+        Ok(report)
+    }
+}
+
+fn append_jewel<W: Write>(
+    jewel: &Emerald,
+    builder: &mut Builder<W>,
+    options: &ExportOptions,
+) -> Result<ExportReport> {
+    let mut walk = WalkBuilder::new(jewel, &Path::root())
+        .follow_symlinks(false)
+        .hidden(options.hidden)
+        .exclude(&format!("/{}", MARKER_DIR));
+
+    for pattern in &options.include {
+        walk = walk.include(pattern);
+    }
+
+    for pattern in &options.exclude {
+        walk = walk.exclude(pattern);
+    }
+
+    let mut report = ExportReport::default();
+
+    for entry in walk.build()? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                report.skipped.push(err.to_string());
+                continue;
+            }
+        };
+
+        let meta = entry.metadata();
+        let name = entry.path().as_str().trim_start_matches('/');
+        let skip = |err: Error| format!("{}: {}", entry.path(), err);
+
+        let mut header = Header::new_gnu();
+        header.set_mtime(
+            meta.modified()
+                .map_or(0, |time| time.timestamp().max(0) as u64),
+        );
+
+        if meta.is_symlink() {
+            let link = match Symlink::load(jewel, entry.path()) {
+                Ok(link) => link,
+                Err(err) => {
+                    report.skipped.push(skip(err));
+                    continue;
+                }
+            };
+
+            header.set_entry_type(EntryType::Symlink);
+            header.set_mode(0o777);
+            header.set_size(0);
+            builder.append_link(&mut header, name, link.target())?;
+        } else if meta.is_dir() {
+            header.set_entry_type(EntryType::Directory);
+            header.set_mode(0o755);
+            header.set_size(0);
+            builder.append_data(&mut header, format!("{}/", name), std::io::empty())?;
+        } else {
+            let mut content = Vec::default();
+
+            if let Err(err) =
+                open(jewel, entry.path()).and_then(|mut file| Ok(file.read_to_end(&mut content)?))
+            {
+                report.skipped.push(skip(err));
+                continue;
+            }
+
+            header.set_entry_type(EntryType::Regular);
+            header.set_mode(0o644);
+            header.set_size(content.len() as u64);
+            builder.append_data(&mut header, name, content.as_slice())?;
+        }
+
+        report.archived += 1;
+    }
+
+    Ok(report)
+}
+
+/// Unpacks a tar archive, gzipped or not, into the directory of the jewel.
+///
+/// The entries which would escape the directory, absolute or with `..` parts,
+/// are rejected and the others are still unpacked. The entries within the `.emerald` directory
+/// are rejected too, so that an archive cannot replace the configuration or the internal data
+/// of the jewel.
+/// The symbolic links are recreated as symbolic link files, with their target unchanged.
+/// The links targeting an absolute path or a path out of the jewel are rejected, as well as
+/// the entries which would be unpacked through a symbolic link.
+/// The existing files are handled by the conflict policy, directories are merged.
+pub fn import_archive<R: Read>(
+    jewel: &Emerald,
+    reader: R,
+    into: &Path,
+    options: ImportOptions,
+) -> Result<ImportReport> {
+    let mut reader = reader;
+    let mut magic = [0; GZIP_MAGIC.len()];
+    let mut read = 0;
+
+    // The reader may return fewer bytes than the magic, until the end of the stream.
+    while read < magic.len() {
+        match reader.read(&mut magic[read..]) {
+            Ok(0) => break,
+            Ok(count) => read += count,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    let reader = (&magic[..read]).chain(reader);
+
+    if magic[..read] == GZIP_MAGIC {
+        unpack(jewel, Archive::new(GzDecoder::new(reader)), into, options)
+    } else {
+        unpack(jewel, Archive::new(reader), into, options)
+    }
+}
+
+fn unpack<R: Read>(
+    jewel: &Emerald,
+    mut archive: Archive<R>,
+    into: &Path,
+    options: ImportOptions,
+) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let marker = Path::new(MARKER_DIR).expect("valid constant");
+    create_dir_all(jewel, into)?;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+
+        let Some(path) = entry_path(into, &entry.path()?).filter(|path| !path.starts_with(&marker))
+        else {
+            report.rejected.push(name);
+            continue;
+        };
+
+        let kind = entry.header().entry_type();
+
+        // A symbolic link, unpacked or already in the jewel, is never written through.
+        if through_symlink(jewel, into, &path, kind.is_dir())? {
+            report.rejected.push(name);
+            continue;
+        }
+
+        if kind.is_dir() {
+            create_dir_all(jewel, &path)?;
+            continue;
+        }
+
+        if !(kind.is_file() || kind.is_symlink()) {
+            report.skipped.push(path);
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(jewel, &parent)?;
+        }
+
+        let Some(dest) = destination(jewel, &path, options.conflict)? else {
+            report.skipped.push(path);
+            continue;
+        };
+
+        if kind.is_symlink() {
+            let target = entry
+                .link_name()?
+                .map(|target| target.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            if !within_jewel(&dest, &target) {
+                report.rejected.push(name);
+                continue;
+            }
+
+            match symlink(jewel, &dest, &target) {
+                Ok(()) => {}
+                Err(Error::InvalidPath(_) | Error::InvalidInput(_)) => {
+                    report.rejected.push(name);
+                    continue;
+                }
+                Err(err) => return Err(err),
+            }
+        } else {
+            let mut content = Vec::default();
+            entry.read_to_end(&mut content)?;
+            write(jewel, &dest, content)?;
+        }
+
+        report.imported.push(dest);
+    }
+
+    Ok(report)
+}
+
+/// The location of the archived entry in the directory,
+/// None if the entry is absolute or would escape the directory.
+fn entry_path(into: &Path, entry: &std::path::Path) -> Option<Path> {
+    let mut path = into.clone();
+
+    for component in entry.components() {
+        match component {
+            std::path::Component::Normal(part) => {
+                let part = part.to_str()?;
+
+                if part.split('\\').any(|part| part == "..") || !path.append(part) {
+                    return None;
+                }
+            }
+            std::path::Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    path.starts_with(into).then_some(path)
+}
+
+/// Returns true if one of the parts of the path below the directory is a symbolic link,
+/// the leaf included if `leaf`.
+fn through_symlink(jewel: &Emerald, into: &Path, path: &Path, leaf: bool) -> Result<bool> {
+    let parts = path.parts().skip(into.parts().count()).collect::<Vec<_>>();
+    let count = match leaf {
+        true => parts.len(),
+        false => parts.len().saturating_sub(1),
+    };
+    let mut current = into.clone();
+
+    for part in &parts[..count] {
+        current.append(part);
+
+        match Symlink::load(jewel, &current) {
+            Ok(_) => return Ok(true),
+            Err(Error::NotFound(_) | Error::NotASymlink(_)) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(false)
+}
+
+/// Returns true if the target of the link is relative, and stays within the jewel.
+fn within_jewel(link: &Path, target: &str) -> bool {
+    let target = target.trim();
+
+    !target.starts_with(['/', '\\'])
+        && link
+            .parent()
+            .and_then(|parent| parent.join(target))
+            .is_some()
+}
+
+/// Where to unpack the file at the path, with the conflict policy.
+///
+/// Returns None if the file is kept out.
+fn destination(jewel: &Emerald, path: &Path, policy: ConflictPolicy) -> Result<Option<Path>> {
+    let existing = match metadata(jewel, path) {
+        Ok(meta) => meta,
+        Err(Error::NotFound(_)) => return Ok(Some(path.clone())),
+        Err(err) => return Err(err),
+    };
+
+    match policy {
+        ConflictPolicy::Skip => Ok(None),
+        ConflictPolicy::Overwrite if existing.is_dir() => Ok(None),
+        ConflictPolicy::Overwrite => {
+            // A symbolic link is replaced, rather than written through.
+            remove_file(jewel, path)?;
+            Ok(Some(path.clone()))
+        }
+        ConflictPolicy::Rename => {
+            let parent = path.parent().unwrap_or_default();
+            let stem = path.file_stem().unwrap_or_default();
+            let extension = path
+                .extension()
+                .map(|extension| format!(".{}", extension))
+                .unwrap_or_default();
+
+            for count in 1..=MAX_RENAMES {
+                let renamed = parent
+                    .join(&format!("{} ({}){}", stem, count, extension))
+                    .ok_or_else(|| Error::InvalidPath(path.to_string()))?;
+
+                match metadata(jewel, &renamed) {
+                    Ok(_) => {}
+                    Err(Error::NotFound(_)) => return Ok(Some(renamed)),
+                    Err(err) => return Err(err),
+                }
+            }
+
+            Err(Error::AlreadyExists(path.clone()))
+        }
+    }
+}
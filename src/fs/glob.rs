@@ -0,0 +1,86 @@
+use super::{canonicalize, DirEntry, Walk, WalkBuilder};
+use crate::{
+    error::{Error, Result},
+    path::Path,
+    Emerald,
+};
+
+/// The characters starting a wildcard in a glob part.
+const WILDCARDS: [char; 4] = ['*', '?', '[', '{'];
+
+/// Iterator over the entries matching a glob pattern, sorted by path.
+pub struct Glob {
+    walk: Option<Walk>,
+}
+
+impl Iterator for Glob {
+    type Item = Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walk.as_mut()?.next()
+    }
+}
+
+/// Returns the entries of the jewel matching the pattern.
+///
+/// The pattern is matched against the path from the jewel's root:
+/// - `*` matches any sequence of characters within a part,
+/// - `?` matches any single character,
+/// - `**` matches any number of directories,
+/// - `[a-z]` and `[!a-z]` match a character class,
+/// - `{a,b}` matches one of the alternatives.
+///
+/// Symbolic links to directories are walked through, hidden files are not matched.
+///
+/// ```
+/// use emerald::{storage::MemoryStorage, Emerald};
+///
+/// let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+///     ("/projects/alpha/2024-01.md", ""),
+///     ("/projects/2023-12.md", ""),
+///     ("/projects/2024-02.md", ""),
+/// ]));
+///
+/// let shards = emerald::fs::glob(&jewel, "/projects/**/2024-*.md")
+///     .unwrap()
+///     .map(|entry| entry.unwrap().path().to_string())
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(shards, vec!["/projects/2024-02.md", "/projects/alpha/2024-01.md"]);
+/// ```
+pub fn glob(jewel: &Emerald, pattern: &str) -> Result<Glob> {
+    let parts = pattern
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+
+    // The walk starts from the deepest directory without wildcards.
+    let literal = parts
+        .iter()
+        .take_while(|part| !part.contains(WILDCARDS))
+        .count()
+        .min(parts.len().saturating_sub(1));
+
+    let base = Path::new(&parts[..literal].join("/"))
+        .ok_or_else(|| Error::InvalidPath(pattern.to_string()))?;
+
+    let is_dir = canonicalize(jewel, &base)
+        .and_then(|canon| Ok(jewel.storage().metadata(&canon)?))
+        .is_ok_and(|meta| meta.is_dir);
+
+    if parts.is_empty() || !is_dir {
+        return Ok(Glob { walk: None });
+    }
+
+    let mut builder = WalkBuilder::new(jewel, &base)
+        .ignore_files(false)
+        .include(&format!("/{}", parts.join("/")));
+
+    if !parts[literal..].iter().any(|part| part.contains("**")) {
+        builder = builder.max_depth(parts.len() - literal);
+    }
+
+    Ok(Glob {
+        walk: Some(builder.build()?),
+    })
+}
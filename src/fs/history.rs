@@ -0,0 +1,479 @@
+use std::{collections::HashSet, io::Read, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{
+    lock::{lock, Lock, LOCK_TIMEOUT},
+    metadata, open, read_dir, remove_file_permanently, write,
+};
+use crate::{
+    error::{Error, Result},
+    path::Path,
+    shard::Shard,
+    Emerald,
+};
+
+/// The contents of the versions, named by their SHA-256 hash.
+const OBJECTS_DIR: &str = "/.emerald/history/objects";
+
+/// The versions of each shard, named by the SHA-256 hash of its path.
+const LOGS_DIR: &str = "/.emerald/history/logs";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+/// A recorded version of a shard.
+pub struct Version {
+    /// The SHA-256 hash of the content, which identifies the version.
+    pub hash: String,
+    /// The time of the write.
+    pub time: DateTime<Utc>,
+    /// The size of the content, in bytes.
+    pub len: u64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+/// The versions of a shard, from the oldest to the most recent.
+struct Log {
+    path: Path,
+    versions: Vec<Version>,
+}
+
+impl Log {
+    fn location(path: &Path) -> Result<Path> {
+        let name = format!("{:x}.json", Sha256::digest(path.as_str()));
+        history_path(LOGS_DIR, &name)
+    }
+
+    fn load(jewel: &Emerald, path: &Path) -> Result<Self> {
+        match open(jewel, &Self::location(path)?) {
+            Ok(file) => Self::read(file),
+            Err(Error::NotFound(_)) => Ok(Self {
+                path: path.clone(),
+                versions: vec![],
+            }),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn read(file: super::File) -> Result<Self> {
+        serde_json::from_reader(file)
+            .map_err(|err| Error::InvalidInput(format!("invalid history log: {}", err)))
+    }
+
+    fn save(&self, jewel: &Emerald) -> Result<()> {
+        let location = Self::location(&self.path)?;
+
+        if self.versions.is_empty() {
+            return remove_file_permanently(jewel, &location).or_else(|err| match err {
+                Error::NotFound(_) => Ok(()),
+                err => Err(err),
+            });
+        }
+
+        let content =
+            serde_json::to_vec(self).map_err(|err| Error::InvalidInput(err.to_string()))?;
+        super::create_dir_all(jewel, &Path::new(LOGS_DIR).expect("valid constant"))?;
+        write(jewel, &location, content)
+    }
+
+    /// Locks the log of the shard, while it is loaded, edited and saved.
+    fn lock(jewel: &Emerald, path: &Path) -> Result<Lock> {
+        lock(jewel, &Self::location(path)?, LOCK_TIMEOUT)
+    }
+
+    fn find(&self, hash: &str) -> Result<&Version> {
+        self.versions
+            .iter()
+            .find(|version| version.hash == hash)
+            .ok_or_else(|| Error::InvalidInput(format!("{} has no version {}", self.path, hash)))
+    }
+}
+
+fn history_path(dir: &str, name: &str) -> Result<Path> {
+    Path::new(dir)
+        .and_then(|dir| dir.join(name))
+        .ok_or_else(|| Error::InvalidPath(name.to_string()))
+}
+
+/// Locks the contents, so that they are not removed while a log may come to reference them.
+///
+/// The contents are locked before any log, by both [record] and [prune_history_with].
+fn lock_objects(jewel: &Emerald) -> Result<Lock> {
+    lock(
+        jewel,
+        &Path::new(OBJECTS_DIR).expect("valid constant"),
+        LOCK_TIMEOUT,
+    )
+}
+
+fn object_path(hash: &str) -> Result<Path> {
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::InvalidInput(format!("invalid version: {}", hash)));
+    }
+
+    history_path(OBJECTS_DIR, hash)
+}
+
+/// Returns true if the writes to the path are recorded: the shards outside of `/.emerald`.
+fn is_recorded(jewel: &Emerald, path: &Path) -> bool {
+    jewel.config().history.enabled && path.extension() == Some("md") && !is_internal(path)
+}
+
+/// Returns true if the path is within `/.emerald`, such as the trashed shards.
+fn is_internal(path: &Path) -> bool {
+    path.starts_with(&Path::new(crate::emerald::MARKER_DIR).expect("valid constant"))
+}
+
+/// Record the content written to the path, as its most recent version.
+///
+/// The callers record the version once the content is written, and ignore the error:
+/// a write is never reported as failed, nor undone, because of its history.
+pub(super) fn record(jewel: &Emerald, path: &Path, content: &[u8]) -> Result<()> {
+    if !is_recorded(jewel, path) {
+        return Ok(());
+    }
+
+    // The history is kept for any handle allowed to write the shard.
+    let jewel = &jewel.unrestricted();
+    let _objects = lock_objects(jewel)?;
+    let _log = Log::lock(jewel, path)?;
+
+    let hash = format!("{:x}", Sha256::digest(content));
+    let mut log = Log::load(jewel, path)?;
+
+    if log.versions.last().is_some_and(|last| last.hash == hash) {
+        return Ok(());
+    }
+
+    let object = object_path(&hash)?;
+
+    if metadata(jewel, &object).is_err() {
+        super::create_dir_all(jewel, &Path::new(OBJECTS_DIR).expect("valid constant"))?;
+        write(jewel, &object, content)?;
+    }
+
+    log.versions.push(Version {
+        hash,
+        time: Utc::now(),
+        len: content.len() as u64,
+    });
+    log.save(jewel)
+}
+
+/// Carry the history of the renamed shard, or of the shards of the renamed directory,
+/// over to their new location. The history of a replaced shard is dropped.
+///
+/// The history of a trashed shard stays at its location, where the shard is restored.
+/// As for [record], the callers ignore the error once the rename is done.
+pub(super) fn rename(jewel: &Emerald, from: &Path, to: &Path) -> Result<()> {
+    if !jewel.config().history.enabled || is_internal(from) || is_internal(to) {
+        return Ok(());
+    }
+
+    let jewel = &jewel.unrestricted();
+
+    if !metadata(jewel, to)?.is_dir() {
+        return move_log(jewel, from, to);
+    }
+
+    for location in logs(jewel)? {
+        let Some(log) = read_log(jewel, &location)? else {
+            continue;
+        };
+
+        let Some(relative) = log.path.strip_prefix(from) else {
+            continue;
+        };
+
+        let destination = to
+            .join(relative.as_str().trim_start_matches('/'))
+            .ok_or_else(|| Error::InvalidPath(relative.to_string()))?;
+        move_log(jewel, &log.path, &destination)?;
+    }
+
+    Ok(())
+}
+
+/// Moves the log of a shard to the log of another one, under the locks of both.
+fn move_log(jewel: &Emerald, from: &Path, to: &Path) -> Result<()> {
+    let location = Log::location(from)?;
+
+    if from == to || metadata(jewel, &location).is_err() {
+        return Ok(());
+    }
+
+    // The logs are locked in a fixed order, so that two opposite moves do not wait for each other.
+    let (first, second) = if location < Log::location(to)? {
+        (from, to)
+    } else {
+        (to, from)
+    };
+    let _first = Log::lock(jewel, first)?;
+    let _second = Log::lock(jewel, second)?;
+
+    let Some(mut log) = read_log(jewel, &location)? else {
+        return Ok(());
+    };
+
+    log.path = to.clone();
+    log.save(jewel)?;
+
+    match remove_file_permanently(jewel, &location) {
+        Err(Error::NotFound(_)) => Ok(()),
+        result => result,
+    }
+}
+
+/// Returns the recorded versions of the shard, from the oldest to the most recent.
+///
+/// Every write of a shard through [crate::fs] records a version,
+/// unless the history is disabled in the jewel's configuration.
+pub fn history(jewel: &Emerald, path: &Path) -> Result<Vec<Version>> {
+    jewel.access().check_read(path)?;
+    Ok(Log::load(&jewel.unrestricted(), path)?.versions)
+}
+
+/// Reads the content of a version of the shard.
+pub fn read_version_bytes(jewel: &Emerald, path: &Path, hash: &str) -> Result<Vec<u8>> {
+    jewel.access().check_read(path)?;
+    let jewel = &jewel.unrestricted();
+    Log::load(jewel, path)?.find(hash)?;
+
+    let mut content = Vec::default();
+    open(jewel, &object_path(hash)?)?.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// Reads a version of the shard.
+pub fn read_version(jewel: &Emerald, path: &Path, hash: &str) -> Result<Shard> {
+    let content = String::from_utf8(read_version_bytes(jewel, path, hash)?)
+        .map_err(|err| Error::InvalidInput(format!("{}: {}", path, err)))?;
+    Shard::parse(&content, &jewel.config().markdown)
+}
+
+/// Writes back a version of the shard, which records a new version.
+pub fn restore_version(jewel: &Emerald, path: &Path, hash: &str) -> Result<()> {
+    let content = read_version_bytes(jewel, path, hash)?;
+    write(jewel, path, content)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A line of a diff between two versions.
+pub enum DiffLine {
+    /// The line is in both versions.
+    Equal(String),
+    /// The line is only in the new version.
+    Insert(String),
+    /// The line is only in the old version.
+    Delete(String),
+}
+
+impl std::fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffLine::Equal(line) => write!(f, " {}", line),
+            DiffLine::Insert(line) => write!(f, "+{}", line),
+            DiffLine::Delete(line) => write!(f, "-{}", line),
+        }
+    }
+}
+
+/// Returns the line diff from the old version to the new version of the shard.
+pub fn diff_versions(jewel: &Emerald, path: &Path, old: &str, new: &str) -> Result<Vec<DiffLine>> {
+    let text = |hash| {
+        read_version_bytes(jewel, path, hash)
+            .map(|content| String::from_utf8_lossy(&content).into_owned())
+    };
+
+    Ok(diff_lines(&text(old)?, &text(new)?))
+}
+
+/// The size of the largest table of [diff_lines], about 32 MiB.
+const MAX_DIFF_CELLS: usize = 4 * 1024 * 1024;
+
+/// Diff the lines with their longest common subsequence.
+///
+/// The changed lines are compared with a table of their common subsequences,
+/// which is bounded by [MAX_DIFF_CELLS].
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    // The common prefix and suffix are kept out of the table.
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut lines = old[..prefix]
+        .iter()
+        .map(|line| DiffLine::Equal(line.to_string()))
+        .collect::<Vec<_>>();
+
+    // The table is quadratic, the larger changes are a deletion followed by an insertion.
+    if a.len().saturating_mul(b.len()) > MAX_DIFF_CELLS {
+        lines.extend(a.iter().map(|line| DiffLine::Delete(line.to_string())));
+        lines.extend(b.iter().map(|line| DiffLine::Insert(line.to_string())));
+        lines.extend(
+            old[old.len() - suffix..]
+                .iter()
+                .map(|line| DiffLine::Equal(line.to_string())),
+        );
+        return lines;
+    }
+
+    // lcs[i][j] is the length of the common subsequence of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            lines.push(DiffLine::Equal(a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(DiffLine::Delete(a[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Insert(b[j].to_string()));
+            j += 1;
+        }
+    }
+
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::Equal(line.to_string())),
+    );
+    lines
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+/// The versions to prune from the history.
+///
+/// The most recent version of a shard is always kept.
+pub struct Retention {
+    /// Keep the N most recent versions of each shard.
+    pub keep: Option<usize>,
+    /// Prune the versions older than the duration.
+    pub older_than: Option<Duration>,
+}
+
+/// Prunes the history with the retention policy of the jewel's configuration,
+/// and returns the number of pruned versions.
+pub fn prune_history(jewel: &Emerald) -> Result<usize> {
+    let config = &jewel.config().history;
+    let retention = Retention {
+        keep: config.keep,
+        older_than: config.max_age.map(Duration::from_secs),
+    };
+
+    prune_history_with(jewel, retention)
+}
+
+/// Prunes the history with the retention policy, and returns the number of pruned versions.
+///
+/// The contents which are no longer referenced by any version are removed.
+/// Each log is pruned under its lock, and the contents under the lock of
+/// the contents, so that the concurrent writes keep recording their versions.
+pub fn prune_history_with(jewel: &Emerald, retention: Retention) -> Result<usize> {
+    let older_than = retention
+        .older_than
+        .map(chrono::Duration::from_std)
+        .transpose()
+        .map_err(|err| Error::InvalidInput(err.to_string()))?;
+
+    let now = Utc::now();
+    let mut pruned = 0;
+
+    for location in logs(jewel)? {
+        let Some(log) = read_log(jewel, &location)? else {
+            continue;
+        };
+        let _lock = Log::lock(jewel, &log.path)?;
+
+        // The log may have changed before it was locked.
+        let Some(mut log) = read_log(jewel, &location)? else {
+            continue;
+        };
+
+        let count = log.versions.len();
+        let keep = retention.keep.unwrap_or(count).max(1);
+
+        log.versions = log
+            .versions
+            .into_iter()
+            .enumerate()
+            .filter(|(index, version)| {
+                let rank = count - index;
+                rank == 1 || (rank <= keep && older_than.is_none_or(|age| now - version.time < age))
+            })
+            .map(|(_, version)| version)
+            .collect();
+
+        pruned += count - log.versions.len();
+        log.save(jewel)?;
+    }
+
+    // The logs only reference new contents under the lock of the contents.
+    let _objects = lock_objects(jewel)?;
+    let mut referenced = HashSet::<String>::default();
+
+    for location in logs(jewel)? {
+        if let Some(log) = read_log(jewel, &location)? {
+            referenced.extend(log.versions.into_iter().map(|version| version.hash));
+        }
+    }
+
+    let objects = match read_dir(jewel, &Path::new(OBJECTS_DIR).expect("valid constant")) {
+        Ok(objects) => objects.flatten().collect::<Vec<_>>(),
+        Err(Error::NotFound(_)) => vec![],
+        Err(err) => return Err(err),
+    };
+
+    for object in objects {
+        if !object
+            .path()
+            .file_name()
+            .is_some_and(|hash| referenced.contains(hash))
+        {
+            remove_file_permanently(jewel, object.path())?;
+        }
+    }
+
+    Ok(pruned)
+}
+
+/// Reads the log, which may have been removed since it was listed.
+fn read_log(jewel: &Emerald, location: &Path) -> Result<Option<Log>> {
+    match open(jewel, location) {
+        Ok(file) => Log::read(file).map(Some),
+        Err(Error::NotFound(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// The locations of the logs.
+fn logs(jewel: &Emerald) -> Result<Vec<Path>> {
+    match read_dir(jewel, &Path::new(LOGS_DIR).expect("valid constant")) {
+        Ok(logs) => Ok(logs.flatten().map(|entry| entry.path().clone()).collect()),
+        Err(Error::NotFound(_)) => Ok(vec![]),
+        Err(err) => Err(err),
+    }
+}
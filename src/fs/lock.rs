@@ -0,0 +1,305 @@
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{
+    canonicalize_nofollow, create_dir_all, metadata_with, write, Intent, Metadata, MetadataOptions,
+};
+use crate::{
+    error::{Error, Result},
+    path::Path,
+    Emerald,
+};
+
+/// The lock files, named by the SHA-256 hash of the locked path.
+const LOCKS_DIR: &str = "/.emerald/locks";
+
+/// A lock file older than this is left by a crashed process, and is broken.
+const STALE_AFTER: Duration = Duration::from_secs(600);
+
+/// The interval between two touches of a held lock file, well below [STALE_AFTER].
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(150);
+
+/// The time [write_if_unchanged] waits for the lock of the shard.
+pub(super) const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The interval between two attempts of [lock].
+const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// An advisory lock on a path of the jewel, released when dropped.
+///
+/// The lock is a file of `/.emerald/locks`, so it is shared by every process
+/// working on the jewel. It is advisory: the writes are not prevented,
+/// the cooperating tools lock the paths they edit.
+///
+/// The file holds a token unique to its owner, and is touched while the lock is held
+/// so that it never looks stale. A lock broken by another process is not removed
+/// when dropped.
+pub struct Lock {
+    jewel: Emerald,
+    path: Path,
+    file: std::path::PathBuf,
+    content: Vec<u8>,
+    heartbeat: Option<(Sender<()>, JoinHandle<()>)>,
+}
+
+impl Lock {
+    /// The locked path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if let Some((stop, thread)) = self.heartbeat.take() {
+            drop(stop);
+            let _ = thread.join();
+        }
+
+        if owns(&self.jewel, &self.file, &self.content) {
+            let _ = self.jewel.storage().remove_file(&self.file);
+        }
+    }
+}
+
+impl std::fmt::Debug for Lock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Lock").field(&self.path).finish()
+    }
+}
+
+/// Returns true if the lock file still holds the content written by its owner.
+fn owns(jewel: &Emerald, file: &std::path::Path, content: &[u8]) -> bool {
+    jewel
+        .storage()
+        .read(file)
+        .is_ok_and(|current| current == content)
+}
+
+/// Returns true if the file was not modified since [STALE_AFTER].
+fn is_stale(jewel: &Emerald, file: &std::path::Path) -> bool {
+    jewel
+        .storage()
+        .metadata(file)
+        .ok()
+        .and_then(|meta| meta.modified)
+        .and_then(|modified| (Utc::now() - modified).to_std().ok())
+        .is_some_and(|age| age > STALE_AFTER)
+}
+
+/// A token identifying the owner of a lock, unique among the processes of the host.
+fn token() -> String {
+    static COUNT: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+
+    format!(
+        "{}-{}-{}",
+        std::process::id(),
+        nanos,
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Breaks the stale lock file, returns false if it is held again.
+///
+/// The file is first renamed to a tombstone, so that a single process breaks it.
+/// If a lock was taken in the meantime, the tombstone is fresh and is put back.
+fn break_stale(jewel: &Emerald, file: &std::path::Path, token: &str) -> bool {
+    let mut name = file.as_os_str().to_owned();
+    name.push(format!(".{}.stale", token));
+    let tombstone = std::path::PathBuf::from(name);
+
+    if jewel.storage().rename(file, &tombstone).is_err() {
+        // Another process broke it first.
+        return true;
+    }
+
+    if !is_stale(jewel, &tombstone) {
+        if let Ok(content) = jewel.storage().read(&tombstone) {
+            if let Ok(mut writer) = jewel.storage().create_new(file) {
+                let _ = writer.write_all(&content);
+            }
+        }
+
+        let _ = jewel.storage().remove_file(&tombstone);
+        return false;
+    }
+
+    let _ = jewel.storage().remove_file(&tombstone);
+    true
+}
+
+/// Touches the lock file until the sender is dropped, while it is still owned.
+fn heartbeat(
+    jewel: Emerald,
+    file: std::path::PathBuf,
+    content: Vec<u8>,
+) -> (Sender<()>, JoinHandle<()>) {
+    let (stop, stopped) = mpsc::channel::<()>();
+
+    let thread = std::thread::spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(HEARTBEAT_INTERVAL) {
+            if !owns(&jewel, &file, &content) || jewel.storage().write(&file, &content).is_err() {
+                return;
+            }
+        }
+    });
+
+    (stop, thread)
+}
+
+/// Acquires the lock of the path, fails with [Error::Locked] if it is already held.
+pub fn try_lock(jewel: &Emerald, path: &Path) -> Result<Lock> {
+    jewel.access().check_read(path)?;
+
+    // The lock files are bookkeeping, written for any handle allowed to read the path.
+    let jewel = jewel.unrestricted();
+    let dir = Path::new(LOCKS_DIR).expect("valid constant");
+    let name = format!("{:x}.lock", Sha256::digest(path.as_str()));
+    let location = dir
+        .join(&name)
+        .ok_or_else(|| Error::InvalidPath(name.clone()))?;
+
+    create_dir_all(&jewel, &dir)?;
+    let file = canonicalize_nofollow(&jewel, &location, Intent::Write)?;
+    let token = token();
+    let content = format!("{}\n{}\n", path, token).into_bytes();
+
+    for attempt in 0..2 {
+        match jewel.storage().create_new(&file) {
+            Ok(mut writer) => {
+                let written = writer.write_all(&content).and_then(|_| writer.flush());
+                drop(writer);
+
+                if let Err(err) = written {
+                    let _ = jewel.storage().remove_file(&file);
+                    return Err(Error::from_io(err, &location));
+                }
+
+                let heartbeat = heartbeat(jewel.clone(), file.clone(), content.clone());
+                return Ok(Lock {
+                    jewel,
+                    path: path.clone(),
+                    file,
+                    content,
+                    heartbeat: Some(heartbeat),
+                });
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists && attempt == 0 => {
+                if !is_stale(&jewel, &file) || !break_stale(&jewel, &file, &token) {
+                    break;
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => break,
+            Err(err) => return Err(Error::from_io(err, &location)),
+        }
+    }
+
+    Err(Error::Locked(path.clone()))
+}
+
+/// Acquires the lock of the path, waiting for it to be released up to the timeout.
+pub fn lock(jewel: &Emerald, path: &Path, timeout: Duration) -> Result<Lock> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        match try_lock(jewel, path) {
+            Err(Error::Locked(_)) if Instant::now() < deadline => {
+                std::thread::sleep(RETRY_INTERVAL)
+            }
+            result => return result,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+/// The state of a file when it was read, checked by [write_if_unchanged].
+pub enum Revision {
+    /// The file does not exist.
+    Absent,
+    /// The SHA-256 hash of the content, see [MetadataOptions::hash].
+    Hash(String),
+    /// The last modification time.
+    Modified(DateTime<Utc>),
+}
+
+impl Revision {
+    /// The revision of the file: the hash of its content if computed,
+    /// or else its last modification time.
+    pub fn of(metadata: &Metadata) -> Option<Self> {
+        match metadata.hash() {
+            Some(hash) => Some(Revision::Hash(hash.to_string())),
+            None => metadata.modified().map(Revision::Modified),
+        }
+    }
+}
+
+/// Writes the contents of the file, if it is still at the expected revision.
+///
+/// Fails with [Error::Conflict] if the file changed since it was read,
+/// so that a concurrent edit is never overwritten. The check and the write are done
+/// under the lock of the path, and the write is atomic.
+///
+/// ```
+/// use emerald::{fs::{MetadataOptions, Revision}, path::Path, storage::MemoryStorage, Emerald, Error};
+///
+/// let jewel = Emerald::from_storage(MemoryStorage::from_iter([("/index.md", "# Index")]));
+/// let index = Path::new("/index.md").unwrap();
+///
+/// let options = MetadataOptions { hash: true, title: false };
+/// let meta = emerald::fs::metadata_with(&jewel, &index, options).unwrap();
+/// let revision = Revision::of(&meta).unwrap();
+///
+/// emerald::fs::write_if_unchanged(&jewel, &index, &revision, "# Home").unwrap();
+/// assert!(matches!(
+///     emerald::fs::write_if_unchanged(&jewel, &index, &revision, "# Other"),
+///     Err(Error::Conflict(_))
+/// ));
+/// ```
+pub fn write_if_unchanged<C: AsRef<[u8]>>(
+    jewel: &Emerald,
+    path: &Path,
+    expected: &Revision,
+    contents: C,
+) -> Result<()> {
+    let _lock = lock(jewel, path, LOCK_TIMEOUT)?;
+
+    let options = MetadataOptions {
+        hash: matches!(expected, Revision::Hash(_)),
+        title: false,
+    };
+
+    let current = match metadata_with(jewel, path, options) {
+        Ok(meta) => Some(meta),
+        Err(Error::NotFound(_)) => None,
+        Err(err) => return Err(err),
+    };
+
+    let unchanged = match (expected, &current) {
+        (Revision::Absent, None) => true,
+        (Revision::Hash(hash), Some(meta)) => meta.hash() == Some(hash.as_str()),
+        (Revision::Modified(time), Some(meta)) => meta.modified() == Some(*time),
+        _ => false,
+    };
+
+    if !unchanged {
+        return Err(Error::Conflict(path.clone()));
+    }
+
+    write(jewel, path, contents)
+}
@@ -0,0 +1,988 @@
+mod access;
+#[cfg(feature = "async")]
+pub mod aio;
+mod archive;
+mod glob;
+mod history;
+mod lock;
+mod mount;
+#[cfg(feature = "parallel")]
+mod par;
+mod relink;
+mod tags;
+mod trash;
+mod walk;
+mod watch;
+mod wikilink;
+
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{Error, Result},
+    path::Path,
+    shard::Shard,
+    Emerald,
+};
+
+pub use access::Access;
+pub use archive::{
+    export_archive, import_archive, ConflictPolicy, ExportOptions, ExportReport, ImportOptions,
+    ImportReport,
+};
+pub use glob::{glob, Glob};
+pub use history::{
+    diff_versions, history, prune_history, prune_history_with, read_version, read_version_bytes,
+    restore_version, DiffLine, Retention, Version,
+};
+pub use lock::{lock, try_lock, write_if_unchanged, Lock, Revision};
+#[cfg(feature = "parallel")]
+pub use par::{load_all, par_walk, LoadedShard};
+pub use relink::{move_shard, move_shard_with, LinkEdit, MoveOptions};
+pub use tags::{tags, tags_with, TagSummary};
+pub use trash::{empty_trash, list_trash, restore, trash, TrashEntry};
+pub use walk::{walk, SortOrder, Walk, WalkBuilder};
+pub use watch::{watch, Event, WatchBuilder, Watcher};
+pub use wikilink::resolve_wikilink;
+
+/// The maximum number of symbolic links followed while resolving a path.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// The magic header of a symbolic link file.
+const SYMLINK_MAGIC: &str = "@/>";
+
+/// A symlink information
+///
+/// A symbolic link is an extension-less file starting with `@/>`, followed by its target:
+/// - `@/>/projects/alpha` targets a path from the jewel's root,
+/// - `@/>../alpha` targets a path relative to the link's directory,
+/// - `@/>/home/user/notes` targets a path on the storage, outside of the jewel.
+///
+/// Absolute targets are first resolved in the jewel, then on the storage. A link to the storage
+/// is only followed by unrestricted handles, and written through by the handles derived
+/// with [crate::Emerald::allow_storage_writes].
+pub struct Symlink {
+    pub name: String,
+    target: String,
+}
+
+/// The target of a symbolic link, and its canonical path if it exists.
+type Resolved = (SymlinkTarget, Result<PathBuf>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Why a path is resolved: the targets of the links followed are checked accordingly.
+pub(crate) enum Intent {
+    Read,
+    Write,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+/// The resolved target of a symbolic link.
+pub enum SymlinkTarget {
+    /// A path within the jewel.
+    Jewel(Path),
+    /// A canonical path on the storage, outside of the jewel.
+    Storage(PathBuf),
+}
+
+impl Symlink {
+    /// Read the symbolic link file from the canonical path.
+    fn load_from_canon(jewel: &Emerald, path: &std::path::Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_string();
+        let stream = String::from_utf8(jewel.storage().read(path).ok()?).ok()?;
+
+        if stream.starts_with(SYMLINK_MAGIC) {
+            let (_, target) = stream.split_once(SYMLINK_MAGIC)?;
+
+            return Some(Symlink {
+                name,
+                target: target.trim().to_string(),
+            });
+        }
+
+        None
+    }
+
+    /// Read the symbolic link file located at the jewel path.
+    fn load(jewel: &Emerald, link: &Path) -> Result<Self> {
+        jewel.access().check_read(link)?;
+        let canon = canonicalize_nofollow(jewel, link, Intent::Read)?;
+
+        if !jewel.storage().exists(&canon) {
+            return Err(Error::NotFound(link.clone()));
+        }
+
+        Self::load_from_canon(jewel, &canon).ok_or_else(|| Error::NotASymlink(link.clone()))
+    }
+
+    /// The target, as written in the link file.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Resolve the target of the link located at the jewel path,
+    /// and canonicalize it if it exists.
+    fn resolve(
+        &self,
+        jewel: &Emerald,
+        link: &Path,
+        hops: usize,
+        intent: Intent,
+    ) -> Result<Resolved> {
+        let storage = PathBuf::from(&self.target);
+        let relative = !self.target.starts_with('/');
+        let within = relative || storage.starts_with(jewel.get_root());
+
+        let path = if relative {
+            link.parent().and_then(|parent| parent.join(&self.target))
+        } else if let Ok(inner) = storage.strip_prefix(jewel.get_root()) {
+            inner.to_str().and_then(Path::new)
+        } else {
+            Path::new(&self.target)
+        };
+
+        // A restricted handle cannot follow a link out of its restrictions,
+        // nor write through a link to a path it cannot write.
+        let canon = match &path {
+            Some(path) => match intent {
+                Intent::Read => jewel.access().check_read(path),
+                Intent::Write => jewel.access().check_write(path),
+            }
+            .and_then(|()| resolve(jewel, path, hops + 1, intent)),
+            None => Err(Error::InvalidPath(self.target.clone())),
+        };
+
+        if canon.is_ok() || within {
+            let path = path.ok_or_else(|| Error::InvalidPath(self.target.clone()))?;
+            return Ok((SymlinkTarget::Jewel(path), canon));
+        }
+
+        if jewel.storage().exists(&storage) {
+            let canon = jewel
+                .access()
+                .check_storage(link, intent)
+                .map(|()| storage.clone());
+            return Ok((SymlinkTarget::Storage(storage), canon));
+        }
+
+        // The link is dangling.
+        Ok((
+            path.map_or(SymlinkTarget::Storage(storage), SymlinkTarget::Jewel),
+            canon,
+        ))
+    }
+}
+
+/// Creates a new symbolic link at the jewel path.
+///
+/// The target is either a path from the jewel's root (`/projects/alpha`),
+/// or relative to the link's directory (`../alpha`).
+/// Similar to [https://doc.rust-lang.org/std/os/unix/fs/fn.symlink.html]
+pub fn symlink(jewel: &Emerald, link: &Path, target: &str) -> Result<()> {
+    if link.extension().is_some() {
+        return Err(Error::InvalidInput(format!(
+            "a symlink cannot have an extension: {}",
+            link
+        )));
+    }
+
+    let target = target.trim();
+    let valid = if target.starts_with('/') {
+        Path::new(target).is_some()
+    } else {
+        link.parent()
+            .and_then(|parent| parent.join(target))
+            .is_some()
+    };
+
+    if !valid {
+        return Err(Error::InvalidPath(target.to_string()));
+    }
+
+    let canon = canonicalize_mut(jewel, link)?;
+
+    if jewel.storage().exists(&canon) {
+        return Err(Error::AlreadyExists(link.clone()));
+    }
+
+    jewel
+        .storage()
+        .write(&canon, format!("{}{}", SYMLINK_MAGIC, target).as_bytes())
+        .map_err(|err| Error::from_io(err, link))?;
+
+    Ok(())
+}
+
+/// Reads the symbolic link, and returns its resolved target.
+/// Similar to [https://doc.rust-lang.org/std/fs/fn.read_link.html]
+pub fn read_link(jewel: &Emerald, link: &Path) -> Result<SymlinkTarget> {
+    Ok(Symlink::load(jewel, link)?
+        .resolve(jewel, link, 0, Intent::Read)?
+        .0)
+}
+
+/// Returns true if the symbolic link targets a path which does not exist.
+pub fn is_dangling(jewel: &Emerald, link: &Path) -> Result<bool> {
+    Ok(Symlink::load(jewel, link)?
+        .resolve(jewel, link, 0, Intent::Read)?
+        .1
+        .is_err())
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+/// The optional, and costly, metadata to compute.
+///
+/// Both options read the whole file.
+pub struct MetadataOptions {
+    /// Compute the SHA-256 hash of the file content.
+    pub hash: bool,
+    /// Parse the shard to extract its title.
+    pub title: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Metadata information about a file.
+/// Similar to [https://doc.rust-lang.org/std/fs/struct.Metadata.html]
+pub struct Metadata {
+    is_symlink: bool,
+    is_shard: bool,
+    is_file: bool,
+    is_dir: bool,
+    len: u64,
+    created: Option<DateTime<Utc>>,
+    modified: Option<DateTime<Utc>>,
+    accessed: Option<DateTime<Utc>>,
+    symlink_target: Option<SymlinkTarget>,
+    hash: Option<String>,
+    title: Option<String>,
+}
+
+impl Metadata {
+    pub fn is_symlink(&self) -> bool {
+        self.is_symlink
+    }
+
+    pub fn is_shard(&self) -> bool {
+        self.is_shard
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.is_file
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// The size of the file, in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The creation time, if supported by the storage.
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        self.created
+    }
+
+    /// The last modification time, if supported by the storage.
+    pub fn modified(&self) -> Option<DateTime<Utc>> {
+        self.modified
+    }
+
+    /// The last access time, if supported by the storage.
+    pub fn accessed(&self) -> Option<DateTime<Utc>> {
+        self.accessed
+    }
+
+    /// The resolved target, if the file is a symbolic link.
+    pub fn symlink_target(&self) -> Option<&SymlinkTarget> {
+        self.symlink_target.as_ref()
+    }
+
+    /// The SHA-256 hash of the file content, in hexadecimal.
+    ///
+    /// Only computed for files, with [MetadataOptions::hash].
+    pub fn hash(&self) -> Option<&str> {
+        self.hash.as_deref()
+    }
+
+    /// The title of the shard, see [Shard::title].
+    ///
+    /// Only computed for shards, with [MetadataOptions::title].
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Classify the file located at the canonical path.
+    fn from_canon(
+        jewel: &Emerald,
+        path: &Path,
+        canon: &std::path::Path,
+        options: MetadataOptions,
+    ) -> Result<Self> {
+        let raw = jewel
+            .storage()
+            .metadata(canon)
+            .map_err(|err| Error::from_io(err, path))?;
+
+        let mut meta = Metadata {
+            is_dir: raw.is_dir,
+            is_file: raw.is_file,
+            is_shard: false,
+            is_symlink: false,
+            len: raw.len,
+            created: raw.created,
+            modified: raw.modified,
+            accessed: raw.accessed,
+            symlink_target: None,
+            hash: None,
+            title: None,
+        };
+
+        if raw.is_file {
+            // A shard
+            if let Some(ext) = canon.extension() {
+                meta.is_shard = ext == "md";
+            } else if let Some(link) = Symlink::load_from_canon(jewel, canon) {
+                meta.is_file = false;
+                meta.is_symlink = true;
+                meta.symlink_target = link
+                    .resolve(jewel, path, 0, Intent::Read)
+                    .ok()
+                    .map(|(target, _)| target);
+            }
+        }
+
+        if raw.is_file && (options.hash || (options.title && meta.is_shard)) {
+            let content = jewel
+                .storage()
+                .read(canon)
+                .map_err(|err| Error::from_io(err, path))?;
+
+            if options.hash {
+                meta.hash = Some(format!("{:x}", Sha256::digest(&content)));
+            }
+
+            if options.title && meta.is_shard {
+                let content = String::from_utf8(content)
+                    .map_err(|err| Error::InvalidInput(format!("{}: {}", path, err)))?;
+                meta.title = Shard::parse(&content, &jewel.config().markdown)?.title();
+            }
+        }
+
+        Ok(meta)
+    }
+}
+
+/// Returns the metadata of the file located at the path, without following the symbolic link.
+/// Similar to [https://doc.rust-lang.org/std/fs/fn.symlink_metadata.html]
+pub fn metadata(jewel: &Emerald, path: &Path) -> Result<Metadata> {
+    metadata_with(jewel, path, MetadataOptions::default())
+}
+
+/// Returns the metadata of the file located at the path, with the optional metadata.
+pub fn metadata_with(jewel: &Emerald, path: &Path, options: MetadataOptions) -> Result<Metadata> {
+    jewel.access().check_read(path)?;
+    Metadata::from_canon(
+        jewel,
+        path,
+        &canonicalize_nofollow(jewel, path, Intent::Read)?,
+        options,
+    )
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Entries returned by the ReadDir iterator.
+/// Similar to [https://doc.rust-lang.org/std/fs/struct.DirEntry.html]
+pub struct DirEntry {
+    path: Path,
+    metadata: Metadata,
+    mount: Option<Path>,
+}
+
+impl DirEntry {
+    fn new(path: Path, metadata: Metadata, mount: Option<Path>) -> Self {
+        Self {
+            path,
+            metadata,
+            mount,
+        }
+    }
+
+    fn from_canon(
+        jewel: &Emerald,
+        path: &Path,
+        canon: &std::path::Path,
+        options: MetadataOptions,
+    ) -> Result<Self> {
+        let meta = Metadata::from_canon(jewel, path, canon, options)?;
+        let mount = mount::mount_of(jewel, path).map(|mount| mount.path.clone());
+        Ok(Self::new(path.clone(), meta, mount))
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The mount point of the mount the entry comes from, None if it is stored in the jewel.
+    pub fn mount(&self) -> Option<&Path> {
+        self.mount.as_ref()
+    }
+}
+
+/// Iterator over the entries in a directory.
+/// Similar to [https://doc.rust-lang.org/std/fs/struct.ReadDir.html]
+pub struct ReadDir {
+    jewel: Emerald,
+    path: Path,
+    canon: PathBuf,
+    names: std::vec::IntoIter<String>,
+    options: MetadataOptions,
+}
+
+impl ReadDir {
+    fn new(jewel: &Emerald, path: &Path, canon: PathBuf) -> Result<Self> {
+        let mut names = jewel
+            .storage()
+            .read_dir(&canon)
+            .map_err(|err| Error::from_io(err, path))?;
+
+        for name in mount::mounted_names(jewel, path) {
+            if !names.iter().any(|existing| existing == name) {
+                names.push(name.to_string());
+            }
+        }
+
+        Ok(Self {
+            jewel: jewel.clone(),
+            path: path.clone(),
+            canon,
+            names: names.into_iter(),
+            options: MetadataOptions::default(),
+        })
+    }
+
+    /// Compute the optional metadata of the entries.
+    pub fn metadata(mut self, options: MetadataOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl Iterator for ReadDir {
+    type Item = Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let name = self.names.next()?;
+            let mut path = self.path.clone();
+
+            if !path.append(&name) {
+                return Some(Err(Error::InvalidPath(name)));
+            }
+
+            // The trashed entries, and the entries denied to the handle, are hidden.
+            if path.as_str() == trash::TRASH_DIR || !self.jewel.access().can_read(&path) {
+                continue;
+            }
+
+            let canon = match mount::mount_point(&self.jewel, &path) {
+                Some(mount) => mount.canon(self.jewel.get_root()),
+                None => self.canon.join(name),
+            };
+
+            return Some(DirEntry::from_canon(
+                &self.jewel,
+                &path,
+                &canon,
+                self.options,
+            ));
+        }
+    }
+}
+
+/// Returns an iterator over the entries within a directory.
+/// Similar to [https://doc.rust-lang.org/std/fs/fn.read_dir.html]
+pub fn read_dir(jewel: &Emerald, path: &Path) -> Result<ReadDir> {
+    let canon: PathBuf = canonicalize(jewel, path)?;
+
+    let meta = jewel
+        .storage()
+        .metadata(&canon)
+        .map_err(|err| Error::from_io(err, path))?;
+
+    // Symbolic links are already followed by canonicalize.
+    if meta.is_dir {
+        return ReadDir::new(jewel, path, canon);
+    }
+
+    Err(Error::NotADirectory(path.clone()))
+}
+
+/// Open a file from the Emerald
+pub fn open(emerald: &Emerald, path: &Path) -> Result<File> {
+    File::open(emerald, path)
+}
+
+/// Returns the canonical, absolute form of a path with all intermediate components normalized and symbolic links resolved.
+pub fn canonicalize(jewel: &Emerald, path: &Path) -> Result<PathBuf> {
+    jewel.access().check_read(path)?;
+    resolve(jewel, path, 0, Intent::Read)
+}
+
+/// Canonicalize the path, counting the symbolic links followed so far.
+fn resolve(jewel: &Emerald, path: &Path, hops: usize, intent: Intent) -> Result<PathBuf> {
+    let mut canon = jewel.get_root().to_owned();
+    let mut current = Path::root();
+
+    for part in path.parts() {
+        canon.push(part);
+        current.append(part);
+
+        if let Some(mount) = mount::mount_point(jewel, &current) {
+            canon = mount.canon(jewel.get_root());
+        }
+
+        canon = follow(jewel, &current, canon, hops, intent)?;
+    }
+
+    Ok(canon)
+}
+
+/// Returns the canonical form of a path, without resolving the leaf if it is a symbolic link.
+///
+/// The leaf may not exist.
+fn canonicalize_nofollow(jewel: &Emerald, path: &Path, intent: Intent) -> Result<PathBuf> {
+    if let Some(mount) = mount::mount_point(jewel, path) {
+        return Ok(mount.canon(jewel.get_root()));
+    }
+
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => Ok(resolve(jewel, &parent, 0, intent)?.join(name)),
+        _ => Ok(jewel.get_root().to_owned()),
+    }
+}
+
+/// Returns the canonical form of a path to write into.
+///
+/// The leaf may not exist, but if it is a symbolic link, it is resolved.
+fn canonicalize_for_write(jewel: &Emerald, path: &Path) -> Result<PathBuf> {
+    jewel.access().check_write(path)?;
+    let mut canon = canonicalize_nofollow(jewel, path, Intent::Write)?;
+
+    if jewel.storage().exists(&canon) {
+        canon = follow(jewel, path, canon, 0, Intent::Write)?;
+    }
+
+    mount::check_writable(jewel, path, &canon)?;
+    Ok(canon)
+}
+
+/// Returns the canonical form of a path to modify, without resolving the leaf.
+///
+/// A mount point cannot be modified, nor any path within a read-only mount.
+fn canonicalize_mut(jewel: &Emerald, path: &Path) -> Result<PathBuf> {
+    jewel.access().check_write(path)?;
+
+    if mount::mount_point(jewel, path).is_some() {
+        return Err(Error::InvalidInput(format!("{} is a mount point", path)));
+    }
+
+    let canon = canonicalize_nofollow(jewel, path, Intent::Write)?;
+    mount::check_writable(jewel, path, &canon)?;
+    Ok(canon)
+}
+
+/// Replace the canonical path by the target of the symbolic link, if it is one.
+fn follow(
+    jewel: &Emerald,
+    path: &Path,
+    canon: PathBuf,
+    hops: usize,
+    intent: Intent,
+) -> Result<PathBuf> {
+    let meta = jewel
+        .storage()
+        .metadata(&canon)
+        .map_err(|err| Error::from_io(err, path))?;
+
+    if meta.is_file {
+        if let Some(lnk) = Symlink::load_from_canon(jewel, &canon) {
+            if hops >= MAX_SYMLINK_HOPS {
+                return Err(Error::SymlinkLoop(path.clone()));
+            }
+
+            return lnk
+                .resolve(jewel, path, hops, intent)?
+                .1
+                .map_err(|err| match err {
+                    Error::NotFound(_) => Error::BrokenSymlink {
+                        link: path.clone(),
+                        target: lnk.target,
+                    },
+                    err => err,
+                });
+        }
+    }
+
+    Ok(canon)
+}
+
+/// Opens a file in write-only mode, creates it if it does not exist, and truncates it if it does.
+/// Similar to [https://doc.rust-lang.org/std/fs/struct.File.html#method.create]
+pub fn create(jewel: &Emerald, path: &Path) -> Result<File> {
+    File::create(jewel, path)
+}
+
+/// Writes a slice as the entire contents of a file.
+///
+/// The write is atomic: the contents are written to a temporary file next to the destination,
+/// which is then renamed over it.
+/// Similar to [https://doc.rust-lang.org/std/fs/fn.write.html]
+pub fn write<C: AsRef<[u8]>>(jewel: &Emerald, path: &Path, contents: C) -> Result<()> {
+    let canon = canonicalize_for_write(jewel, path)?;
+    write_atomic(jewel, path, &canon, contents.as_ref())?;
+
+    // The history is best effort, the write itself is not undone.
+    let _ = history::record(jewel, path, contents.as_ref());
+    Ok(())
+}
+
+/// The temporary file, next to the destination, where its new contents are written.
+///
+/// The temporary file is hidden, and unique among the processes and the threads.
+fn temporary(canon: &std::path::Path) -> PathBuf {
+    static COUNT: AtomicU64 = AtomicU64::new(0);
+
+    let name = canon
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    canon.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Replace the destination by the temporary file, which is removed on failure.
+///
+/// The temporary file is flushed to the device and given the permissions of the destination
+/// before the rename, and the directory is flushed after it.
+fn replace(
+    jewel: &Emerald,
+    path: &Path,
+    temp: &std::path::Path,
+    canon: &std::path::Path,
+) -> Result<()> {
+    let storage = jewel.storage();
+
+    let prepared = storage
+        .sync(temp)
+        .and_then(|_| match storage.copy_permissions(canon, temp) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        });
+
+    prepared
+        .and_then(|_| storage.rename(temp, canon))
+        .map_err(|err| {
+            let _ = storage.remove_file(temp);
+            Error::from_io(err, path)
+        })?;
+
+    // The content is already replaced, a directory that cannot be flushed is not an error.
+    if let Some(parent) = canon.parent() {
+        let _ = storage.sync(parent);
+    }
+
+    Ok(())
+}
+
+/// Write the contents to a temporary file, then rename it over the destination.
+fn write_atomic(
+    jewel: &Emerald,
+    path: &Path,
+    canon: &std::path::Path,
+    contents: &[u8],
+) -> Result<()> {
+    let temp = temporary(canon);
+
+    jewel.storage().write(&temp, contents).map_err(|err| {
+        let _ = jewel.storage().remove_file(&temp);
+        Error::from_io(err, path)
+    })?;
+
+    replace(jewel, path, &temp, canon)
+}
+
+/// Recursively creates a directory and all of its parent components if they are missing.
+/// Similar to [https://doc.rust-lang.org/std/fs/fn.create_dir_all.html]
+pub fn create_dir_all(jewel: &Emerald, path: &Path) -> Result<()> {
+    jewel.access().check_write(path)?;
+
+    let mut canon = jewel.get_root().to_owned();
+    let mut current = Path::root();
+
+    for part in path.parts() {
+        canon.push(part);
+        current.append(part);
+
+        if let Some(mount) = mount::mount_point(jewel, &current) {
+            canon = mount.canon(jewel.get_root());
+        }
+
+        if jewel.storage().exists(&canon) {
+            canon = follow(jewel, &current, canon, 0, Intent::Write)?;
+        } else {
+            mount::check_writable(jewel, &current, &canon)?;
+            jewel
+                .storage()
+                .create_dir(&canon)
+                .map_err(|err| Error::from_io(err, &current))?;
+        }
+    }
+
+    let meta = jewel
+        .storage()
+        .metadata(&canon)
+        .map_err(|err| Error::from_io(err, path))?;
+
+    if !meta.is_dir {
+        return Err(Error::NotADirectory(path.clone()));
+    }
+
+    Ok(())
+}
+
+/// Renames a file or directory, a symbolic link is renamed, not its target.
+///
+/// The history of the shards follows them to their new location, see [history].
+/// Similar to [https://doc.rust-lang.org/std/fs/fn.rename.html]
+pub fn rename(jewel: &Emerald, from: &Path, to: &Path) -> Result<()> {
+    let from_canon = canonicalize_mut(jewel, from)?;
+    let to_canon = canonicalize_mut(jewel, to)?;
+
+    if !jewel.storage().exists(&from_canon) {
+        return Err(Error::NotFound(from.clone()));
+    }
+
+    jewel
+        .storage()
+        .rename(&from_canon, &to_canon)
+        .map_err(|err| Error::from_io(err, to))?;
+
+    // The history is best effort, the rename itself is not undone.
+    let _ = history::rename(jewel, from, to);
+    Ok(())
+}
+
+/// Copies the contents of one file to another, returns the number of bytes copied.
+/// Similar to [https://doc.rust-lang.org/std/fs/fn.copy.html]
+pub fn copy(jewel: &Emerald, from: &Path, to: &Path) -> Result<u64> {
+    let from_canon = canonicalize(jewel, from)?;
+    let to_canon = canonicalize_for_write(jewel, to)?;
+    let contents = jewel
+        .storage()
+        .read(&from_canon)
+        .map_err(|err| Error::from_io(err, from))?;
+    write_atomic(jewel, to, &to_canon, &contents)?;
+
+    // The history is best effort, the copy itself is not undone.
+    let _ = history::record(jewel, to, &contents);
+    Ok(contents.len() as u64)
+}
+
+/// Removes a file, a symbolic link is removed, not its target.
+///
+/// The file is moved into the trash, from which it can be restored, see [trash].
+/// A file within the trash is removed permanently, see [remove_file_permanently].
+/// Similar to [https://doc.rust-lang.org/std/fs/fn.remove_file.html]
+pub fn remove_file(jewel: &Emerald, path: &Path) -> Result<()> {
+    if trash::is_trashed(path) {
+        return remove_file_permanently(jewel, path);
+    }
+
+    if metadata(jewel, path)?.is_dir() {
+        return Err(Error::IsADirectory(path.clone()));
+    }
+
+    trash(jewel, path).map(|_| ())
+}
+
+/// Removes a file permanently, a symbolic link is removed, not its target.
+pub fn remove_file_permanently(jewel: &Emerald, path: &Path) -> Result<()> {
+    jewel
+        .storage()
+        .remove_file(&canonicalize_mut(jewel, path)?)
+        .map_err(|err| Error::from_io(err, path))?;
+    Ok(())
+}
+
+/// Removes a directory after removing all its contents, symbolic links are not followed.
+///
+/// The directory is moved into the trash, from which it can be restored, see [trash].
+/// A directory within the trash is removed permanently, see [remove_dir_all_permanently].
+/// Similar to [https://doc.rust-lang.org/std/fs/fn.remove_dir_all.html]
+pub fn remove_dir_all(jewel: &Emerald, path: &Path) -> Result<()> {
+    if path.is_root() || trash::is_trashed(path) {
+        return remove_dir_all_permanently(jewel, path);
+    }
+
+    if !metadata(jewel, path)?.is_dir() {
+        return Err(Error::NotADirectory(path.clone()));
+    }
+
+    trash(jewel, path).map(|_| ())
+}
+
+/// Removes a directory permanently after removing all its contents,
+/// symbolic links are not followed.
+pub fn remove_dir_all_permanently(jewel: &Emerald, path: &Path) -> Result<()> {
+    if path.is_root() {
+        return Err(Error::InvalidInput(
+            "cannot remove the jewel's root".to_string(),
+        ));
+    }
+
+    jewel
+        .storage()
+        .remove_dir_all(&canonicalize_mut(jewel, path)?)
+        .map_err(|err| Error::from_io(err, path))?;
+    Ok(())
+}
+
+enum Stream {
+    Reader(Box<dyn Read + Send>),
+    Writer(Box<dyn Write + Send>, Option<Pending>),
+}
+
+/// Keeps a copy of the content written to a shard, to record it in the history.
+struct Pending {
+    jewel: Emerald,
+    path: Path,
+    /// The temporary file written into.
+    temp: PathBuf,
+    /// The destination, replaced by the temporary file.
+    canon: PathBuf,
+    content: Vec<u8>,
+}
+
+pub struct File(Stream);
+
+impl File {
+    pub fn open(emerald: &Emerald, path: &Path) -> Result<Self> {
+        let canon = canonicalize(emerald, path)?;
+        let reader = emerald
+            .storage()
+            .open(&canon)
+            .map_err(|err| Error::from_io(err, path))?;
+        Ok(Self(Stream::Reader(reader)))
+    }
+
+    /// Opens the file in write-only mode.
+    ///
+    /// The content is written to a temporary file, which replaces the file when it is closed,
+    /// or dropped. The content of a shard is then recorded in its history.
+    pub fn create(emerald: &Emerald, path: &Path) -> Result<Self> {
+        let canon = canonicalize_for_write(emerald, path)?;
+        let temp = temporary(&canon);
+        let writer = emerald
+            .storage()
+            .create(&temp)
+            .map_err(|err| Error::from_io(err, path))?;
+        let pending = Pending {
+            jewel: emerald.clone(),
+            path: path.clone(),
+            temp,
+            canon,
+            content: vec![],
+        };
+        Ok(Self(Stream::Writer(writer, Some(pending))))
+    }
+
+    /// Closes the file, and returns the error of the write, which is ignored when dropped.
+    pub fn close(mut self) -> Result<()> {
+        self.commit()
+    }
+
+    /// Replace the file by the written content.
+    fn commit(&mut self) -> Result<()> {
+        let Stream::Writer(writer, pending) = &mut self.0 else {
+            return Ok(());
+        };
+
+        let Some(pending) = pending.take() else {
+            return Ok(());
+        };
+
+        // The storage may only store the content once the writer is dropped.
+        let flushed = writer.flush();
+        drop(std::mem::replace(writer, Box::new(std::io::sink())));
+
+        if let Err(err) = flushed {
+            let _ = pending.jewel.storage().remove_file(&pending.temp);
+            return Err(Error::from_io(err, &pending.path));
+        }
+
+        replace(&pending.jewel, &pending.path, &pending.temp, &pending.canon)?;
+
+        // The history is best effort, the write itself is not undone.
+        let _ = history::record(&pending.jewel, &pending.path, &pending.content);
+        Ok(())
+    }
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match &mut self.0 {
+            Stream::Reader(reader) => reader.read(buf),
+            Stream::Writer(..) => Err(std::io::Error::other("file is not opened for reading")),
+        }
+    }
+}
+
+impl Write for File {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.0 {
+            Stream::Writer(writer, pending) => {
+                let written = writer.write(buf)?;
+
+                if let Some(pending) = pending {
+                    pending.content.extend_from_slice(&buf[..written]);
+                }
+
+                Ok(written)
+            }
+            Stream::Reader(_) => Err(std::io::Error::other("file is not opened for writing")),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.0 {
+            Stream::Writer(writer, _) => writer.flush(),
+            Stream::Reader(_) => Ok(()),
+        }
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        let _ = self.commit();
+    }
+}
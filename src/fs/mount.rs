@@ -0,0 +1,50 @@
+use crate::{
+    config::Mount,
+    error::{Error, Result},
+    path::Path,
+    Emerald,
+};
+
+/// The mounts of the jewel's configuration, a mount at the root is ignored.
+fn mounts(jewel: &Emerald) -> impl Iterator<Item = &Mount> {
+    jewel
+        .config()
+        .mounts
+        .iter()
+        .filter(|mount| !mount.path.is_root())
+}
+
+/// The mount whose mount point is the path.
+pub(super) fn mount_point<'a>(jewel: &'a Emerald, path: &Path) -> Option<&'a Mount> {
+    mounts(jewel).find(|mount| &mount.path == path)
+}
+
+/// The innermost mount containing the path.
+pub(super) fn mount_of<'a>(jewel: &'a Emerald, path: &Path) -> Option<&'a Mount> {
+    mounts(jewel)
+        .filter(|mount| path.starts_with(&mount.path))
+        .max_by_key(|mount| mount.path.parts().count())
+}
+
+/// The names of the mount points within the directory.
+pub(super) fn mounted_names<'a>(
+    jewel: &'a Emerald,
+    dir: &'a Path,
+) -> impl Iterator<Item = &'a str> {
+    mounts(jewel)
+        .filter(move |mount| mount.path.parent().as_ref() == Some(dir))
+        .filter_map(|mount| mount.path.file_name())
+}
+
+/// Fails if the path, or its canonical form, is within a read-only mount.
+pub(super) fn check_writable(jewel: &Emerald, path: &Path, canon: &std::path::Path) -> Result<()> {
+    let denied = mounts(jewel).filter(|mount| mount.read_only).any(|mount| {
+        path.starts_with(&mount.path) || canon.starts_with(mount.canon(jewel.get_root()))
+    });
+
+    if denied {
+        return Err(Error::PermissionDenied(path.clone()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Parallel walk and bulk parsing of the shards, on a bounded rayon thread pool.
+//!
+//! The results are in the order of the sequential walk, so they do not depend
+//! on the scheduling of the threads.
+//!
+//! ```
+//! use emerald::{fs::WalkBuilder, path::Path, storage::MemoryStorage, Emerald};
+//!
+//! let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+//!     ("/index.md", "# Index"),
+//!     ("/projects/alpha.md", "# Alpha"),
+//!     ("/projects/notes.txt", "notes"),
+//! ]));
+//!
+//! let shards = emerald::fs::load_all(WalkBuilder::new(&jewel, &Path::root()), 2).unwrap();
+//! let titles = shards
+//!     .into_iter()
+//!     .map(|entry| {
+//!         let (path, shard) = entry.unwrap();
+//!         (path.to_string(), shard.unwrap().title())
+//!     })
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(
+//!     titles,
+//!     vec![
+//!         ("/index.md".to_string(), Some("Index".to_string())),
+//!         ("/projects/alpha.md".to_string(), Some("Alpha".to_string())),
+//!     ]
+//! );
+//! ```
+use std::io::Read;
+
+use rayon::prelude::*;
+
+use super::{open, DirEntry, WalkBuilder};
+use crate::{
+    error::{Error, Result},
+    path::Path,
+    shard::Shard,
+    Emerald,
+};
+
+/// An entry loaded by [load_all]: the path and the parsed shard, or the error of the walk.
+pub type LoadedShard = Result<(Path, Result<Shard>)>;
+
+/// Run the operation on a pool of `threads` threads, 0 uses one thread per CPU.
+fn install<T, F>(threads: usize, operation: F) -> Result<T>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|err| Error::Io(std::io::Error::other(err)))?;
+
+    Ok(pool.install(operation))
+}
+
+/// Walk with the options of the builder, the directories are read in parallel
+/// on a pool of `threads` threads, 0 uses one thread per CPU.
+///
+/// The entries are in the order of [WalkBuilder::build]. A directory reachable through
+/// several symbolic links may be walked once per link.
+pub fn par_walk(builder: WalkBuilder, threads: usize) -> Result<Vec<Result<DirEntry>>> {
+    let walk = builder.build()?;
+    install(threads, move || walk.par_collect())
+}
+
+/// Walk with the options of the builder and parse the shards in parallel,
+/// with the markdown configuration of the jewel, see [par_walk].
+///
+/// A shard which cannot be read or parsed yields an error next to its path,
+/// an entry which cannot be walked yields an error, and the load goes on with the next entries.
+pub fn load_all(builder: WalkBuilder, threads: usize) -> Result<Vec<LoadedShard>> {
+    let jewel = builder.jewel().clone();
+    let walk = builder.build()?;
+
+    install(threads, move || {
+        walk.par_collect()
+            .into_par_iter()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .map_or(true, |entry| entry.metadata().is_shard())
+            })
+            .map(|entry| {
+                let entry = entry?;
+                let shard = read_shard(&jewel, entry.path());
+                Ok((entry.path().clone(), shard))
+            })
+            .collect()
+    })
+}
+
+fn read_shard(jewel: &Emerald, path: &Path) -> Result<Shard> {
+    let mut content = String::default();
+    open(jewel, path)?.read_to_string(&mut content)?;
+    Shard::parse(&content, &jewel.config().markdown)
+}
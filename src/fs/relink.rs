@@ -0,0 +1,377 @@
+use std::{io::Read, ops::Range};
+
+use serde::{Deserialize, Serialize};
+
+use super::{canonicalize_for_write, create_dir_all, metadata, open, rename, write, WalkBuilder};
+use crate::{
+    error::{Error, Result},
+    path::Path,
+    shard::{
+        ast::{traits::Node as _, NodeAttributes, NodeType, Position},
+        Shard,
+    },
+    Emerald,
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+/// The options of [move_shard_with].
+pub struct MoveOptions {
+    /// Compute the edits, without moving the shard nor rewriting any link.
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug)]
+/// A link destination rewritten by [move_shard].
+pub struct LinkEdit {
+    /// The shard containing the link, at its location after the move.
+    pub shard: Path,
+    /// The type of the node holding the destination: a link, an image or a definition.
+    pub kind: NodeType,
+    /// The position of the node in the shard, before the edit.
+    pub position: Position,
+    /// The destination, as written in the shard.
+    pub old: String,
+    /// The rewritten destination.
+    pub new: String,
+    /// The byte range of the destination in the shard, before the edit.
+    range: Range<usize>,
+}
+
+impl std::fmt::Display for LinkEdit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {} -> {}",
+            self.shard, self.position.start.line, self.position.start.column, self.old, self.new
+        )
+    }
+}
+
+/// Moves the shard, and rewrites the links targeting it across the jewel.
+///
+/// Returns the edits made to the links, see [move_shard_with].
+pub fn move_shard(jewel: &Emerald, from: &Path, to: &Path) -> Result<Vec<LinkEdit>> {
+    move_shard_with(jewel, from, to, MoveOptions::default())
+}
+
+/// Moves the shard, and rewrites the links targeting it across the jewel.
+///
+/// The destinations of the links, images and definitions resolving to the old path
+/// are rewritten in every shard, keeping their style: a path from the jewel's root
+/// stays absolute, a relative path stays relative, the fragment and the query are kept.
+/// Reference links follow their definition. The relative links of the moved shard
+/// are rebased on its new location. The rest of each document is left untouched.
+///
+/// Shards which cannot be parsed, and hidden or ignored shards, are not rewritten.
+///
+/// The documents to rewrite are checked to be writable before the shard is moved.
+/// If one of them still fails to be written afterwards, the move is not undone and
+/// fails with [Error::PartialMove], listing the documents already rewritten.
+///
+/// ```
+/// use emerald::{path::Path, storage::MemoryStorage, Emerald};
+///
+/// let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+///     ("/people/alice.md", "# Alice"),
+///     ("/index.md", "See [Alice](people/alice.md#bio)."),
+/// ]));
+///
+/// let from = Path::new("/people/alice.md").unwrap();
+/// let to = Path::new("/contacts/alice.md").unwrap();
+/// let edits = emerald::fs::move_shard(&jewel, &from, &to).unwrap();
+///
+/// assert_eq!(edits.len(), 1);
+/// assert_eq!(edits[0].new, "contacts/alice.md#bio");
+/// ```
+pub fn move_shard_with(
+    jewel: &Emerald,
+    from: &Path,
+    to: &Path,
+    options: MoveOptions,
+) -> Result<Vec<LinkEdit>> {
+    if metadata(jewel, from)?.is_dir() {
+        return Err(Error::IsADirectory(from.clone()));
+    }
+
+    match metadata(jewel, to) {
+        Ok(_) => return Err(Error::AlreadyExists(to.clone())),
+        Err(Error::NotFound(_)) => {}
+        Err(err) => return Err(err),
+    }
+
+    let mut documents = Vec::<(Path, String, Vec<LinkEdit>)>::default();
+
+    let walk = WalkBuilder::new(jewel, &Path::root())
+        .follow_symlinks(false)
+        .build()?;
+
+    for entry in walk.flatten() {
+        if !entry.metadata().is_shard() {
+            continue;
+        }
+
+        let mut content = String::default();
+        let Ok(mut file) = open(jewel, entry.path()) else {
+            continue;
+        };
+        if file.read_to_string(&mut content).is_err() {
+            continue;
+        }
+
+        let Ok(edits) = relink(jewel, &content, entry.path(), from, to) else {
+            continue;
+        };
+
+        if !edits.is_empty() {
+            let location = if entry.path() == from {
+                to
+            } else {
+                entry.path()
+            };
+            documents.push((location.clone(), content, edits));
+        }
+    }
+
+    documents.sort_by(|a, b| a.0.cmp(&b.0));
+
+    if options.dry_run {
+        return Ok(documents
+            .into_iter()
+            .flat_map(|(_, _, edits)| edits)
+            .collect());
+    }
+
+    for (location, content, edits) in &mut documents {
+        // The moved shard is checked at its current location.
+        canonicalize_for_write(jewel, if location == to { from } else { location })?;
+
+        // Apply the edits from the end, so the ranges stay valid.
+        for edit in edits.iter().rev() {
+            content.replace_range(edit.range.clone(), &edit.new);
+        }
+    }
+
+    if let Some(parent) = to.parent() {
+        create_dir_all(jewel, &parent)?;
+    }
+    rename(jewel, from, to)?;
+
+    let mut all = Vec::default();
+    let mut rewritten = Vec::default();
+
+    for (location, content, edits) in documents {
+        if let Err(err) = write(jewel, &location, content) {
+            return Err(Error::PartialMove {
+                moved: to.clone(),
+                rewritten,
+                source: Box::new(err),
+            });
+        }
+
+        rewritten.push(location);
+        all.extend(edits);
+    }
+
+    Ok(all)
+}
+
+/// Compute the edits of the destinations of the shard located at the path.
+fn relink(
+    jewel: &Emerald,
+    content: &str,
+    shard: &Path,
+    from: &Path,
+    to: &Path,
+) -> Result<Vec<LinkEdit>> {
+    let parsed = Shard::parse(content, &jewel.config().markdown)?;
+    let location = if shard == from { to } else { shard };
+    let mut edits = Vec::default();
+
+    for node in parsed.walk_ref() {
+        let Some(position) = node.get_position() else {
+            continue;
+        };
+
+        let (url, start) = match node.get_attributes() {
+            NodeAttributes::Link(link) => {
+                // The destination follows the text of the link.
+                let start = node
+                    .iter_children_by_ast(&parsed.ast)
+                    .filter_map(|child| child.get_position().map(|pos| pos.end.offset))
+                    .max()
+                    .unwrap_or(position.start.offset);
+                (&link.url, find(content, start, position, "]("))
+            }
+            NodeAttributes::Image(image) => (
+                &image.url,
+                find(content, position.start.offset, position, "]("),
+            ),
+            NodeAttributes::Definition(definition) => (
+                &definition.url,
+                find(content, position.start.offset, position, "]:"),
+            ),
+            _ => continue,
+        };
+
+        let Some(range) = start.and_then(|start| destination(content, start)) else {
+            continue;
+        };
+
+        let raw = &content[range.clone()];
+        let (path, _) = split_suffix(url);
+        let Some(target) = resolve(shard, path) else {
+            continue;
+        };
+
+        let moved = if &target == from { to } else { &target };
+
+        // The destination still resolves from the shard's new location.
+        if resolve(location, path).as_ref() == Some(moved) {
+            continue;
+        }
+
+        let new_path = if path.starts_with('/') {
+            moved.to_string()
+        } else {
+            moved.relative_to(&location.parent().unwrap_or_default())
+        };
+
+        let bracketed = raw.starts_with('<');
+        let inner = raw.trim_start_matches('<').trim_end_matches('>');
+        let (_, suffix) = split_suffix(inner);
+        let encoded = percent_encode(&new_path, bracketed);
+
+        let new = if bracketed {
+            format!("<{}{}>", encoded, suffix)
+        } else {
+            format!("{}{}", encoded, suffix)
+        };
+
+        edits.push(LinkEdit {
+            shard: location.clone(),
+            kind: node.get_type(),
+            position: position.clone(),
+            old: raw.to_string(),
+            new,
+            range,
+        });
+    }
+
+    edits.sort_by_key(|edit| edit.range.start);
+    Ok(edits)
+}
+
+/// Find the offset following the marker, within the node.
+fn find(content: &str, start: usize, position: &Position, marker: &str) -> Option<usize> {
+    let span = content.get(start..position.end.offset)?;
+    span.find(marker).map(|index| start + index + marker.len())
+}
+
+/// Returns the byte range of the destination starting at the offset, after blanks.
+///
+/// The range includes the angle brackets of a `<destination>`.
+fn destination(content: &str, offset: usize) -> Option<Range<usize>> {
+    let rest = content.get(offset..)?;
+    let start = offset + (rest.len() - rest.trim_start().len());
+    let rest = &content[start..];
+
+    if rest.starts_with('<') {
+        let end = rest.find('>')?;
+        return Some(start..start + end + 1);
+    }
+
+    let mut depth = 0usize;
+    let mut escaped = false;
+
+    for (index, c) in rest.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(start..start + index),
+            ')' => depth -= 1,
+            c if c.is_whitespace() || c.is_control() => return Some(start..start + index),
+            _ => {}
+        }
+    }
+
+    Some(start..content.len())
+}
+
+/// Split the destination into its path, and its query or fragment.
+fn split_suffix(url: &str) -> (&str, &str) {
+    url.split_at(url.find(['#', '?']).unwrap_or(url.len()))
+}
+
+/// Resolve the path of a destination, relative to the shard.
+///
+/// Returns None for a fragment, an URL with a scheme, or a path escaping the jewel.
+fn resolve(shard: &Path, path: &str) -> Option<Path> {
+    if path.is_empty() || has_scheme(path) {
+        return None;
+    }
+
+    let path = percent_decode(path);
+
+    if path.starts_with('/') {
+        Path::new(&path)
+    } else {
+        shard.parent()?.join(&path)
+    }
+}
+
+/// Returns true if the destination starts with a scheme, as `https:` or `mailto:`.
+fn has_scheme(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Encode the characters which cannot appear in a destination.
+fn percent_encode(value: &str, bracketed: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '%' => encoded.push_str("%25"),
+            '<' => encoded.push_str("%3C"),
+            '>' => encoded.push_str("%3E"),
+            ' ' if !bracketed => encoded.push_str("%20"),
+            '(' if !bracketed => encoded.push_str("%28"),
+            ')' if !bracketed => encoded.push_str("%29"),
+            c => encoded.push(c),
+        }
+    }
+
+    encoded
+}
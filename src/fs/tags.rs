@@ -0,0 +1,106 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Read,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{open, WalkBuilder};
+use crate::{error::Result, path::Path, shard::Shard, Emerald};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+/// A tag of the jewel, and the shards carrying it.
+pub struct TagSummary {
+    /// The name of the tag, without the `#`.
+    pub name: String,
+    /// The shards carrying the tag itself, sorted.
+    pub shards: Vec<Path>,
+    /// The shards carrying the tag or one of its nested tags, sorted:
+    /// `#area` rolls up `#area/sub-area`.
+    pub rollup: Vec<Path>,
+}
+
+impl TagSummary {
+    /// The number of shards carrying the tag itself.
+    pub fn count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The number of shards carrying the tag or one of its nested tags.
+    pub fn total(&self) -> usize {
+        self.rollup.len()
+    }
+}
+
+/// Lists the tags of all the shards of the jewel, see [tags_with].
+pub fn tags(jewel: &Emerald) -> Result<Vec<TagSummary>> {
+    tags_with(WalkBuilder::new(jewel, &Path::root()))
+}
+
+/// Lists the tags of the shards walked by the builder, sorted by name, see [Shard::tags].
+///
+/// The parents of a nested tag are listed, even if no shard carries them directly.
+/// The shards which cannot be read or parsed are skipped.
+///
+/// ```
+/// use emerald::{storage::MemoryStorage, Emerald};
+///
+/// let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+///     ("/alpha.md", "#area/web and #todo"),
+///     ("/beta.md", "#area"),
+/// ]));
+///
+/// let tags = emerald::fs::tags(&jewel).unwrap();
+/// let names = tags.iter().map(|tag| tag.name.as_str()).collect::<Vec<_>>();
+/// assert_eq!(names, vec!["area", "area/web", "todo"]);
+/// assert_eq!((tags[0].count(), tags[0].total()), (1, 2));
+/// ```
+pub fn tags_with(builder: WalkBuilder) -> Result<Vec<TagSummary>> {
+    let jewel = builder.jewel().clone();
+    let mut index = BTreeMap::<String, (BTreeSet<Path>, BTreeSet<Path>)>::default();
+
+    for entry in builder.build()?.flatten() {
+        if !entry.metadata().is_shard() {
+            continue;
+        }
+
+        let mut content = String::default();
+        let Ok(mut file) = open(&jewel, entry.path()) else {
+            continue;
+        };
+        if file.read_to_string(&mut content).is_err() {
+            continue;
+        }
+
+        let Ok(shard) = Shard::parse(&content, &jewel.config().markdown) else {
+            continue;
+        };
+
+        for tag in shard.tags() {
+            let (shards, _) = index.entry(tag.clone()).or_default();
+            shards.insert(entry.path().clone());
+
+            // The tag and its parents roll up the shard.
+            let mut name = tag.as_str();
+
+            loop {
+                let (_, rollup) = index.entry(name.to_string()).or_default();
+                rollup.insert(entry.path().clone());
+
+                match name.rsplit_once('/') {
+                    Some((parent, _)) => name = parent,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    Ok(index
+        .into_iter()
+        .map(|(name, (shards, rollup))| TagSummary {
+            name,
+            shards: shards.into_iter().collect(),
+            rollup: rollup.into_iter().collect(),
+        })
+        .collect())
+}
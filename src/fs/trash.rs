@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{create_dir_all, metadata, read_dir, remove_dir_all_permanently, rename, write, File};
+use crate::{
+    error::{Error, Result},
+    path::Path,
+    Emerald,
+};
+
+/// The directory holding the trashed entries, hidden from the jewel's listings.
+pub(crate) const TRASH_DIR: &str = "/.emerald/trash";
+
+/// The record of a trashed entry, next to its content.
+const INFO_FILE: &str = "info.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+/// An entry of the trash.
+///
+/// Each entry is stored in its own directory, `/.emerald/trash/<id>`,
+/// holding the trashed file or directory and its record.
+pub struct TrashEntry {
+    /// The identifier of the entry in the trash.
+    pub id: String,
+    /// The original path of the entry.
+    pub path: Path,
+    /// The deletion time.
+    pub deleted: DateTime<Utc>,
+    /// The entry is a symbolic link, which was trashed instead of its target.
+    pub is_symlink: bool,
+    pub is_dir: bool,
+}
+
+impl TrashEntry {
+    /// The directory of the entry in the trash.
+    fn dir(id: &str) -> Result<Path> {
+        if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\']) {
+            return Err(Error::InvalidInput(format!("invalid trash entry: {}", id)));
+        }
+
+        trash_dir()
+            .join(id)
+            .ok_or_else(|| Error::InvalidPath(id.to_string()))
+    }
+
+    fn info(id: &str) -> Result<Path> {
+        Self::dir(id)?
+            .join(INFO_FILE)
+            .ok_or_else(|| Error::InvalidPath(id.to_string()))
+    }
+
+    /// The location of the trashed content.
+    fn content(&self) -> Result<Path> {
+        let name = self.path.file_name().unwrap_or_default();
+        Self::dir(&self.id)?
+            .join(name)
+            .ok_or_else(|| Error::InvalidPath(name.to_string()))
+    }
+
+    fn load(jewel: &Emerald, id: &str) -> Result<Self> {
+        let file = File::open(jewel, &Self::info(id)?)?;
+
+        serde_json::from_reader(file)
+            .map_err(|err| Error::InvalidInput(format!("invalid trash entry {}: {}", id, err)))
+    }
+}
+
+fn trash_dir() -> Path {
+    Path::new(TRASH_DIR).expect("valid constant")
+}
+
+/// Returns true if the path is within the trash.
+pub(crate) fn is_trashed(path: &Path) -> bool {
+    path.starts_with(&trash_dir())
+}
+
+/// Moves the file or directory into the trash, a symbolic link is trashed, not its target.
+///
+/// The entry can be restored with [restore], until the trash is emptied.
+/// A restricted handle may trash the paths it can write.
+pub fn trash(jewel: &Emerald, path: &Path) -> Result<TrashEntry> {
+    if path.is_root() || is_trashed(path) {
+        return Err(Error::InvalidInput(format!("cannot trash {}", path)));
+    }
+
+    jewel.access().check_write(path)?;
+    let meta = metadata(jewel, path)?;
+    let deleted = Utc::now();
+
+    // The trash is bookkeeping, kept for any handle allowed to write the path.
+    let jewel = &jewel.unrestricted();
+
+    // The identifier is the deletion time, made unique within the trash.
+    let stamp = deleted.format("%Y%m%dT%H%M%S%3f").to_string();
+    let mut id = stamp.clone();
+    let mut count = 1;
+
+    while metadata(jewel, &TrashEntry::dir(&id)?).is_ok() {
+        id = format!("{}-{}", stamp, count);
+        count += 1;
+    }
+
+    let entry = TrashEntry {
+        id,
+        path: path.clone(),
+        deleted,
+        is_symlink: meta.is_symlink(),
+        is_dir: meta.is_dir(),
+    };
+
+    let dir = TrashEntry::dir(&entry.id)?;
+    create_dir_all(jewel, &dir)?;
+
+    let info =
+        serde_json::to_vec_pretty(&entry).map_err(|err| Error::InvalidInput(err.to_string()))?;
+    write(jewel, &TrashEntry::info(&entry.id)?, info)?;
+
+    if let Err(err) = rename(jewel, path, &entry.content()?) {
+        remove_dir_all_permanently(jewel, &dir)?;
+        return Err(err);
+    }
+
+    Ok(entry)
+}
+
+/// Moves the trashed entry back to its original path, and returns it.
+///
+/// Fails if the original path exists, its missing parent directories are created.
+/// A restricted handle may restore the entries whose original path it can write.
+pub fn restore(jewel: &Emerald, id: &str) -> Result<Path> {
+    let entry = TrashEntry::load(&jewel.unrestricted(), id)?;
+    jewel.access().check_write(&entry.path)?;
+    let jewel = &jewel.unrestricted();
+
+    match metadata(jewel, &entry.path) {
+        Ok(_) => return Err(Error::AlreadyExists(entry.path)),
+        Err(Error::NotFound(_)) => {}
+        Err(err) => return Err(err),
+    }
+
+    if let Some(parent) = entry.path.parent() {
+        create_dir_all(jewel, &parent)?;
+    }
+
+    rename(jewel, &entry.content()?, &entry.path)?;
+    remove_dir_all_permanently(jewel, &TrashEntry::dir(id)?)?;
+
+    Ok(entry.path)
+}
+
+/// Returns the entries of the trash, from the oldest to the most recent deletion.
+///
+/// Entries whose record cannot be read are skipped, as well as the entries whose
+/// original path cannot be read by a restricted handle.
+pub fn list_trash(jewel: &Emerald) -> Result<Vec<TrashEntry>> {
+    let access = jewel.access();
+    let jewel = &jewel.unrestricted();
+
+    let dir = match read_dir(jewel, &trash_dir()) {
+        Ok(dir) => dir,
+        Err(Error::NotFound(_)) => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+
+    let mut entries = dir
+        .flatten()
+        .filter_map(|entry| TrashEntry::load(jewel, entry.path().file_name()?).ok())
+        .filter(|entry| access.check_read(&entry.path).is_ok())
+        .collect::<Vec<_>>();
+
+    entries.sort_by(|a, b| (a.deleted, &a.id).cmp(&(b.deleted, &b.id)));
+    Ok(entries)
+}
+
+/// Permanently removes the entries trashed for longer than the duration,
+/// and returns the number of removed entries.
+///
+/// A zero duration empties the whole trash.
+/// A restricted handle only removes the entries whose original path it can write.
+pub fn empty_trash(jewel: &Emerald, older_than: Duration) -> Result<usize> {
+    let older_than = chrono::Duration::from_std(older_than)
+        .map_err(|err| Error::InvalidInput(err.to_string()))?;
+    let now = Utc::now();
+    let mut count = 0;
+
+    for entry in list_trash(jewel)? {
+        if now - entry.deleted >= older_than && jewel.access().check_write(&entry.path).is_ok() {
+            remove_dir_all_permanently(&jewel.unrestricted(), &TrashEntry::dir(&entry.id)?)?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
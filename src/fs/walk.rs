@@ -0,0 +1,543 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use super::{canonicalize, open, read_dir, DirEntry, MetadataOptions};
+use crate::{error::Result, path::Path, Emerald};
+
+/// The name of the file holding the ignore rules of a directory, with gitignore semantics.
+pub const IGNORE_FILE: &str = ".emeraldignore";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// The order of the entries within a directory.
+pub enum SortOrder {
+    /// By file name, byte-wise.
+    Name,
+    /// By file name, sequences of digits are compared by their numerical value (`2` < `10`).
+    Natural,
+}
+
+impl SortOrder {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            SortOrder::Name => a.cmp(b),
+            SortOrder::Natural => natural_cmp(a, b),
+        }
+    }
+}
+
+/// Compare strings, sequences of digits are compared by their numerical value.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                let x_trimmed = x.trim_start_matches('0');
+                let y_trimmed = y.trim_start_matches('0');
+
+                let ord = x_trimmed
+                    .len()
+                    .cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed))
+                    .then_with(|| x.len().cmp(&y.len()));
+
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut number = String::new();
+
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        number.push(c);
+    }
+
+    number
+}
+
+/// Compile glob patterns matching jewel paths.
+///
+/// A pattern without `/` matches the file name at any depth (`*.md`),
+/// otherwise it matches the path from the jewel's root (`/projects/**/*.md`).
+pub(crate) fn compile_globs<S: AsRef<str>>(patterns: &[S]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in patterns {
+        let pattern = pattern.as_ref();
+
+        let pattern = if !pattern.contains('/') {
+            format!("/**/{}", pattern)
+        } else if !pattern.starts_with('/') {
+            format!("/{}", pattern)
+        } else {
+            pattern.to_string()
+        };
+
+        builder.add(
+            GlobBuilder::new(&pattern)
+                .literal_separator(true)
+                .backslash_escape(true)
+                .build()?,
+        );
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Configure a recursive walk over the jewel.
+///
+/// ```
+/// use emerald::{fs::{SortOrder, WalkBuilder}, path::Path, storage::MemoryStorage, Emerald};
+///
+/// let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+///     ("/projects/2024-01.md", ""),
+///     ("/projects/draft.txt", ""),
+/// ]));
+///
+/// let shards = WalkBuilder::new(&jewel, &Path::root())
+///     .max_depth(2)
+///     .include("*.md")
+///     .sort(SortOrder::Natural)
+///     .build()
+///     .unwrap()
+///     .map(|entry| entry.unwrap().path().to_string())
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(shards, vec!["/projects/2024-01.md"]);
+/// ```
+#[derive(Clone)]
+pub struct WalkBuilder {
+    jewel: Emerald,
+    path: Path,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    hidden: bool,
+    ignore_files: bool,
+    includes: Vec<String>,
+    excludes: Vec<String>,
+    sort: Option<SortOrder>,
+    metadata: MetadataOptions,
+}
+
+impl WalkBuilder {
+    /// Walk from the path with the default options:
+    /// follow symlinks, skip hidden files, honor ignore files and sort by name.
+    pub fn new(jewel: &Emerald, path: &Path) -> Self {
+        Self {
+            jewel: jewel.clone(),
+            path: path.clone(),
+            max_depth: None,
+            follow_symlinks: true,
+            hidden: false,
+            ignore_files: true,
+            includes: vec![],
+            excludes: vec![],
+            sort: Some(SortOrder::Name),
+            metadata: MetadataOptions::default(),
+        }
+    }
+
+    /// The maximum depth of the yielded entries, the direct children of the path are at depth 1.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Walk through the symbolic links.
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Yield and walk through hidden files, whose name starts with a `.`.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Honor the `.emeraldignore` files, and the ignore patterns of the jewel's configuration.
+    pub fn ignore_files(mut self, ignore_files: bool) -> Self {
+        self.ignore_files = ignore_files;
+        self
+    }
+
+    /// Only yield the entries matching one of the included patterns.
+    ///
+    /// Directories are still walked through.
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.includes.push(pattern.to_string());
+        self
+    }
+
+    /// Neither yield, nor walk through, the entries matching the pattern.
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.excludes.push(pattern.to_string());
+        self
+    }
+
+    /// The order of the entries within a directory, None keeps the storage order.
+    pub fn sort(mut self, order: impl Into<Option<SortOrder>>) -> Self {
+        self.sort = order.into();
+        self
+    }
+
+    /// Compute the optional metadata of the yielded entries.
+    pub fn metadata(mut self, options: MetadataOptions) -> Self {
+        self.metadata = options;
+        self
+    }
+
+    pub(crate) fn jewel(&self) -> &Emerald {
+        &self.jewel
+    }
+
+    pub fn build(self) -> Result<Walk> {
+        let includes = if self.includes.is_empty() {
+            None
+        } else {
+            Some(compile_globs(&self.includes)?)
+        };
+
+        let excludes = compile_globs(&self.excludes)?;
+        let root = canonicalize(&self.jewel, &self.path)?;
+
+        let mut walk = Walk {
+            jewel: self.jewel,
+            max_depth: self.max_depth,
+            follow_symlinks: self.follow_symlinks,
+            hidden: self.hidden,
+            ignore_files: self.ignore_files,
+            includes,
+            excludes,
+            sort: self.sort,
+            metadata: self.metadata,
+            stack: vec![],
+            ignores: HashMap::default(),
+        };
+
+        if walk.max_depth != Some(0) {
+            walk.push_children(&self.path, 1, Ancestors::default().with(root))?;
+        }
+
+        Ok(walk)
+    }
+}
+
+/// Recursive iterator over the entries of a directory, depth-first.
+///
+/// An entry, or a directory, which cannot be read yields an error,
+/// and the walk goes on with the next entries.
+pub struct Walk {
+    jewel: Emerald,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    hidden: bool,
+    ignore_files: bool,
+    includes: Option<GlobSet>,
+    excludes: GlobSet,
+    sort: Option<SortOrder>,
+    metadata: MetadataOptions,
+    stack: Vec<Result<(DirEntry, usize, Ancestors)>>,
+    /// Ignore rules per directory, loaded lazily.
+    ignores: HashMap<Path, Option<Gitignore>>,
+}
+
+#[derive(Clone, Default)]
+/// The canonical directories walked down to an entry, to avoid symlink loops.
+///
+/// Only the ancestors are tracked: two links to the same directory are both walked through.
+struct Ancestors(Option<Arc<Ancestor>>);
+
+struct Ancestor {
+    canon: PathBuf,
+    parent: Ancestors,
+}
+
+impl Ancestors {
+    fn with(&self, canon: PathBuf) -> Self {
+        Self(Some(Arc::new(Ancestor {
+            canon,
+            parent: self.clone(),
+        })))
+    }
+
+    fn contains(&self, canon: &std::path::Path) -> bool {
+        let mut current = self.0.as_ref();
+
+        while let Some(ancestor) = current {
+            if ancestor.canon == canon {
+                return true;
+            }
+
+            current = ancestor.parent.0.as_ref();
+        }
+
+        false
+    }
+}
+
+impl Walk {
+    /// Push the children of the directory, in reverse order to pop them in order.
+    ///
+    /// The errors are yielded after the entries of the directory.
+    fn push_children(&mut self, path: &Path, depth: usize, ancestors: Ancestors) -> Result<()> {
+        let mut children = vec![];
+        let mut errors = vec![];
+
+        for entry in read_dir(&self.jewel, path)?.metadata(self.metadata) {
+            match entry {
+                Ok(entry) if !self.is_skipped(&entry) => children.push(entry),
+                Ok(_) => {}
+                Err(err) => errors.push(Err(err)),
+            }
+        }
+
+        if let Some(order) = self.sort {
+            children.sort_by(|a, b| {
+                order.compare(
+                    a.path().file_name().unwrap_or_default(),
+                    b.path().file_name().unwrap_or_default(),
+                )
+            });
+        }
+
+        self.stack.extend(errors);
+        self.stack.extend(
+            children
+                .into_iter()
+                .rev()
+                .map(|entry| Ok((entry, depth, ancestors.clone()))),
+        );
+
+        Ok(())
+    }
+
+    /// The entry is neither yielded, nor walked through.
+    fn is_skipped(&mut self, entry: &DirEntry) -> bool {
+        let path = entry.path();
+
+        if !self.hidden && path.file_name().is_some_and(|name| name.starts_with('.')) {
+            return true;
+        }
+
+        if self.excludes.is_match(path.as_str()) {
+            return true;
+        }
+
+        self.ignore_files && self.is_ignored(path, entry.metadata().is_dir())
+    }
+
+    fn is_included(&self, entry: &DirEntry) -> bool {
+        self.includes
+            .as_ref()
+            .is_none_or(|includes| includes.is_match(entry.path().as_str()))
+    }
+
+    /// Check the ignore files of all the ancestors, the nearest one wins.
+    fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        let mut dir = path.parent();
+
+        while let Some(current) = dir {
+            if let Some(ignore) = self.load_ignore(&current) {
+                let matched = ignore.matched(path.as_str(), is_dir);
+
+                if matched.is_ignore() {
+                    return true;
+                }
+
+                if matched.is_whitelist() {
+                    return false;
+                }
+            }
+
+            dir = current.parent();
+        }
+
+        false
+    }
+
+    fn load_ignore(&mut self, dir: &Path) -> Option<&Gitignore> {
+        if !self.ignores.contains_key(dir) {
+            let ignore = Self::read_ignore(&self.jewel, dir);
+            self.ignores.insert(dir.clone(), ignore);
+        }
+
+        self.ignores.get(dir)?.as_ref()
+    }
+
+    /// Read the ignore rules of the directory, the root also has the ignore patterns
+    /// of the jewel's configuration, before its own rules.
+    fn read_ignore(jewel: &Emerald, dir: &Path) -> Option<Gitignore> {
+        let mut rules = if dir.is_root() {
+            jewel.config().ignore.join("\n")
+        } else {
+            String::default()
+        };
+
+        if let Some(mut file) = dir
+            .join(IGNORE_FILE)
+            .and_then(|path| open(jewel, &path).ok())
+        {
+            rules.push('\n');
+            file.read_to_string(&mut rules).ok()?;
+        }
+
+        if rules.trim().is_empty() {
+            return None;
+        }
+
+        let mut builder = GitignoreBuilder::new(dir.as_str());
+
+        for line in rules.lines() {
+            builder.add_line(None, line).ok()?;
+        }
+
+        builder.build().ok()
+    }
+
+    /// The ancestors of the children of the entry, None if the entry is not walked through.
+    fn can_descend(
+        &self,
+        entry: &DirEntry,
+        depth: usize,
+        ancestors: &Ancestors,
+    ) -> Option<Ancestors> {
+        let meta = entry.metadata();
+
+        if !(meta.is_dir() || (self.follow_symlinks && meta.is_symlink())) {
+            return None;
+        }
+
+        if self.max_depth.is_some_and(|max| depth >= max) {
+            return None;
+        }
+
+        // Dangling symlinks cannot be canonicalized, and symlinks to files are not walked through.
+        match canonicalize(&self.jewel, entry.path()) {
+            Ok(canon) if ancestors.contains(&canon) => None,
+            Ok(canon) if meta.is_dir() || self.is_dir(&canon) => Some(ancestors.with(canon)),
+            _ => None,
+        }
+    }
+
+    fn is_dir(&self, canon: &std::path::Path) -> bool {
+        self.jewel
+            .storage()
+            .metadata(canon)
+            .is_ok_and(|meta| meta.is_dir)
+    }
+
+    /// Collect the remaining entries, the directories of each level are walked in parallel,
+    /// in the order of the sequential walk.
+    ///
+    /// Each directory is walked by a fork of the walk, the symlink loops are detected
+    /// along the ancestors of the entries as in the sequential walk.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn par_collect(mut self) -> Vec<Result<DirEntry>> {
+        use rayon::prelude::*;
+
+        let items = std::mem::take(&mut self.stack)
+            .into_iter()
+            .rev()
+            .collect::<Vec<_>>();
+
+        items
+            .into_par_iter()
+            .flat_map_iter(|item| {
+                let (entry, depth, ancestors) = match item {
+                    Ok(item) => item,
+                    Err(err) => return vec![Err(err)],
+                };
+
+                let mut fork = self.fork();
+                let mut entries = vec![];
+                let pushed = fork
+                    .can_descend(&entry, depth, &ancestors)
+                    .map(|ancestors| fork.push_children(entry.path(), depth + 1, ancestors));
+
+                if fork.is_included(&entry) {
+                    entries.push(Ok(entry));
+                }
+
+                match pushed {
+                    Some(Ok(())) => entries.extend(fork.par_collect()),
+                    Some(Err(err)) => entries.push(Err(err)),
+                    None => {}
+                }
+
+                entries
+            })
+            .collect()
+    }
+
+    /// A walk with the same options and caches, and no pending entries.
+    #[cfg(feature = "parallel")]
+    fn fork(&self) -> Self {
+        Self {
+            jewel: self.jewel.clone(),
+            max_depth: self.max_depth,
+            follow_symlinks: self.follow_symlinks,
+            hidden: self.hidden,
+            ignore_files: self.ignore_files,
+            includes: self.includes.clone(),
+            excludes: self.excludes.clone(),
+            sort: self.sort,
+            metadata: self.metadata,
+            stack: vec![],
+            ignores: self.ignores.clone(),
+        }
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (entry, depth, ancestors) = match self.stack.pop()? {
+                Ok(item) => item,
+                Err(err) => return Some(Err(err)),
+            };
+
+            if let Some(ancestors) = self.can_descend(&entry, depth, &ancestors) {
+                if let Err(err) = self.push_children(entry.path(), depth + 1, ancestors) {
+                    self.stack.push(Err(err));
+                }
+            }
+
+            if self.is_included(&entry) {
+                return Some(Ok(entry));
+            }
+        }
+    }
+}
+
+/// Walk from the current directory to all its descendants, with the default options.
+///
+/// See [WalkBuilder] to configure the walk.
+pub fn walk(jewel: &Emerald, path: &Path) -> Result<Walk> {
+    WalkBuilder::new(jewel, path).build()
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{Metadata, WalkBuilder};
+use crate::{error::Result, path::Path, Emerald};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+/// A change within the watched directory.
+pub enum Event {
+    Created {
+        path: Path,
+        metadata: Metadata,
+    },
+    Modified {
+        path: Path,
+        metadata: Metadata,
+    },
+    /// The metadata is the last one known before the removal.
+    Removed {
+        path: Path,
+        metadata: Metadata,
+    },
+    Renamed {
+        from: Path,
+        to: Path,
+        metadata: Metadata,
+    },
+}
+
+impl Event {
+    /// The path of the entry, its new path if it was renamed.
+    pub fn path(&self) -> &Path {
+        match self {
+            Event::Created { path, .. } => path,
+            Event::Modified { path, .. } => path,
+            Event::Removed { path, .. } => path,
+            Event::Renamed { to, .. } => to,
+        }
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        match self {
+            Event::Created { metadata, .. } => metadata,
+            Event::Modified { metadata, .. } => metadata,
+            Event::Removed { metadata, .. } => metadata,
+            Event::Renamed { metadata, .. } => metadata,
+        }
+    }
+}
+
+/// What is compared between two polls to detect a modification.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Fingerprint {
+    is_dir: bool,
+    is_symlink: bool,
+    len: u64,
+    modified: Option<DateTime<Utc>>,
+}
+
+impl From<&Metadata> for Fingerprint {
+    fn from(meta: &Metadata) -> Self {
+        // The changes within a directory are reported on its entries, not on the directory.
+        if meta.is_dir() {
+            return Self {
+                is_dir: true,
+                is_symlink: false,
+                len: 0,
+                modified: None,
+            };
+        }
+
+        Self {
+            is_dir: false,
+            is_symlink: meta.is_symlink(),
+            len: meta.len(),
+            modified: meta.modified(),
+        }
+    }
+}
+
+type Snapshot = HashMap<Path, Metadata>;
+
+/// Configure a watcher over a directory of the jewel.
+///
+/// The watcher polls the storage, hence it works with any [crate::storage::Storage].
+///
+/// ```
+/// use std::time::Duration;
+/// use emerald::{fs::{Event, WatchBuilder}, path::Path, storage::MemoryStorage, Emerald};
+///
+/// let jewel = Emerald::from_storage(MemoryStorage::new());
+/// let watcher = WatchBuilder::new(&jewel, &Path::root())
+///     .interval(Duration::from_millis(10))
+///     .build()
+///     .unwrap();
+///
+/// emerald::fs::write(&jewel, &Path::new("/index.md").unwrap(), "# Index").unwrap();
+///
+/// let event = watcher.recv_timeout(Duration::from_secs(5)).unwrap();
+/// assert!(matches!(event, Event::Created { .. }));
+/// assert!(event.metadata().is_shard());
+/// ```
+pub struct WatchBuilder {
+    walk: WalkBuilder,
+    interval: Duration,
+    debounce: Duration,
+}
+
+impl WatchBuilder {
+    /// Watch the directory, and all its descendants, with the default options:
+    /// poll every 500ms, debounce for 100ms, skip hidden files and honor ignore files.
+    pub fn new(jewel: &Emerald, path: &Path) -> Self {
+        Self {
+            walk: WalkBuilder::new(jewel, path)
+                .follow_symlinks(false)
+                .sort(None),
+            interval: Duration::from_millis(500),
+            debounce: Duration::from_millis(100),
+        }
+    }
+
+    /// The delay between two polls of the storage.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Wait for the changes to settle during this delay, before emitting the events.
+    ///
+    /// A file created, then modified, within the delay emits a single [Event::Created].
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Watch the hidden files, whose name starts with a `.`.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.walk = self.walk.hidden(hidden);
+        self
+    }
+
+    /// Honor the `.emeraldignore` files.
+    pub fn ignore_files(mut self, ignore_files: bool) -> Self {
+        self.walk = self.walk.ignore_files(ignore_files);
+        self
+    }
+
+    /// Do not watch the entries matching the pattern.
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.walk = self.walk.exclude(pattern);
+        self
+    }
+
+    /// Take the initial snapshot, and start polling in a background thread.
+    pub fn build(self) -> Result<Watcher> {
+        let snapshot = take_snapshot(&self.walk)?;
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let poller = Poller {
+            walk: self.walk,
+            interval: self.interval,
+            debounce: self.debounce,
+            stop: stop.clone(),
+            sender,
+        };
+
+        let thread = std::thread::spawn(move || poller.run(snapshot));
+
+        Ok(Watcher {
+            receiver,
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Receives the events of the watched directory.
+///
+/// The background thread stops when the watcher is dropped.
+pub struct Watcher {
+    receiver: Receiver<Event>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    /// Wait for the next event.
+    pub fn recv(&self) -> Option<Event> {
+        self.receiver.recv().ok()
+    }
+
+    /// Wait for the next event, at most for the timeout.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Event> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    /// Returns the next event, if one is pending.
+    pub fn try_recv(&self) -> Option<Event> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Iterator for Watcher {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct Poller {
+    walk: WalkBuilder,
+    interval: Duration,
+    debounce: Duration,
+    stop: Arc<AtomicBool>,
+    sender: Sender<Event>,
+}
+
+impl Poller {
+    fn run(self, mut emitted: Snapshot) {
+        let mut pending = emitted.clone();
+        let mut changed_at: Option<Instant> = None;
+
+        while !self.stop.load(Ordering::Relaxed) {
+            std::thread::sleep(self.interval);
+
+            // The watched directory may be transiently unavailable.
+            let Ok(current) = take_snapshot(&self.walk) else {
+                continue;
+            };
+
+            if !same(&current, &pending) {
+                pending = current;
+                changed_at = Some(Instant::now());
+            }
+
+            if changed_at.is_some_and(|at| at.elapsed() >= self.debounce) {
+                for event in diff(&emitted, &pending) {
+                    if self.sender.send(event).is_err() {
+                        return;
+                    }
+                }
+
+                emitted = pending.clone();
+                changed_at = None;
+            }
+        }
+    }
+}
+
+/// The entries which cannot be read are not watched.
+fn take_snapshot(walk: &WalkBuilder) -> Result<Snapshot> {
+    Ok(walk
+        .clone()
+        .build()?
+        .flatten()
+        .map(|entry| (entry.path().clone(), entry.metadata().clone()))
+        .collect())
+}
+
+fn same(a: &Snapshot, b: &Snapshot) -> bool {
+    a.len() == b.len()
+        && a.iter().all(|(path, meta)| {
+            b.get(path)
+                .is_some_and(|other| Fingerprint::from(meta) == Fingerprint::from(other))
+        })
+}
+
+/// Compute the events between two snapshots, sorted by path.
+///
+/// An entry removed and an entry created with the same fingerprint are paired as a rename.
+fn diff(before: &Snapshot, after: &Snapshot) -> Vec<Event> {
+    let mut events = vec![];
+    let mut removed = before
+        .iter()
+        .filter(|(path, _)| !after.contains_key(*path))
+        .collect::<Vec<_>>();
+    let mut created = after
+        .iter()
+        .filter(|(path, _)| !before.contains_key(*path))
+        .collect::<Vec<_>>();
+
+    removed.sort_by(|a, b| a.0.cmp(b.0));
+    created.sort_by(|a, b| a.0.cmp(b.0));
+
+    // Only pair the unambiguous renames.
+    let mut counts = HashMap::<Fingerprint, (usize, usize)>::new();
+
+    for (_, meta) in &removed {
+        counts.entry(Fingerprint::from(*meta)).or_default().0 += 1;
+    }
+
+    for (_, meta) in &created {
+        counts.entry(Fingerprint::from(*meta)).or_default().1 += 1;
+    }
+
+    let is_rename = |meta: &Metadata| counts.get(&Fingerprint::from(meta)) == Some(&(1, 1));
+
+    for (path, meta) in &created {
+        if is_rename(meta) {
+            let (from, _) = removed
+                .iter()
+                .find(|(_, old)| Fingerprint::from(*old) == Fingerprint::from(*meta))
+                .unwrap();
+
+            events.push(Event::Renamed {
+                from: (*from).clone(),
+                to: (*path).clone(),
+                metadata: (*meta).clone(),
+            });
+        } else {
+            events.push(Event::Created {
+                path: (*path).clone(),
+                metadata: (*meta).clone(),
+            });
+        }
+    }
+
+    for (path, meta) in removed.into_iter().filter(|(_, meta)| !is_rename(meta)) {
+        events.push(Event::Removed {
+            path: path.clone(),
+            metadata: meta.clone(),
+        });
+    }
+
+    for (path, meta) in after {
+        if let Some(old) = before.get(path) {
+            if Fingerprint::from(old) != Fingerprint::from(meta) {
+                events.push(Event::Modified {
+                    path: path.clone(),
+                    metadata: meta.clone(),
+                });
+            }
+        }
+    }
+
+    events.sort_by(|a, b| a.path().cmp(b.path()));
+    events
+}
+
+/// Watch the jewel with the default options.
+///
+/// See [WatchBuilder] to configure the watcher.
+pub fn watch(jewel: &Emerald) -> Result<Watcher> {
+    WatchBuilder::new(jewel, &Path::root()).build()
+}
@@ -0,0 +1,112 @@
+use super::{metadata, WalkBuilder};
+use crate::{
+    error::{Error, Result},
+    path::Path,
+    Emerald,
+};
+
+/// The extension of the shards, optional in a target.
+const SHARD_EXTENSION: &str = "md";
+
+/// Resolves the target of a wikilink of the shard located at `from` to a file of the jewel.
+///
+/// - an empty target, as in `[[#heading]]`, is the shard itself,
+/// - a target starting with `/`, `./` or `../` is a path, absolute or relative to the shard,
+/// - any other target matches the files whose path ends with it, case-insensitively,
+///   such as `alpha` or `projects/alpha` for `/projects/alpha.md`. The file relative to
+///   the shard wins, then the shortest match; several shortest matches are ambiguous.
+///
+/// The `.md` extension of the target is optional.
+///
+/// ```
+/// use emerald::{path::Path, storage::MemoryStorage, Emerald};
+///
+/// let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+///     ("/index.md", "See [[alpha]]"),
+///     ("/projects/alpha.md", "# Alpha"),
+///     ("/archive/projects/alpha.md", "# Old alpha"),
+/// ]));
+///
+/// let index = Path::new("/index.md").unwrap();
+/// let alpha = emerald::fs::resolve_wikilink(&jewel, &index, "alpha").unwrap();
+/// assert_eq!(alpha.as_str(), "/projects/alpha.md");
+/// ```
+pub fn resolve_wikilink(jewel: &Emerald, from: &Path, target: &str) -> Result<Path> {
+    let target = target.trim();
+    let invalid = || Error::InvalidPath(target.to_string());
+
+    if target.is_empty() {
+        return Ok(from.clone());
+    }
+
+    let dir = from.parent().unwrap_or_default();
+
+    if target.starts_with(['/', '.']) {
+        let path = dir.join(target).ok_or_else(invalid)?;
+
+        return with_extension(&path)
+            .into_iter()
+            .find(|candidate| is_file(jewel, candidate))
+            .ok_or(Error::NotFound(path));
+    }
+
+    let relative = dir
+        .join(target)
+        .map(|path| with_extension(&path))
+        .unwrap_or_default();
+    let suffixes = with_extension(&Path::root().join(target).ok_or_else(invalid)?)
+        .into_iter()
+        .map(|path| path.as_str().to_lowercase())
+        .collect::<Vec<_>>();
+
+    let mut matches = vec![];
+
+    for entry in WalkBuilder::new(jewel, &Path::root()).build()? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+
+        let path = entry.path().as_str().to_lowercase();
+
+        if !entry.metadata().is_dir() && suffixes.iter().any(|suffix| path.ends_with(suffix)) {
+            if relative.contains(entry.path()) {
+                return Ok(entry.path().clone());
+            }
+
+            matches.push(entry.path().clone());
+        }
+    }
+
+    let shortest = matches.iter().map(|path| path.parts().count()).min();
+    let mut shortest = matches
+        .into_iter()
+        .filter(|path| Some(path.parts().count()) == shortest);
+
+    match (shortest.next(), shortest.next()) {
+        (Some(path), None) => Ok(path),
+        (Some(first), Some(second)) => Err(Error::InvalidInput(format!(
+            "the wikilink [[{}]] is ambiguous: {} and {}",
+            target, first, second
+        ))),
+        (None, _) => Err(Error::NotFound(
+            Path::root().join(target).ok_or_else(invalid)?,
+        )),
+    }
+}
+
+/// The path as is, and with the shard extension unless it already has it.
+fn with_extension(path: &Path) -> Vec<Path> {
+    let extended = match path.extension() {
+        Some(SHARD_EXTENSION) => None,
+        _ => Path::new(&format!("{}.{}", path, SHARD_EXTENSION)),
+    };
+
+    [Some(path.clone()), extended]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn is_file(jewel: &Emerald, path: &Path) -> bool {
+    metadata(jewel, path).is_ok_and(|meta| !meta.is_dir())
+}
@@ -1,17 +1,38 @@
-use std::{error::Error, ffi::OsStr};
+use std::ffi::OsStr;
 
+pub mod config;
 pub mod emerald;
+pub mod error;
 pub mod fs;
 pub mod path;
 
 pub mod script;
 pub mod shard;
+pub mod storage;
+pub use config::Config;
 pub use emerald::Emerald;
+pub use error::{Error, Result};
 
 /// Open the jewel
-pub fn open<S>(root: &S) -> Result<Emerald, Box<dyn Error>>
+pub fn open<S>(root: &S) -> Result<Emerald>
 where
     S: AsRef<OsStr> + ?Sized,
 {
     Emerald::open(root)
 }
+
+/// Scaffold a new jewel, see [Emerald::init].
+pub fn init<S>(root: &S) -> Result<Emerald>
+where
+    S: AsRef<OsStr> + ?Sized,
+{
+    Emerald::init(root)
+}
+
+/// Open the nearest jewel containing the directory, see [Emerald::discover].
+pub fn discover<S>(cwd: &S) -> Result<Emerald>
+where
+    S: AsRef<OsStr> + ?Sized,
+{
+    Emerald::discover(cwd)
+}
@@ -1,16 +1,91 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Default, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+/// The separator between two parts of a jewel path.
+pub const SEPARATOR: char = '/';
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(try_from = "String", into = "String")]
 /// A path in the jewel fs
+///
+/// The path is always absolute from the jewel's root, and normalized:
+/// - separators are collapsed (`a//b` is `/a/b`),
+/// - `.` parts are dropped (`./a` is `/a`),
+/// - `..` parts are resolved (`/a/../b` is `/b`).
+///
+/// A path cannot escape the jewel's root, `Path::new("../etc")` returns `None`.
 pub struct Path {
     inner: String,
 }
 
+impl Default for Path {
+    fn default() -> Self {
+        Self::root()
+    }
+}
+
 impl Path {
+    /// Creates a normalized path, returns None if the path is invalid,
+    /// or if it escapes the jewel's root.
     pub fn new(value: &str) -> Option<Self> {
-        Some(Self {
-            inner: value.into(),
-        })
+        let mut path = Self::root();
+        path.push_parts(value).then_some(path)
+    }
+
+    /// The root of the jewel.
+    pub fn root() -> Self {
+        Self {
+            inner: SEPARATOR.to_string(),
+        }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.inner.len() == 1
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.inner
+    }
+
+    /// Push the parts of the value into the path.
+    ///
+    /// Returns false, and leaves the path untouched, if the value is invalid.
+    fn push_parts(&mut self, value: &str) -> bool {
+        if value.contains('\0') {
+            return false;
+        }
+
+        let mut parts = self.parts().collect::<Vec<_>>();
+
+        for part in value.split(['/', '\\']) {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    if parts.pop().is_none() {
+                        return false;
+                    }
+                }
+                part => parts.push(part),
+            }
+        }
+
+        *self = Self::from_parts(parts);
+        true
+    }
+
+    /// Build the path from already normalized parts.
+    fn from_parts<'a>(parts: impl IntoIterator<Item = Part<'a>>) -> Self {
+        let mut inner = String::new();
+
+        for part in parts {
+            inner.push(SEPARATOR);
+            inner.push_str(part);
+        }
+
+        if inner.is_empty() {
+            return Self::root();
+        }
+
+        Self { inner }
     }
 }
 
@@ -22,21 +97,176 @@ impl std::fmt::Debug for Path {
     }
 }
 
-impl std::string::ToString for Path {
-    fn to_string(&self) -> String {
-        self.inner.clone()
+impl std::fmt::Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+impl std::str::FromStr for Path {
+    type Err = InvalidPath;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s).ok_or_else(|| InvalidPath(s.to_string()))
     }
 }
 
+impl TryFrom<String> for Path {
+    type Error = InvalidPath;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Path> for String {
+    fn from(value: Path) -> Self {
+        value.inner
+    }
+}
+
+impl AsRef<str> for Path {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The path is malformed or escapes the jewel's root.
+pub struct InvalidPath(pub String);
+
+impl std::fmt::Display for InvalidPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid jewel path: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidPath {}
+
 impl Path {
-    pub fn append(&mut self, part: &str) {
-        if !part.starts_with('/') {
-            self.inner.push('/');
-        }
-        self.inner.push_str(part);
+    /// Append a part to the path.
+    ///
+    /// Returns false, and leaves the path untouched, if the result would escape the root.
+    pub fn append(&mut self, part: &str) -> bool {
+        self.push_parts(part)
     }
 
+    /// Iterate over the parts of the path, the root has no parts.
     pub fn parts(&self) -> impl Iterator<Item = Part<'_>> {
-        self.inner.split('/')
+        self.inner.split(SEPARATOR).filter(|part| !part.is_empty())
+    }
+
+    /// Creates a new path by adjoining the value to self.
+    ///
+    /// If the value starts with a `/`, it replaces the current path.
+    pub fn join(&self, value: &str) -> Option<Self> {
+        if value.starts_with(SEPARATOR) {
+            return Self::new(value);
+        }
+
+        let mut path = self.clone();
+        path.push_parts(value).then_some(path)
+    }
+
+    /// Returns the path without its final part, or None if the path is the root.
+    pub fn parent(&self) -> Option<Self> {
+        if self.is_root() {
+            return None;
+        }
+
+        let count = self.parts().count();
+        Some(Self::from_parts(self.parts().take(count - 1)))
+    }
+
+    /// Returns the final part of the path, or None if the path is the root.
+    pub fn file_name(&self) -> Option<Part<'_>> {
+        self.parts().last()
+    }
+
+    /// Returns the file name without its extension.
+    ///
+    /// Similar to [https://doc.rust-lang.org/std/path/struct.Path.html#method.file_stem]
+    pub fn file_stem(&self) -> Option<Part<'_>> {
+        let name = self.file_name()?;
+        Some(Self::split_extension(name).0)
+    }
+
+    /// Returns the extension of the file name, if any.
+    ///
+    /// Similar to [https://doc.rust-lang.org/std/path/struct.Path.html#method.extension]
+    pub fn extension(&self) -> Option<Part<'_>> {
+        let name = self.file_name()?;
+        Self::split_extension(name).1
+    }
+
+    /// Creates a new path with the given extension, an empty extension removes it.
+    ///
+    /// Returns None if the path is the root.
+    pub fn with_extension(&self, extension: &str) -> Option<Self> {
+        let stem = self.file_stem()?;
+        let name = if extension.is_empty() {
+            stem.to_string()
+        } else {
+            format!("{}.{}", stem, extension)
+        };
+
+        self.parent()?.join(&name)
+    }
+
+    /// Determines whether base is a prefix of self, part-wise.
+    pub fn starts_with(&self, base: &Path) -> bool {
+        let mut parts = self.parts();
+        base.parts().all(|part| parts.next() == Some(part))
+    }
+
+    /// Returns the path relative to base, rooted at base.
+    ///
+    /// ```
+    /// use emerald::path::Path;
+    ///
+    /// let path = Path::new("/projects/alpha/index.md").unwrap();
+    /// let base = Path::new("/projects").unwrap();
+    /// assert_eq!(path.strip_prefix(&base), Path::new("/alpha/index.md"));
+    /// ```
+    pub fn strip_prefix(&self, base: &Path) -> Option<Self> {
+        if !self.starts_with(base) {
+            return None;
+        }
+
+        Some(Self::from_parts(self.parts().skip(base.parts().count())))
+    }
+
+    /// Returns the relative path to go from base to self.
+    ///
+    /// ```
+    /// use emerald::path::Path;
+    ///
+    /// let path = Path::new("/projects/alpha/index.md").unwrap();
+    /// let base = Path::new("/people").unwrap();
+    /// assert_eq!(path.relative_to(&base), "../projects/alpha/index.md");
+    /// ```
+    pub fn relative_to(&self, base: &Path) -> String {
+        let common = self
+            .parts()
+            .zip(base.parts())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let parts = std::iter::repeat_n("..", base.parts().count() - common)
+            .chain(self.parts().skip(common))
+            .collect::<Vec<_>>();
+
+        if parts.is_empty() {
+            ".".to_string()
+        } else {
+            parts.join("/")
+        }
+    }
+
+    fn split_extension(name: &str) -> (Part<'_>, Option<Part<'_>>) {
+        match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => (stem, Some(ext)),
+            _ => (name, None),
+        }
     }
 }
@@ -1,34 +1,153 @@
-use mlua::{Function, Lua, Result, Table, UserData, Value};
+use mlua::{AppDataRef, Function, Lua, LuaSerdeExt, Result, Table, UserData, Value};
 
 use crate::{
-    fs::{self, DirEntry, File, Metadata},
+    fs::{self, DirEntry, File, Metadata, MetadataOptions, SortOrder, SymlinkTarget, WalkBuilder},
     path::Path,
 };
 
 use super::Context;
 
-fn fs_walk(lua: &Lua, path: String) -> Result<Function<'_>> {
-    let path = Path::new(&path).unwrap();
-    let ctx = lua.app_data_ref::<Context>().unwrap();
-    let mut walk = fs::walk(&ctx.emerald, &path).unwrap();
+/// Returns the context of the instance.
+fn context(lua: &Lua) -> Result<AppDataRef<'_, Context>> {
+    lua.app_data_ref::<Context>()
+        .ok_or_else(|| mlua::Error::runtime("the emerald context is not injected"))
+}
+
+fn path(value: &str) -> Result<Path> {
+    Ok(value.parse::<Path>().map_err(crate::error::Error::from)?)
+}
+
+/// Returns an iterator function over the entries, skipping the entries which cannot be read.
+fn entries<'lua, I>(lua: &'lua Lua, mut entries: I) -> Result<Function<'lua>>
+where
+    I: Iterator<Item = crate::error::Result<DirEntry>> + 'static,
+{
     lua.create_function_mut(move |lua, ()| {
-        let next = walk.next();
-        Ok(match next {
+        Ok(match entries.find_map(|entry| entry.ok()) {
             Some(entry) => lua.pack(lua.create_ser_userdata(entry)?)?,
             None => Value::Nil,
         })
     })
 }
 
-fn fs_open(lua: &Lua, (path, _mode): (String, u8)) -> Result<Value<'_>> {
-    let ctx = lua.app_data_ref::<Context>().unwrap();
-    let path = Path::new(&path).unwrap();
-    let file = fs::open(&ctx.emerald, &path).unwrap();
+/// Configure the walk from the options table.
+///
+/// ```lua
+/// emerald.fs.walk("/projects", {
+///     max_depth = 2,
+///     follow_symlinks = false,
+///     hidden = false,
+///     ignore_files = true,
+///     include = { "*.md" },
+///     exclude = { "/projects/archive" },
+///     sort = "natural", -- "name", "natural" or "none"
+///     hash = true, -- entry.metadata.hash
+///     title = true, -- entry.metadata.title
+/// })
+/// ```
+fn walk_builder(ctx: &Context, path: &Path, options: Option<Table<'_>>) -> Result<WalkBuilder> {
+    let mut builder = WalkBuilder::new(&ctx.emerald, path);
+
+    let Some(options) = options else {
+        return Ok(builder);
+    };
+
+    if let Some(depth) = options.get::<_, Option<usize>>("max_depth")? {
+        builder = builder.max_depth(depth);
+    }
+
+    if let Some(follow) = options.get::<_, Option<bool>>("follow_symlinks")? {
+        builder = builder.follow_symlinks(follow);
+    }
+
+    if let Some(hidden) = options.get::<_, Option<bool>>("hidden")? {
+        builder = builder.hidden(hidden);
+    }
+
+    if let Some(ignore_files) = options.get::<_, Option<bool>>("ignore_files")? {
+        builder = builder.ignore_files(ignore_files);
+    }
+
+    for pattern in options
+        .get::<_, Option<Vec<String>>>("include")?
+        .unwrap_or_default()
+    {
+        builder = builder.include(&pattern);
+    }
+
+    for pattern in options
+        .get::<_, Option<Vec<String>>>("exclude")?
+        .unwrap_or_default()
+    {
+        builder = builder.exclude(&pattern);
+    }
+
+    if let Some(sort) = options.get::<_, Option<String>>("sort")? {
+        builder = builder.sort(match sort.as_str() {
+            "name" => Some(SortOrder::Name),
+            "natural" => Some(SortOrder::Natural),
+            "none" => None,
+            _ => {
+                return Err(mlua::Error::runtime(format!(
+                    "invalid sort order: {}",
+                    sort
+                )))
+            }
+        });
+    }
+
+    let hash = options.get::<_, Option<bool>>("hash")?.unwrap_or_default();
+    let title = options.get::<_, Option<bool>>("title")?.unwrap_or_default();
+    builder = builder.metadata(MetadataOptions { hash, title });
+
+    Ok(builder)
+}
+
+fn fs_walk<'lua>(
+    lua: &'lua Lua,
+    (path_str, options): (String, Option<Table<'lua>>),
+) -> Result<Function<'lua>> {
+    let path = path(&path_str)?;
+    let walk = walk_builder(&*context(lua)?, &path, options)?.build()?;
+    entries(lua, walk)
+}
+
+fn fs_glob<'lua>(lua: &'lua Lua, pattern: String) -> Result<Function<'lua>> {
+    let glob = fs::glob(&context(lua)?.emerald, &pattern)?;
+    entries(lua, glob)
+}
+
+fn fs_open(lua: &Lua, (path_str, _mode): (String, u8)) -> Result<Value<'_>> {
+    let path = path(&path_str)?;
+    let file = fs::open(&context(lua)?.emerald, &path)?;
     let val = lua.create_userdata(file)?;
 
     Ok(Value::UserData(val))
 }
 
+/// Move the entry into the trash, returns the trash entry as a table.
+fn fs_trash(lua: &Lua, path_str: String) -> Result<Value<'_>> {
+    let path = path(&path_str)?;
+    let entry = fs::trash(&context(lua)?.emerald, &path)?;
+    lua.to_value(&entry)
+}
+
+/// Restore the trash entry, returns its original path.
+fn fs_restore(lua: &Lua, id: String) -> Result<String> {
+    Ok(fs::restore(&context(lua)?.emerald, &id)?.to_string())
+}
+
+fn fs_list_trash(lua: &Lua, (): ()) -> Result<Value<'_>> {
+    let entries = fs::list_trash(&context(lua)?.emerald)?;
+    lua.to_value(&entries)
+}
+
+/// Empty the entries trashed for longer than the number of seconds, or all of them.
+fn fs_empty_trash(lua: &Lua, older_than: Option<u64>) -> Result<usize> {
+    let older_than = std::time::Duration::from_secs(older_than.unwrap_or_default());
+    Ok(fs::empty_trash(&context(lua)?.emerald, older_than)?)
+}
+
 pub fn create_fs_table(lua: &Lua) -> Result<Table<'_>> {
     let fs = lua.create_table()?;
 
@@ -36,7 +155,12 @@ pub fn create_fs_table(lua: &Lua) -> Result<Table<'_>> {
     fs.set("WRITE", 4)?;
 
     fs.set("walk", lua.create_function(fs_walk)?)?;
+    fs.set("glob", lua.create_function(fs_glob)?)?;
     fs.set("open", lua.create_function(fs_open)?)?;
+    fs.set("trash", lua.create_function(fs_trash)?)?;
+    fs.set("restore", lua.create_function(fs_restore)?)?;
+    fs.set("list_trash", lua.create_function(fs_list_trash)?)?;
+    fs.set("empty_trash", lua.create_function(fs_empty_trash)?)?;
 
     Ok(fs)
 }
@@ -49,11 +173,31 @@ impl UserData for Path {
     }
 }
 
+/// The timestamps are exposed as unix timestamps, in seconds.
 impl UserData for Metadata {
     fn add_fields<'lua, F: mlua::UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("is_symlink", |_, this| Ok(this.is_symlink()));
         fields.add_field_method_get("is_shard", |_, this| Ok(this.is_shard()));
         fields.add_field_method_get("is_file", |_, this| Ok(this.is_file()));
         fields.add_field_method_get("is_dir", |_, this| Ok(this.is_dir()));
+        fields.add_field_method_get("len", |_, this| Ok(this.len()));
+        fields.add_field_method_get("created", |_, this| {
+            Ok(this.created().map(|time| time.timestamp()))
+        });
+        fields.add_field_method_get("modified", |_, this| {
+            Ok(this.modified().map(|time| time.timestamp()))
+        });
+        fields.add_field_method_get("accessed", |_, this| {
+            Ok(this.accessed().map(|time| time.timestamp()))
+        });
+        fields.add_field_method_get("symlink_target", |_, this| {
+            Ok(this.symlink_target().map(|target| match target {
+                SymlinkTarget::Jewel(path) => path.to_string(),
+                SymlinkTarget::Storage(path) => path.display().to_string(),
+            }))
+        });
+        fields.add_field_method_get("hash", |_, this| Ok(this.hash().map(str::to_string)));
+        fields.add_field_method_get("title", |_, this| Ok(this.title().map(str::to_string)));
     }
 }
 
@@ -61,5 +205,6 @@ impl UserData for DirEntry {
     fn add_fields<'lua, F: mlua::UserDataFields<'lua, Self>>(fields: &mut F) {
         fields.add_field_method_get("path", |_, this| Ok(this.path().clone()));
         fields.add_field_method_get("metadata", |_, this| Ok(this.metadata().clone()));
+        fields.add_field_method_get("mount", |_, this| Ok(this.mount().cloned()));
     }
 }
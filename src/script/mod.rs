@@ -1,10 +1,16 @@
-use std::error::Error;
-
 mod fs;
 
-use mlua::Lua;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use mlua::{HookTriggers, Lua, LuaOptions, LuaSerdeExt, StdLib};
+
+use crate::{error::Result, Emerald};
 
-use crate::Emerald;
+/// The number of instructions between two checks of the instruction limit.
+const INSTRUCTION_STEP: u32 = 1000;
 
 #[derive(Default)]
 pub struct ScriptEngine {}
@@ -15,10 +21,49 @@ impl ScriptEngine {
         Self {}
     }
     /// Create a new execution context.
-    pub fn new_instance(&mut self, emerald: &Emerald) -> Result<Instance, Box<dyn Error>> {
-        let lua = Lua::new();
+    ///
+    /// The sandbox limits of the jewel's configuration apply to the instance.
+    /// The instance of a restricted handle has no `io` and `os` libraries, nor `dofile`
+    /// and `loadfile`, which would reach the host files around the access checks.
+    pub fn new_instance(&mut self, emerald: &Emerald) -> Result<Instance> {
+        let lua = match emerald.access().is_unrestricted() {
+            true => Lua::new(),
+            false => {
+                let lua = Lua::new_with(
+                    StdLib::ALL_SAFE ^ (StdLib::IO | StdLib::OS),
+                    LuaOptions::default(),
+                )?;
+                lua.globals().set("dofile", mlua::Nil)?;
+                lua.globals().set("loadfile", mlua::Nil)?;
+                lua
+            }
+        };
+        let config = &emerald.config().scripts;
+        let instructions = Arc::new(AtomicU64::new(0));
+
+        if let Some(limit) = config.memory_limit {
+            lua.set_memory_limit(limit)?;
+        }
+
+        if let Some(limit) = config.instruction_limit {
+            let count = instructions.clone();
+            let triggers = HookTriggers::new().every_nth_instruction(INSTRUCTION_STEP);
+
+            lua.set_hook(triggers, move |_, _| {
+                let run = count.fetch_add(INSTRUCTION_STEP as u64, Ordering::Relaxed);
+
+                if run >= limit {
+                    return Err(mlua::Error::runtime(
+                        "the script exceeded its instruction limit",
+                    ));
+                }
+
+                Ok(())
+            });
+        }
+
         Context::inject(&lua, emerald)?;
-        Ok(Instance { lua })
+        Ok(Instance { lua, instructions })
     }
 }
 
@@ -33,30 +78,58 @@ impl Context {
             emerald: emerald.clone(),
         }
     }
-    pub fn inject(lua: &Lua, emerald: &Emerald) -> Result<(), Box<dyn Error>> {
+    pub fn inject(lua: &Lua, emerald: &Emerald) -> Result<()> {
         lua.set_app_data(Self::new(emerald));
-        Self::bind(lua)?;
+        Self::bind(lua, emerald)?;
         Ok(())
     }
 
-    fn bind(lua: &Lua) -> Result<(), Box<dyn Error>> {
+    fn bind(lua: &Lua, emerald: &Emerald) -> Result<()> {
         let api = lua.create_table()?;
         let fs = fs::create_fs_table(lua)?;
 
         api.set("fs", fs)?;
 
+        api.set("config", lua.to_value(emerald.config())?)?;
+        Self::add_script_dirs(lua, emerald)?;
+
         lua.globals().set("emerald", api)?;
 
         Ok(())
     }
+
+    /// Prepend the script directories of the configuration to the search path of `require`.
+    ///
+    /// Only the directories located on the storage can be searched.
+    fn add_script_dirs(lua: &Lua, emerald: &Emerald) -> Result<()> {
+        let package = lua.globals().get::<_, mlua::Table>("package")?;
+        let mut search = package.get::<_, String>("path")?;
+
+        for dir in emerald.config().scripts.dirs.iter().rev() {
+            if let Ok(canon) = crate::fs::canonicalize(emerald, dir) {
+                search = format!(
+                    "{}/?.lua;{}/?/init.lua;{}",
+                    canon.display(),
+                    canon.display(),
+                    search
+                );
+            }
+        }
+
+        package.set("path", search)?;
+        Ok(())
+    }
 }
 
 pub struct Instance {
     lua: Lua,
+    /// The instructions run by the current execution.
+    instructions: Arc<AtomicU64>,
 }
 
 impl Instance {
-    pub fn execute(&self, code: &str) -> Result<(), Box<dyn Error>> {
+    pub fn execute(&self, code: &str) -> Result<()> {
+        self.instructions.store(0, Ordering::Relaxed);
         self.lua.load(code).exec()?;
         Ok(())
     }
@@ -1,3 +1,11 @@
+use indexmap::IndexMap;
+use markdown::{mdast, unist::Point};
+
+use crate::{
+    error::{Error, Result},
+    shard::{value::Number, Value},
+};
+
 #[derive(Debug, Clone)]
 /// The format of the frontmatter
 pub enum FrontMatterFormat {
@@ -7,37 +15,79 @@ pub enum FrontMatterFormat {
 
 impl std::fmt::Display for FrontMatter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let val = crate::shard::Value::Map(self.properties.clone());
+        let val = Value::Map(self.properties.clone());
         match self.format {
             FrontMatterFormat::Yaml => {
-                write!(f, "{}", serde_yaml::to_string(&val).unwrap())
+                let yaml = serde_yaml::to_string(&serde_yaml::Value::from(val))
+                    .map_err(|_| std::fmt::Error)?;
+                write!(f, "{}", yaml)
+            }
+            FrontMatterFormat::Toml => {
+                let table = self
+                    .properties
+                    .iter()
+                    .filter_map(|(key, value)| Some((key.clone(), toml_value(value)?)))
+                    .collect::<toml::Table>();
+                let toml = toml::to_string(&table).map_err(|_| std::fmt::Error)?;
+                write!(f, "{}", toml)
             }
-            _ => todo!("Implements Toml serialization for Frontmatter's fmt."),
         }
     }
 }
 
-impl From<serde_yaml::Value> for FrontMatter {
-    fn from(value: serde_yaml::Value) -> Self {
-        Self {
+/// Convert the value into TOML, which has no null: the null values are dropped.
+fn toml_value(value: &Value) -> Option<toml::Value> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Boolean(value) => toml::Value::Boolean(*value),
+        Value::String(value) => toml::Value::String(value.clone()),
+        Value::Number(Number::Integer(value)) => toml::Value::Integer(*value),
+        Value::Number(Number::Float(value)) => toml::Value::Float(*value),
+        Value::Array(values) => toml::Value::Array(values.iter().filter_map(toml_value).collect()),
+        Value::Map(map) => toml::Value::Table(
+            map.iter()
+                .filter_map(|(key, value)| Some((key.clone(), toml_value(value)?)))
+                .collect(),
+        ),
+    })
+}
+
+impl TryFrom<serde_yaml::Value> for FrontMatter {
+    type Error = Error;
+
+    fn try_from(value: serde_yaml::Value) -> Result<Self> {
+        Ok(Self {
             format: FrontMatterFormat::Yaml,
-            value: value.into(),
-        }
+            properties: properties(Value::try_from(value)?)?,
+        })
     }
 }
 
-impl From<toml::Value> for FrontMatter {
-    fn from(value: toml::Value) -> Self {
-        Self {
+impl TryFrom<toml::Value> for FrontMatter {
+    type Error = Error;
+
+    fn try_from(value: toml::Value) -> Result<Self> {
+        Ok(Self {
             format: FrontMatterFormat::Toml,
-            value: value.into(),
-        }
+            properties: properties(Value::from(value))?,
+        })
     }
 }
+
+/// The root of the frontmatter must be a map, an empty frontmatter has no properties.
+fn properties(value: Value) -> Result<IndexMap<String, Value>> {
+    match value {
+        Value::Null => Ok(IndexMap::default()),
+        value => value
+            .into_map()
+            .ok_or_else(|| Error::frontmatter("the frontmatter is not a map")),
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Holds the metadata of the shard.
 ///
-/// ```
+/// ```yaml
 /// ---
 /// title: The title of the shard
 /// ---
@@ -49,16 +99,58 @@ pub struct FrontMatter {
     pub properties: IndexMap<String, crate::shard::Value>,
 }
 
-impl TryFrom<mdast::Yaml> for FrontMatter {
-    type Error = Box<dyn Error>;
+/// Locate the error at the start of the frontmatter's node.
+fn locate(err: Error, start: Option<&Point>) -> Error {
+    match err {
+        Error::FrontMatter {
+            message,
+            position: None,
+        } => Error::FrontMatter {
+            message,
+            position: start.cloned(),
+        },
+        err => err,
+    }
+}
 
-    fn try_from(value: mdast::Yaml) -> Result<Self, Self::Error> {
-        let yaml = serde_yaml::from_str(&value.value)?;
-        let value = crate::shard::Value::from(yaml);
+impl TryFrom<&mdast::Yaml> for FrontMatter {
+    type Error = Error;
 
-        Ok(Self {
-            format: FrontMatterFormat::Yaml,
-            properties: value.expect_map(),
-        })
+    fn try_from(value: &mdast::Yaml) -> Result<Self> {
+        let start = value.position.as_ref().map(|position| &position.start);
+
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&value.value).map_err(|err| {
+            // The YAML content starts on the line after the opening fence.
+            let position = match (start, err.location()) {
+                (Some(start), Some(location)) => Some(Point::new(
+                    start.line + location.line(),
+                    location.column(),
+                    start.offset + "---\n".len() + location.index(),
+                )),
+                _ => start.cloned(),
+            };
+
+            Error::FrontMatter {
+                message: err.to_string(),
+                position,
+            }
+        })?;
+
+        Self::try_from(yaml).map_err(|err| locate(err, start))
+    }
+}
+
+impl TryFrom<&mdast::Toml> for FrontMatter {
+    type Error = Error;
+
+    fn try_from(value: &mdast::Toml) -> Result<Self> {
+        let start = value.position.as_ref().map(|position| &position.start);
+
+        let toml: toml::Value = toml::from_str(&value.value).map_err(|err| Error::FrontMatter {
+            message: err.message().to_string(),
+            position: start.cloned(),
+        })?;
+
+        Self::try_from(toml).map_err(|err| locate(err, start))
     }
 }
@@ -0,0 +1,483 @@
+//! Scanner of the extensions of the text: the Dataview-style inline fields,
+//! the wikilinks, the embeds and the tags.
+//!
+//! ```markdown
+//! status:: draft
+//! Reviewed by [reviewer:: Alice] (due:: 2024-01-01), see [[Meetings#Monday|the meeting]]
+//! ![[diagram.png]] #project/emerald
+//! ```
+use std::ops::Range;
+
+use super::{InlineField, InlineFieldKind, WikiLink, WikiLinkAnchor};
+
+/// A part of the text.
+#[derive(Debug, Clone)]
+pub(crate) enum Segment {
+    Text(Range<usize>),
+    Field(InlineField, Range<usize>),
+    WikiLink(WikiLink, Range<usize>),
+    Embed(WikiLink, Range<usize>),
+    /// The name of the tag, without the `#`.
+    Tag(String, Range<usize>),
+}
+
+/// Split the text into plain text, inline fields, wikilinks, embeds and tags.
+///
+/// The line form of the inline fields is only recognized at the start of a line,
+/// `line_start` tells if the text starts a line. The value of a field is kept verbatim.
+///
+/// The source is the markdown the text was parsed from, if known: the brackets and the `#`
+/// escaped with a backslash, as `\[\[note\]\]` or `\#tag`, start no wikilink nor tag.
+pub(crate) fn split(text: &str, source: Option<&str>, line_start: bool) -> Vec<Segment> {
+    let escaped = escapes(text, source);
+
+    split_fields(text, &escaped, line_start)
+        .into_iter()
+        .flat_map(|segment| match segment {
+            Segment::Text(range) => split_wikilinks(text, &escaped, range),
+            segment => vec![segment],
+        })
+        .flat_map(|segment| match segment {
+            Segment::Text(range) => split_tags(text, &escaped, range),
+            segment => vec![segment],
+        })
+        .collect()
+}
+
+/// Flags the bytes of the text escaped with a backslash in the source.
+///
+/// The source is aligned with the text: the backslash escapes and the character references
+/// are decoded, the characters of the source missing from the text, such as the prefixes
+/// of the continuation lines, are skipped.
+fn escapes(text: &str, source: Option<&str>) -> Vec<bool> {
+    let mut escaped = vec![false; text.len()];
+    let Some(mut rest) = source else {
+        return escaped;
+    };
+
+    for (index, c) in text.char_indices() {
+        loop {
+            if let Some(after) = rest
+                .strip_prefix('\\')
+                .filter(|after| c.is_ascii_punctuation() && after.starts_with(c))
+            {
+                escaped[index] = true;
+                rest = &after[1..];
+                break;
+            }
+
+            if let Some(length) = reference(rest).filter(|_| c != '&' || rest.starts_with("&amp;"))
+            {
+                rest = &rest[length..];
+                break;
+            }
+
+            if let Some(after) = rest.strip_prefix(c) {
+                rest = after;
+                break;
+            }
+
+            let mut chars = rest.chars();
+
+            if chars.next().is_none() {
+                return escaped;
+            }
+
+            rest = chars.as_str();
+        }
+    }
+
+    escaped
+}
+
+/// The length of the character reference starting the source, as `&amp;` or `&#35;`.
+fn reference(source: &str) -> Option<usize> {
+    let body = source.strip_prefix('&')?;
+    let end = body.find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))?;
+    (end > 0 && body[end..].starts_with(';')).then_some(end + 2)
+}
+
+fn split_fields(text: &str, escaped: &[bool], line_start: bool) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut plain = 0;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let starts_line = offset > 0 || line_start;
+        let end = offset + line.trim_end_matches('\n').len();
+
+        if let Some(field) = starts_line
+            .then(|| line_field(&text[offset..end]))
+            .flatten()
+        {
+            if plain < offset {
+                segments.push(Segment::Text(plain..offset));
+            }
+            segments.push(Segment::Field(field, offset..end));
+            plain = end;
+        } else {
+            let mut cursor = offset;
+
+            while let Some((field, range)) = next_wrapped_field(text, escaped, cursor, end) {
+                if plain < range.start {
+                    segments.push(Segment::Text(plain..range.start));
+                }
+                cursor = range.end;
+                plain = range.end;
+                segments.push(Segment::Field(field, range));
+            }
+        }
+
+        offset += line.len();
+    }
+
+    if plain < text.len() {
+        segments.push(Segment::Text(plain..text.len()));
+    }
+
+    segments
+}
+
+/// Split the range of the text into plain text, wikilinks and embeds.
+fn split_wikilinks(text: &str, escaped: &[bool], range: Range<usize>) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut plain = range.start;
+    let mut cursor = range.start;
+
+    while let Some(found) = text[cursor..range.end].find("[[") {
+        let open = cursor + found;
+        cursor = open + 2;
+
+        let Some(length) = text[cursor..range.end].find("]]") else {
+            break;
+        };
+        let close = cursor + length;
+
+        if escaped[open..open + 2].contains(&true) || escaped[close..close + 2].contains(&true) {
+            continue;
+        }
+
+        let Some(link) = wikilink(&text[cursor..close]) else {
+            continue;
+        };
+
+        let embed = open > range.start && text.as_bytes()[open - 1] == b'!' && !escaped[open - 1];
+        let start = if embed { open - 1 } else { open };
+
+        if plain < start {
+            segments.push(Segment::Text(plain..start));
+        }
+
+        segments.push(match embed {
+            true => Segment::Embed(link, start..close + 2),
+            false => Segment::WikiLink(link, start..close + 2),
+        });
+        cursor = close + 2;
+        plain = cursor;
+    }
+
+    if plain < range.end {
+        segments.push(Segment::Text(plain..range.end));
+    }
+
+    segments
+}
+
+/// Split the range of the text into plain text and tags.
+///
+/// A tag starts a word, so that the `#` of an URL is not a tag,
+/// and is not only made of digits, as `#1`.
+fn split_tags(text: &str, escaped: &[bool], range: Range<usize>) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut plain = range.start;
+    let mut cursor = range.start;
+
+    while let Some(found) = text[cursor..range.end].find('#') {
+        let start = cursor + found;
+        cursor = start + 1;
+
+        let starts_word = text[range.start..start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| c.is_whitespace() || matches!(c, '(' | ',' | ';'));
+
+        let length = text[cursor..range.end]
+            .find(|c: char| !is_tag_char(c))
+            .unwrap_or(range.end - cursor);
+        let name = text[cursor..cursor + length].trim_end_matches('/');
+
+        if !starts_word
+            || escaped[start]
+            || name.is_empty()
+            || name.chars().all(|c| c.is_ascii_digit())
+        {
+            continue;
+        }
+
+        if plain < start {
+            segments.push(Segment::Text(plain..start));
+        }
+
+        cursor += name.len();
+        plain = cursor;
+        segments.push(Segment::Tag(name.to_string(), start..cursor));
+    }
+
+    if plain < range.end {
+        segments.push(Segment::Text(plain..range.end));
+    }
+
+    segments
+}
+
+/// The characters of a tag's name, `/` separates the nested tags.
+pub(crate) fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// Parse the inside of the brackets: `target#anchor|alias`.
+///
+/// The `|` may be escaped, as within a table.
+fn wikilink(inner: &str) -> Option<WikiLink> {
+    if inner.trim().is_empty() || inner.contains(['[', ']', '\n']) {
+        return None;
+    }
+
+    let (reference, alias) = match inner.split_once('|') {
+        Some((reference, alias)) => (
+            reference.strip_suffix('\\').unwrap_or(reference),
+            Some(alias),
+        ),
+        None => (inner, None),
+    };
+
+    let (target, anchor) = match reference.split_once('#') {
+        Some((target, anchor)) => (target, Some(anchor.trim())),
+        None => (reference, None),
+    };
+
+    let anchor =
+        anchor
+            .filter(|anchor| !anchor.is_empty())
+            .map(|anchor| match anchor.strip_prefix('^') {
+                Some(block) => WikiLinkAnchor::Block(block.to_string()),
+                None => WikiLinkAnchor::Heading(anchor.to_string()),
+            });
+
+    Some(WikiLink {
+        target: target.trim().to_string(),
+        anchor,
+        alias: alias.map(|alias| alias.trim().to_string()),
+    })
+}
+
+/// A whole line `key:: value`.
+fn line_field(line: &str) -> Option<InlineField> {
+    let (key, value) = line.split_once("::")?;
+    field(key, value, InlineFieldKind::Line)
+}
+
+/// The next `[key:: value]` or `(key:: value)` within the range of the text.
+fn next_wrapped_field(
+    text: &str,
+    escaped: &[bool],
+    mut cursor: usize,
+    end: usize,
+) -> Option<(InlineField, Range<usize>)> {
+    while let Some(found) = text[cursor..end].find(['[', '(']) {
+        let start = cursor + found;
+
+        if !escaped[start] {
+            if let Some((field, length)) = wrapped_field(&text[start..end]) {
+                return Some((field, start..start + length));
+            }
+        }
+
+        cursor = start + 1;
+    }
+
+    None
+}
+
+/// Returns true if the text starts with a `[key:: value]` or `(key:: value)` on its first line.
+pub(crate) fn starts_field(text: &str) -> bool {
+    wrapped_field(text.split('\n').next().unwrap_or_default()).is_some()
+}
+
+/// The `[key:: value]` or `(key:: value)` starting the text, and its length.
+fn wrapped_field(text: &str) -> Option<(InlineField, usize)> {
+    let (open, close, kind) = match text.as_bytes().first()? {
+        b'[' => (b'[', b']', InlineFieldKind::Bracket),
+        b'(' => (b'(', b')', InlineFieldKind::Paren),
+        _ => return None,
+    };
+
+    let length = closing(text.as_bytes(), open, close)?;
+    let (key, value) = text[1..length - 1].split_once("::")?;
+    Some((field(key, value, kind)?, length))
+}
+
+/// The length up to the matching closing bracket, the nested brackets are balanced.
+fn closing(bytes: &[u8], open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+
+    for (index, &byte) in bytes.iter().enumerate() {
+        if byte == open {
+            depth += 1;
+        } else if byte == close {
+            depth -= 1;
+
+            if depth == 0 {
+                return Some(index + 1);
+            }
+        }
+    }
+
+    None
+}
+
+/// The key is not empty and holds no bracket nor markup, the `::` is followed by a space
+/// so that `std::fs` is not a field.
+fn field(key: &str, value: &str, kind: InlineFieldKind) -> Option<InlineField> {
+    let key = key.trim();
+    let invalid = |c: char| matches!(c, '[' | ']' | '(' | ')' | '`' | '*' | '>');
+
+    if key.is_empty() || key.contains(invalid) || key.starts_with(['#', '-', '+']) {
+        return None;
+    }
+
+    if value.starts_with(|c: char| !c.is_whitespace()) {
+        return None;
+    }
+
+    Some(InlineField {
+        key: key.to_string(),
+        value: value.trim().to_string(),
+        kind,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split, Segment, WikiLinkAnchor};
+
+    fn fields(text: &str, line_start: bool) -> Vec<(String, String)> {
+        split(text, None, line_start)
+            .into_iter()
+            .filter_map(|segment| match segment {
+                Segment::Field(field, _) => Some((field.key, field.value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn split_fields() {
+        assert_eq!(
+            fields("status:: draft\nBy [author:: Alice] (due:: soon)", true),
+            vec![
+                ("status".into(), "draft".into()),
+                ("author".into(), "Alice".into()),
+                ("due".into(), "soon".into()),
+            ]
+        );
+
+        // The line form is only recognized at the start of a line.
+        assert!(fields("status:: draft", false).is_empty());
+        assert!(fields("[[link]] [no field] (std::fs)", true).is_empty());
+        assert_eq!(
+            fields("[key:: [nested] value]", true),
+            vec![("key".into(), "[nested] value".into())]
+        );
+    }
+
+    #[test]
+    fn split_wikilinks() {
+        let text = "See [[Meetings#Monday|the meeting]], ![[diagram.png]] and [[#^block]] [[ ]]";
+        let segments = split(text, None, true);
+        let ranges = segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(range) => ("text", &text[range.clone()]),
+                Segment::WikiLink(_, range) => ("link", &text[range.clone()]),
+                Segment::Embed(_, range) => ("embed", &text[range.clone()]),
+                Segment::Field(_, range) => ("field", &text[range.clone()]),
+                Segment::Tag(_, range) => ("tag", &text[range.clone()]),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            ranges,
+            vec![
+                ("text", "See "),
+                ("link", "[[Meetings#Monday|the meeting]]"),
+                ("text", ", "),
+                ("embed", "![[diagram.png]]"),
+                ("text", " and "),
+                ("link", "[[#^block]]"),
+                ("text", " [[ ]]"),
+            ]
+        );
+
+        let Segment::WikiLink(link, _) = &segments[1] else {
+            panic!("not a wikilink");
+        };
+        assert_eq!(link.target, "Meetings");
+        assert_eq!(link.anchor, Some(WikiLinkAnchor::Heading("Monday".into())));
+        assert_eq!(link.alias.as_deref(), Some("the meeting"));
+
+        // The value of a field is kept verbatim.
+        assert!(matches!(
+            &split("[related:: [[Alpha]]]", None, true)[..],
+            [Segment::Field(field, _)] if field.value == "[[Alpha]]"
+        ));
+    }
+
+    #[test]
+    fn split_tags() {
+        let tags = |text: &str| {
+            split(text, None, true)
+                .into_iter()
+                .filter_map(|segment| match segment {
+                    Segment::Tag(name, _) => Some(name),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            tags("#todo, (#area/sub-area/) and #été"),
+            vec!["todo", "area/sub-area", "été"]
+        );
+        assert!(tags("issue #1, page.html#anchor, # heading, C#").is_empty());
+    }
+
+    #[test]
+    fn split_escaped() {
+        let kinds = |text: &str, source: &str| {
+            split(text, Some(source), true)
+                .into_iter()
+                .map(|segment| match segment {
+                    Segment::Text(_) => "text",
+                    Segment::Field(..) => "field",
+                    Segment::WikiLink(..) => "link",
+                    Segment::Embed(..) => "embed",
+                    Segment::Tag(..) => "tag",
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(kinds("#tag [[x]]", "\\#tag \\[\\[x\\]\\]"), vec!["text"]);
+        assert_eq!(
+            kinds("[[x]] [key:: value]", "[[x\\]\\] \\[key:: value]"),
+            vec!["text"]
+        );
+        assert_eq!(kinds("![[x]]", "\\![[x]]"), vec!["text", "link"]);
+
+        // The references and the continuation lines of the source are skipped.
+        assert_eq!(
+            kinds("a & b\n#tag", "a &amp; b\n> #tag"),
+            vec!["text", "tag"]
+        );
+    }
+}
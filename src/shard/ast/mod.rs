@@ -1,5 +1,9 @@
 pub mod frontmatter;
+mod inline;
 pub mod node;
+pub mod table;
 pub mod walker;
 
+pub use frontmatter::*;
 pub use node::*;
+pub use table::*;
@@ -1,10 +1,26 @@
-use std::{error::Error, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    str::FromStr,
+};
 
-use markdown::{to_mdast, Constructs, ParseOptions};
+use indexmap::IndexMap;
+use markdown::{mdast, to_mdast, unist::Point, Constructs, ParseOptions};
 
 pub use markdown::unist::Position;
 
-use crate::shard::ast::walker::RefWalker;
+use crate::{
+    config::MarkdownConfig,
+    error::{Error, Result},
+    shard::{
+        ast::{
+            inline::{self, Segment},
+            walker::RefWalker,
+            FrontMatter, InlineField, Table, TableView,
+        },
+        Value,
+    },
+};
 
 use super::{
     r#ref::{NodeMut, NodeRef},
@@ -15,10 +31,17 @@ pub type NodeIndex = generational_arena::Index;
 type Arena = generational_arena::Arena<Node>;
 
 /// An AST owned by an arena.
+///
+/// The AST keeps the parsed source, so that the untouched nodes are serialized
+/// as they were written, see the [std::fmt::Display] implementation.
 #[derive(Default)]
 pub struct Ast {
     arena: Arena,
     root: Option<NodeIndex>,
+    /// The parsed source, the positions of the nodes are within it.
+    source: String,
+    /// The nodes borrowed mutably, which are serialized from their attributes.
+    modified: HashSet<NodeIndex>,
 }
 
 impl super::traits::NodeConverter for Ast {
@@ -33,13 +56,14 @@ impl super::traits::NodeConverter for Ast {
 }
 
 impl FromStr for Ast {
-    type Err = Box<dyn Error>;
+    type Err = Error;
 
     /// Build the shard AST from string.
     ///
     /// ```
-    /// let content = "
-    /// ---
+    /// use emerald::shard::Shard;
+    ///
+    /// let content = "---
     /// title: My shard
     /// date: 01/01/01
     /// ---
@@ -47,28 +71,190 @@ impl FromStr for Ast {
     /// This is a content [property:: value]
     /// ";
     ///
-    /// Ast::from_str(&content)
+    /// let shard: Shard = content.parse().unwrap();
+    /// assert_eq!(shard.title().as_deref(), Some("My shard"));
     /// ```
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let constructs = Constructs {
-            frontmatter: true,
-            ..Constructs::gfm()
-        };
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s, MarkdownConfig::default().constructs())
+    }
+}
 
+impl Ast {
+    /// Build the shard AST from string, with the markdown constructs.
+    pub fn parse(s: &str, constructs: Constructs) -> Result<Self> {
         let options = ParseOptions {
             constructs,
             ..ParseOptions::default()
         };
 
-        let tree = to_mdast(s, &options)?;
+        let tree = to_mdast(s, &options).map_err(|message| parse_error(s, message))?;
 
-        let mut ast = Self::default();
+        // The converter drops an invalid frontmatter, report it instead.
+        match tree.children().and_then(|children| children.first()) {
+            Some(mdast::Node::Yaml(yaml)) => {
+                FrontMatter::try_from(yaml)?;
+            }
+            Some(mdast::Node::Toml(toml)) => {
+                FrontMatter::try_from(toml)?;
+            }
+            _ => {}
+        }
+
+        let mut ast = Self {
+            source: s.to_string(),
+            ..Self::default()
+        };
         ast.root = ast.convert(tree);
+        ast.split_inline();
         Ok(ast)
     }
 }
 
+/// The position of a part of the text, when the text is verbatim in the source.
+fn sub_position(text: &str, position: Option<&Position>, range: Range<usize>) -> Option<Position> {
+    let position = position?;
+
+    if position.end.offset - position.start.offset != text.len() {
+        return None;
+    }
+
+    let point = |index: usize| {
+        let prefix = &text[..index];
+        let offset = position.start.offset + index;
+
+        match prefix.rfind('\n') {
+            Some(newline) => Point::new(
+                position.start.line + prefix.matches('\n').count(),
+                index - newline,
+                offset,
+            ),
+            None => Point::new(position.start.line, position.start.column + index, offset),
+        }
+    };
+
+    Some(Position {
+        start: point(range.start),
+        end: point(range.end),
+    })
+}
+
+/// Build the parse error from the `line:column: reason` message of the parser.
+fn parse_error(s: &str, message: String) -> Error {
+    let located = message.split_once(": ").and_then(|(point, reason)| {
+        let (line, column) = point.split_once(':')?;
+        let (line, column) = (line.parse::<usize>().ok()?, column.parse::<usize>().ok()?);
+        let offset = s
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum::<usize>()
+            + column.saturating_sub(1);
+
+        Some((Point::new(line, column, offset), reason.to_string()))
+    });
+
+    match located {
+        Some((point, reason)) => Error::Parse {
+            message: reason,
+            position: Some(point),
+        },
+        None => Error::Parse {
+            message,
+            position: None,
+        },
+    }
+}
+
 impl Ast {
+    /// Split the inline fields, the wikilinks, the embeds and the tags out of the text
+    /// of the paragraphs, the headings and the phrasing content.
+    fn split_inline(&mut self) {
+        let containers = self
+            .arena
+            .iter()
+            .filter_map(|(index, node)| match node.attributes {
+                super::NodeAttributes::Paragraph => Some((index, true)),
+                super::NodeAttributes::Heading(_)
+                | super::NodeAttributes::Emphasis
+                | super::NodeAttributes::Strong
+                | super::NodeAttributes::Delete
+                | super::NodeAttributes::TableCell => Some((index, false)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for (container, mut line_start) in containers {
+            let children = std::mem::take(&mut self.arena[container].children);
+            let mut split = Vec::with_capacity(children.len());
+
+            for child in children {
+                let node = &self.arena[child];
+                let super::NodeAttributes::Text(text) = &node.attributes else {
+                    line_start = matches!(node.attributes, super::NodeAttributes::Break);
+                    split.push(child);
+                    continue;
+                };
+
+                let source = node.position.as_ref().and_then(|position| {
+                    self.source.get(position.start.offset..position.end.offset)
+                });
+                let segments = inline::split(text, source, line_start);
+                line_start = text.ends_with('\n');
+
+                if matches!(segments[..], [] | [Segment::Text(_)]) {
+                    split.push(child);
+                    continue;
+                }
+
+                let Some(node) = self.arena.remove(child) else {
+                    continue;
+                };
+                let super::NodeAttributes::Text(text) = node.attributes else {
+                    continue;
+                };
+
+                for segment in segments {
+                    let (attributes, r#type, range) = match segment {
+                        Segment::Text(range) => (
+                            super::NodeAttributes::Text(text[range.clone()].to_string()),
+                            super::NodeType::Text,
+                            range,
+                        ),
+                        Segment::Field(field, range) => (
+                            super::NodeAttributes::InlineField(field),
+                            super::NodeType::InlineField,
+                            range,
+                        ),
+                        Segment::WikiLink(link, range) => (
+                            super::NodeAttributes::WikiLink(link),
+                            super::NodeType::WikiLink,
+                            range,
+                        ),
+                        Segment::Embed(link, range) => (
+                            super::NodeAttributes::Embed(link),
+                            super::NodeType::Embed,
+                            range,
+                        ),
+                        Segment::Tag(name, range) => (
+                            super::NodeAttributes::Tag(name),
+                            super::NodeType::Tag,
+                            range,
+                        ),
+                    };
+
+                    split.push(self.arena.insert(Node {
+                        position: sub_position(&text, node.position.as_ref(), range),
+                        children: vec![],
+                        attributes,
+                        r#type,
+                    }));
+                }
+            }
+
+            self.arena[container].children = split;
+        }
+    }
+
     pub fn walk_ref(&self) -> RefWalker<'_> {
         RefWalker::new(self, self.root)
     }
@@ -79,7 +265,10 @@ impl Ast {
 
     /// Copy the entire tree into a new tree.
     pub fn fork(&self, from: NodeIndex) -> Ast {
-        let mut forked = Self::default();
+        let mut forked = Self {
+            source: self.source.clone(),
+            ..Self::default()
+        };
         forked.root = self.fork_node(&mut forked, from);
         forked
     }
@@ -109,12 +298,409 @@ impl Ast {
                 r#type: node.r#type,
             };
 
-            return Some(to.insert_node(node));
+            let index = to.insert_node(node);
+
+            if self.modified.contains(&src) {
+                to.modified.insert(index);
+            }
+
+            return Some(index);
         }
 
         None
     }
 
+    /// The title of the document: the `title` property of the frontmatter,
+    /// or else the text of the first heading.
+    pub fn title(&self) -> Option<String> {
+        let root = self.get_root()?;
+        let mut heading = None;
+
+        for child in root.children.iter().flat_map(|&child| self.get(child)) {
+            match &child.attributes {
+                super::NodeAttributes::FrontMatter(frontmatter) => {
+                    if let Some(crate::shard::Value::String(title)) =
+                        frontmatter.properties.get("title")
+                    {
+                        return Some(title.clone());
+                    }
+                }
+                super::NodeAttributes::Heading(_) if heading.is_none() => {
+                    heading = Some(child.index);
+                }
+                _ => {}
+            }
+        }
+
+        let mut title = String::default();
+        self.push_text(heading?, &mut title);
+        Some(title.trim().to_string())
+    }
+
+    /// The inline fields of the document, in document order.
+    pub fn inline_fields(&self) -> Vec<&InlineField> {
+        let mut fields = vec![];
+
+        if let Some(root) = self.root {
+            self.push_inline_fields(root, &mut fields);
+        }
+
+        fields
+    }
+
+    fn push_inline_fields<'tree>(
+        &'tree self,
+        index: NodeIndex,
+        fields: &mut Vec<&'tree InlineField>,
+    ) {
+        let Some(node) = self.arena.get(index) else {
+            return;
+        };
+
+        if let super::NodeAttributes::InlineField(field) = &node.attributes {
+            fields.push(field);
+        }
+
+        for &child in &node.children {
+            self.push_inline_fields(child, fields);
+        }
+    }
+
+    /// The tags of the document: the `tags` of the frontmatter, then the tags
+    /// of the content in document order, without duplicates.
+    ///
+    /// The frontmatter's `tags` is a list, or a string separated by commas or spaces,
+    /// the `#` of its tags is optional.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = Vec::<String>::default();
+        let mut push = |tag: &str| {
+            let tag = tag.trim().trim_start_matches('#');
+
+            if !tag.is_empty() && !tags.iter().any(|known| known == tag) {
+                tags.push(tag.to_string());
+            }
+        };
+
+        if let Some(properties) = self.frontmatter_properties() {
+            match properties.get("tags") {
+                Some(Value::Array(values)) => {
+                    for value in values {
+                        match value {
+                            Value::String(tag) => push(tag),
+                            Value::Number(tag) => push(&tag.to_string()),
+                            _ => {}
+                        }
+                    }
+                }
+                Some(Value::String(value)) => {
+                    value.split([',', ' ']).for_each(&mut push);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(root) = self.root {
+            self.push_tags(root, &mut push);
+        }
+
+        tags
+    }
+
+    fn push_tags(&self, index: NodeIndex, push: &mut impl FnMut(&str)) {
+        let Some(node) = self.arena.get(index) else {
+            return;
+        };
+
+        if let super::NodeAttributes::Tag(name) = &node.attributes {
+            push(name);
+        }
+
+        for &child in &node.children {
+            self.push_tags(child, push);
+        }
+    }
+
+    /// The properties of the frontmatter, if any.
+    fn frontmatter_properties(&self) -> Option<IndexMap<String, Value>> {
+        self.get_root()?
+            .iter_children()
+            .find_map(|child| match &child.attributes {
+                super::NodeAttributes::FrontMatter(frontmatter) => {
+                    Some(frontmatter.properties.clone())
+                }
+                _ => None,
+            })
+    }
+
+    /// The properties of the document: the properties of the frontmatter,
+    /// then the inline fields in document order.
+    ///
+    /// The values of a repeated key are accumulated into an array.
+    pub fn properties(&self) -> Value {
+        let mut properties = self.frontmatter_properties().unwrap_or_default();
+
+        for field in self.inline_fields() {
+            Value::accumulate(
+                &mut properties,
+                &field.key,
+                Value::from_inline(&field.value),
+            );
+        }
+
+        Value::Map(properties)
+    }
+
+    /// The tables of the document, in document order.
+    pub fn tables(&self) -> Vec<(NodeIndex, TableView)> {
+        let mut tables = vec![];
+
+        if let Some(root) = self.root {
+            self.push_tables(root, &mut tables);
+        }
+
+        tables
+    }
+
+    fn push_tables(&self, index: NodeIndex, tables: &mut Vec<(NodeIndex, TableView)>) {
+        let Some(node) = self.arena.get(index) else {
+            return;
+        };
+
+        if let Some(table) = self.table(index) {
+            tables.push((index, table));
+        }
+
+        for &child in &node.children {
+            self.push_tables(child, tables);
+        }
+    }
+
+    /// The view of the table, or None if the node is not a table.
+    ///
+    /// The first row is the header, the cells hold their text without markup.
+    ///
+    /// ```
+    /// use emerald::shard::ast::Ast;
+    ///
+    /// let ast: Ast = "| Name | Rating |\n| --- | --: |\n| **Alpha** | 4 |\n".parse().unwrap();
+    /// let (_, table) = &ast.tables()[0];
+    ///
+    /// assert_eq!(table.header, vec!["Name", "Rating"]);
+    /// assert_eq!(table.rows, vec![vec!["Alpha", "4"]]);
+    /// ```
+    pub fn table(&self, index: NodeIndex) -> Option<TableView> {
+        let node = self.get(index)?;
+        let super::NodeAttributes::Table(table) = &node.attributes else {
+            return None;
+        };
+
+        let cells = node
+            .children
+            .iter()
+            .map(|&row| {
+                self.get(row)
+                    .map(|row| row.children.clone())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        let mut rows = cells.iter().map(|row| {
+            row.iter()
+                .map(|&cell| self.cell_text(cell))
+                .collect::<Vec<_>>()
+        });
+
+        Some(TableView {
+            header: rows.next().unwrap_or_default(),
+            rows: rows.collect(),
+            align: table.align.clone(),
+            cells,
+        })
+    }
+
+    /// Replace the content of the table by the view.
+    ///
+    /// The rows are cut or padded to the width of the header, the newlines of
+    /// the cells are replaced by spaces. The table is then serialized from the view,
+    /// the cells whose text is unchanged keep their markup.
+    ///
+    /// ```
+    /// use emerald::shard::{ast::{Ast, TableView}, Value};
+    /// use indexmap::IndexMap;
+    ///
+    /// let mut ast: Ast = "Intro\n\n| Name |\n| --- |\n| Alpha |\n".parse().unwrap();
+    /// let (index, _) = ast.tables()[0];
+    ///
+    /// let record = IndexMap::from([
+    ///     ("Name".to_string(), Value::String("Beta".into())),
+    ///     ("Rating".to_string(), Value::Number(5.into())),
+    /// ]);
+    /// ast.set_table(index, &TableView::from_records(&[record])).unwrap();
+    ///
+    /// assert_eq!(ast.to_string(), "Intro\n\n| Name | Rating |\n| --- | --- |\n| Beta | 5 |\n");
+    /// ```
+    pub fn set_table(&mut self, index: NodeIndex, view: &TableView) -> Result<()> {
+        match self.arena.get(index).map(|node| &node.attributes) {
+            Some(super::NodeAttributes::Table(_)) => {}
+            _ => return Err(Error::InvalidInput("the node is not a table".to_string())),
+        }
+
+        // The cells of the table, which the view may keep.
+        let rows = self.arena[index].children.clone();
+        let mut reusable = rows
+            .iter()
+            .filter_map(|&row| self.arena.get(row))
+            .flat_map(|row| row.children.clone())
+            .map(|cell| (cell, self.cell_text(cell)))
+            .collect::<HashMap<_, _>>();
+
+        let (table, new_rows) = self.table_rows(view, &mut reusable)?;
+
+        for row in rows {
+            self.arena.remove(row);
+            self.modified.remove(&row);
+        }
+
+        for cell in reusable.into_keys() {
+            self.remove_subtree(cell);
+        }
+
+        let node = &mut self.arena[index];
+        node.attributes = super::NodeAttributes::Table(table);
+        node.children = new_rows;
+        self.modified.insert(index);
+
+        Ok(())
+    }
+
+    /// Insert a table built from the view among the children of the parent,
+    /// at the position `at`, or last if it is out of bounds.
+    ///
+    /// The source of the parent is kept around the new table.
+    pub fn insert_table(
+        &mut self,
+        parent: NodeIndex,
+        at: usize,
+        view: &TableView,
+    ) -> Result<NodeIndex> {
+        if !self.arena.contains(parent) {
+            return Err(Error::InvalidInput(
+                "the parent node does not exist".to_string(),
+            ));
+        }
+
+        let (table, rows) = self.table_rows(view, &mut HashMap::default())?;
+        let index = self.insert_node(Self::from_table(table, rows, None));
+        self.modified.insert(index);
+
+        let children = &mut self.arena[parent].children;
+        children.insert(at.min(children.len()), index);
+
+        Ok(index)
+    }
+
+    /// Build the rows of the view, the header first.
+    ///
+    /// A cell of the view whose text is unchanged is the reusable cell it was read from,
+    /// taken out of the reusable cells with its markup.
+    fn table_rows(
+        &mut self,
+        view: &TableView,
+        reusable: &mut HashMap<NodeIndex, String>,
+    ) -> Result<(Table, Vec<NodeIndex>)> {
+        let width = view.header.len();
+
+        if width == 0 {
+            return Err(Error::InvalidInput(
+                "a table has at least one column".to_string(),
+            ));
+        }
+
+        let mut rows = vec![];
+
+        for (line, row) in std::iter::once(&view.header).chain(&view.rows).enumerate() {
+            let mut cells = vec![];
+
+            for column in 0..width {
+                let text = row
+                    .get(column)
+                    .map(|text| text.replace(['\r', '\n'], " ").trim().to_string())
+                    .unwrap_or_default();
+
+                // The cell at the same place first, then any cell moved with its row.
+                let original = view
+                    .cells
+                    .get(line)
+                    .and_then(|cells| cells.get(column))
+                    .into_iter()
+                    .chain(view.cells.iter().flatten())
+                    .find(|cell| reusable.get(cell) == Some(&text))
+                    .copied();
+
+                if let Some(cell) = original {
+                    reusable.remove(&cell);
+                    cells.push(cell);
+                    continue;
+                }
+                let children = match text.is_empty() {
+                    true => vec![],
+                    false => vec![self.insert_node(Self::from_text(text, vec![], None))],
+                };
+
+                cells.push(self.insert_node(Self::from_table_cell(children, None)));
+            }
+
+            rows.push(self.insert_node(Self::from_table_row(cells, None)));
+        }
+
+        let mut align = view.align.clone();
+        align.resize(width, mdast::AlignKind::None);
+
+        Ok((Table { align }, rows))
+    }
+
+    /// The text of the cell, without markup.
+    fn cell_text(&self, cell: NodeIndex) -> String {
+        let mut text = String::default();
+        self.push_text(cell, &mut text);
+        text.trim().to_string()
+    }
+
+    /// Remove the node and its descendants from the arena.
+    fn remove_subtree(&mut self, index: NodeIndex) {
+        if let Some(node) = self.arena.remove(index) {
+            self.modified.remove(&index);
+
+            for child in node.children {
+                self.remove_subtree(child);
+            }
+        }
+    }
+
+    /// Push the text of the node and its descendants, in document order.
+    fn push_text(&self, index: NodeIndex, text: &mut String) {
+        let Some(node) = self.get(index) else {
+            return;
+        };
+
+        match &node.attributes {
+            super::NodeAttributes::Text(value) => text.push_str(value),
+            super::NodeAttributes::InlineCode(code) => text.push_str(&code.value),
+            super::NodeAttributes::WikiLink(link) => {
+                text.push_str(link.alias.as_ref().unwrap_or(&link.target))
+            }
+            super::NodeAttributes::Tag(name) => {
+                text.push('#');
+                text.push_str(name);
+            }
+            _ => {}
+        }
+
+        for &child in &node.children {
+            self.push_text(child, text);
+        }
+    }
+
     pub fn get_root(&self) -> Option<NodeRef<'_>> {
         self.root.and_then(|r| self.get(r))
     }
@@ -129,10 +715,23 @@ impl Ast {
     }
 
     /// Get a mutable reference by its index.
+    ///
+    /// The node is then serialized from its attributes and children,
+    /// instead of its source.
     pub fn get_mut(&mut self, index: NodeIndex) -> Option<NodeMut<'_>> {
-        self.arena
-            .get_mut(index)
-            .map(|content| NodeMut { index, content })
+        let content = self.arena.get_mut(index)?;
+        self.modified.insert(index);
+        Some(NodeMut { index, content })
+    }
+
+    /// The parsed source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns true if the node was borrowed mutably.
+    pub(super) fn is_modified(&self, index: NodeIndex) -> bool {
+        self.modified.contains(&index)
     }
 }
 
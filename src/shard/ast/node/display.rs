@@ -1,176 +1,700 @@
-use std::fmt::Write;
+//! Markdown serialization of the AST.
+//!
+//! The untouched nodes are written as their source, so that an unmodified shard is
+//! serialized byte for byte, and an edited one with a minimal diff. The nodes borrowed
+//! with [Ast::get_mut], and the nodes without position, are written as CommonMark/GFM
+//! from their attributes, their untouched children still being written as their source.
+use std::collections::HashSet;
 
-use super::{r#ref::NodeRef, Ast};
+use markdown::mdast::{AlignKind, AttributeContent, AttributeValue, ReferenceKind};
+
+use super::{
+    arena::NodeIndex, r#ref::NodeRef, traits::Node as _, Ast, InlineFieldKind, NodeAttributes,
+    WikiLink, WikiLinkAnchor,
+};
+use crate::shard::ast::{inline, FrontMatterFormat};
 
 impl std::fmt::Display for Ast {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fmt_node_refs(f, self.walk_ref().max_depth(0))
+        match self.get_root() {
+            Some(root) => write!(f, "{}", root),
+            None => Ok(()),
+        }
     }
 }
 
-fn fmt_node_refs<'tree, W: Write>(
-    f: &mut W,
-    nodes: impl Iterator<Item = NodeRef<'tree>>,
-) -> std::fmt::Result {
-    nodes
-        .map(|node| write!(f, "{}", node))
-        .collect::<Result<Vec<_>, _>>()?;
-    Ok(())
-}
-
 impl<'tree> std::fmt::Display for NodeRef<'tree> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.attributes.clone() {
-            super::NodeAttributes::Root => fmt_node_refs(f, self.iter_children()),
-            super::NodeAttributes::BlockQuote => {
-                let mut body = String::default();
-                fmt_node_refs(&mut body, self.iter_children())?;
-                body = body
-                    .split('\n')
-                    .fold(String::new(), |acc, line| format!("{}\n> {}", acc, line));
-                write!(f, "{}", body)
-            }
-            super::NodeAttributes::FootnoteDefinition(attrs) => {
-                write!(
-                    f,
-                    "[^{label_or_identifier}]: ",
-                    label_or_identifier = [attrs.label, Some(attrs.identifier)]
-                        .into_iter()
-                        .flatten()
-                        .next()
-                        .unwrap_or_default()
-                )?;
-                fmt_node_refs(f, self.iter_children())
-            }
-            super::NodeAttributes::FootnoteReference(attrs) => {
-                write!(
-                    f,
-                    "[^{label_or_identifier}]",
-                    label_or_identifier = [attrs.label, Some(attrs.identifier)]
-                        .into_iter()
-                        .flatten()
-                        .next()
-                        .unwrap_or_default()
-                )
-            }
-            super::NodeAttributes::MdxJsxFlowElement(_) => fmt_node_refs(f, self.iter_children()),
-            super::NodeAttributes::MdxFlowExpression(_) => fmt_node_refs(f, self.iter_children()),
-            super::NodeAttributes::MdxjsEsm(_) => fmt_node_refs(f, self.iter_children()),
-            super::NodeAttributes::MdxJsxTextElement(_) => fmt_node_refs(f, self.iter_children()),
-            super::NodeAttributes::MdxTextExpression(_) => fmt_node_refs(f, self.iter_children()),
-            super::NodeAttributes::List(_) => fmt_node_refs(f, self.iter_children()),
-            super::NodeAttributes::ListItem(_) => fmt_node_refs(f, self.iter_children()),
-            super::NodeAttributes::FrontMatter(frontmatter) => {
-                writeln!(f, "---")?;
-                write!(f, "{}", frontmatter)?;
-                writeln!(f, "---")
-            }
-            super::NodeAttributes::Html(value) => write!(f, "{}", value),
-            super::NodeAttributes::ThematicBreak => writeln!(f, "***"),
-            super::NodeAttributes::Break => writeln!(f, "\\"),
-            super::NodeAttributes::InlineCode(attrs) => {
-                write!(f, "`{}`", attrs.value)
-            }
-            super::NodeAttributes::InlineMath(attrs) => {
-                write!(f, "${}$", attrs.value)
-            }
-            super::NodeAttributes::Text(value) => write!(f, "{}", value),
-            super::NodeAttributes::Delete => {
-                write!(f, "~~")?;
-                fmt_node_refs(f, self.iter_children())?;
-                write!(f, "~~")
-            }
-            super::NodeAttributes::Emphasis => {
-                write!(f, "*")?;
-                fmt_node_refs(f, self.iter_children())?;
-                write!(f, "*")
-            }
-            super::NodeAttributes::Strong => {
-                write!(f, "**")?;
-                fmt_node_refs(f, self.iter_children())?;
-                write!(f, "**")
-            }
-            super::NodeAttributes::Image(attrs) => {
-                write!(f, "![{}]", attrs.alt)?;
-                write!(f, "(\"{}\"", attrs.url)?;
-                if let Some(title) = attrs.title {
-                    write!(f, " \"{}\"", title)?;
+        let mut serializer = Serializer::new(self.ast, self.index);
+        serializer.node(self.index);
+        f.write_str(&serializer.out)
+    }
+}
+
+struct Serializer<'tree> {
+    ast: &'tree Ast,
+    /// The nodes holding a modified node in their subtree, themselves included.
+    touched: HashSet<NodeIndex>,
+    out: String,
+    /// The prefix of the continuation lines, pushed by the block containers.
+    prefix: String,
+    /// A generated line break was written, the prefix of the next line is not.
+    pending: bool,
+    /// The marker of the list item to generate.
+    marker: Option<String>,
+    /// The pipes of the text are escaped within a table.
+    in_table: bool,
+}
+
+impl<'tree> Serializer<'tree> {
+    fn new(ast: &'tree Ast, from: NodeIndex) -> Self {
+        let mut serializer = Self {
+            ast,
+            touched: HashSet::default(),
+            out: String::default(),
+            prefix: String::default(),
+            pending: false,
+            marker: None,
+            in_table: false,
+        };
+
+        serializer.mark_touched(from);
+        serializer
+    }
+
+    fn mark_touched(&mut self, index: NodeIndex) -> bool {
+        let Some(node) = self.ast.get(index) else {
+            return false;
+        };
+
+        let mut touched = self.ast.is_modified(index);
+
+        for &child in &node.children {
+            touched |= self.mark_touched(child);
+        }
+
+        if touched {
+            self.touched.insert(index);
+        }
+
+        touched
+    }
+
+    /// The source of the node, if the AST holds it.
+    fn source(&self, node: &NodeRef<'tree>) -> Option<&'tree str> {
+        let position = node.get_position()?;
+        self.ast
+            .source()
+            .get(position.start.offset..position.end.offset)
+    }
+
+    /// Write generated markdown, the continuation lines are prefixed.
+    fn write(&mut self, text: &str) {
+        for c in text.chars() {
+            if self.pending {
+                self.pending = false;
+
+                if c == '\n' {
+                    self.out.push_str(self.prefix.trim_end());
+                } else {
+                    self.out.push_str(&self.prefix);
                 }
-                write!(f, ")")
             }
-            super::NodeAttributes::ImageReference(attrs) => {
-                write!(f, "!")?;
 
-                if !attrs.alt.is_empty() {
-                    write!(f, "[{}]", attrs.alt)?;
+            self.out.push(c);
+            self.pending = c == '\n';
+        }
+    }
+
+    /// The output is at the start of a line, after the prefix and the list marker of its
+    /// containers, where the text could start a block.
+    fn at_line_start(&self) -> bool {
+        let line = &self.out[self.out.rfind('\n').map_or(0, |end| end + 1)..];
+
+        self.pending
+            || line
+                .split_whitespace()
+                .all(|marker| marker == ">" || list_marker(marker))
+    }
+
+    /// Write the source of a node, it starts after the prefix of its line.
+    fn write_source(&mut self, source: &str) {
+        if self.pending {
+            self.pending = false;
+            self.out.push_str(&self.prefix);
+        }
+
+        self.out.push_str(source);
+    }
+
+    /// Write the source between two nodes, it holds the prefixes of its lines.
+    fn write_gap(&mut self, source: &str) {
+        self.pending = false;
+        self.out.push_str(source);
+    }
+
+    fn with_prefix(&mut self, prefix: &str, write: impl FnOnce(&mut Self)) {
+        let length = self.prefix.len();
+        self.prefix.push_str(prefix);
+        write(self);
+        self.prefix.truncate(length);
+    }
+
+    fn node(&mut self, index: NodeIndex) {
+        let Some(node) = self.ast.get(index) else {
+            return;
+        };
+
+        if !self.touched.contains(&index) {
+            if let Some(source) = self.source(&node) {
+                self.marker = None;
+                return self.write_source(source);
+            }
+        }
+
+        if !self.ast.is_modified(index) && self.splice(&node) {
+            return;
+        }
+
+        self.generate(&node);
+    }
+
+    /// Write the source of the node around its children, which are serialized.
+    ///
+    /// The new children of a container of blocks, without position, are separated
+    /// from their siblings by a blank line.
+    ///
+    /// Returns false, without writing anything, if the node has no position,
+    /// or if one of the children of another node has no position.
+    fn splice(&mut self, node: &NodeRef<'tree>) -> bool {
+        let (Some(source), Some(position)) = (self.source(node), node.get_position()) else {
+            return false;
+        };
+
+        let blocks = matches!(
+            node.attributes,
+            NodeAttributes::Root
+                | NodeAttributes::BlockQuote
+                | NodeAttributes::FootnoteDefinition(_)
+                | NodeAttributes::ListItem(_)
+        );
+
+        let mut ranges = vec![];
+        let mut last = position.start.offset;
+
+        for child in node.iter_children() {
+            match child.get_position() {
+                Some(child)
+                    if child.start.offset >= last && child.end.offset >= child.start.offset =>
+                {
+                    ranges.push(Some((child.start.offset, child.end.offset)));
+                    last = child.end.offset;
                 }
+                None if blocks => ranges.push(None),
+                _ => return false,
+            }
+        }
 
-                write!(f, "[{}", attrs.identifier)?;
+        if ranges.iter().all(Option::is_none) && !ranges.is_empty() {
+            return false;
+        }
+
+        if last > position.end.offset {
+            return false;
+        }
 
-                if let Some(title) = attrs.label {
-                    write!(f, " \"{}\"", title)?;
+        let base = position.start.offset;
+        let gap = |start: usize, end: usize| &source[start - base..end - base];
+
+        let prefix = match &node.attributes {
+            NodeAttributes::BlockQuote => "> ".to_string(),
+            NodeAttributes::FootnoteDefinition(_) => "    ".to_string(),
+            // The content of an item is aligned after its marker.
+            NodeAttributes::ListItem(_) => ranges
+                .iter()
+                .flatten()
+                .next()
+                .and_then(|&(start, _)| gap(base, start).split('\n').next_back())
+                .map(|marker| " ".repeat(marker.chars().count()))
+                .unwrap_or_default(),
+            _ => String::default(),
+        };
+
+        self.marker = None;
+        // The node starts after the prefix of its line.
+        self.write_source("");
+
+        self.with_prefix(&prefix, |serializer| {
+            let mut last = base;
+            let mut written = false;
+
+            for (child, range) in node.iter_children().zip(&ranges) {
+                match *range {
+                    Some((start, end)) => {
+                        serializer.write_gap(gap(last, start));
+                        serializer.node(child.index);
+                        last = end;
+                    }
+                    None if written => {
+                        serializer.write("\n\n");
+                        serializer.node(child.index);
+                    }
+                    None => {
+                        serializer.node(child.index);
+                        serializer.write("\n\n");
+                    }
                 }
 
-                write!(f, "]")
+                written = true;
+            }
+
+            serializer.write_gap(gap(last, position.end.offset));
+        });
+
+        true
+    }
+
+    fn children(&mut self, node: &NodeRef<'tree>) {
+        for &child in &node.children {
+            self.node(child);
+        }
+    }
+
+    /// Write the children, separated by the separator.
+    fn blocks(&mut self, node: &NodeRef<'tree>, separator: &str) {
+        for (position, &child) in node.children.iter().enumerate() {
+            if position > 0 {
+                self.write(separator);
             }
-            super::NodeAttributes::Link(attrs) => {
-                if self.children.is_empty() {
-                    write!(f, "[")?;
-                    fmt_node_refs(f, self.iter_children())?;
-                    write!(f, "]({}", attrs.url)?;
-                    if let Some(title) = attrs.title {
-                        write!(f, " \"{}\"", title)?;
+
+            self.node(child);
+        }
+    }
+
+    /// Generate the markdown of the node, from its attributes and children.
+    fn generate(&mut self, node: &NodeRef<'tree>) {
+        match &node.attributes {
+            NodeAttributes::Root => {
+                self.blocks(node, "\n\n");
+
+                if !self.out.is_empty() && !self.out.ends_with('\n') {
+                    self.write("\n");
+                }
+            }
+            NodeAttributes::BlockQuote => {
+                self.write("> ");
+                self.with_prefix("> ", |serializer| serializer.blocks(node, "\n\n"));
+            }
+            NodeAttributes::FootnoteDefinition(attrs) => {
+                self.write(&format!(
+                    "[^{}]: ",
+                    attrs.label.as_ref().unwrap_or(&attrs.identifier)
+                ));
+                self.with_prefix("    ", |serializer| serializer.blocks(node, "\n\n"));
+            }
+            NodeAttributes::FootnoteReference(attrs) => {
+                self.write(&format!(
+                    "[^{}]",
+                    attrs.label.as_ref().unwrap_or(&attrs.identifier)
+                ));
+            }
+            NodeAttributes::MdxJsxFlowElement(attrs) => {
+                self.jsx(node, attrs.name.as_deref(), &attrs.attributes)
+            }
+            NodeAttributes::MdxJsxTextElement(attrs) => {
+                self.jsx(node, attrs.name.as_deref(), &attrs.attributes)
+            }
+            NodeAttributes::MdxFlowExpression(attrs) => self.write(&format!("{{{}}}", attrs.value)),
+            NodeAttributes::MdxTextExpression(attrs) => self.write(&format!("{{{}}}", attrs.value)),
+            NodeAttributes::MdxjsEsm(attrs) => self.write(&attrs.value),
+            NodeAttributes::List(list) => {
+                let separator = if list.spread { "\n\n" } else { "\n" };
+
+                for (position, &item) in node.children.iter().enumerate() {
+                    if position > 0 {
+                        self.write(separator);
                     }
-                    write!(f, ")")
-                } else {
-                    write!(f, "[{}]", attrs.url)
+
+                    self.marker = Some(match list.ordered {
+                        true => format!("{}. ", list.start.unwrap_or(1) as usize + position),
+                        false => "- ".to_string(),
+                    });
+                    self.node(item);
+                    self.marker = None;
                 }
             }
-            super::NodeAttributes::LinkReference(attrs) => {
-                write!(f, "[{}]", attrs.identifier)?;
+            NodeAttributes::ListItem(item) => {
+                let marker = self.marker.take().unwrap_or_else(|| "- ".to_string());
+                self.write(&marker);
 
-                if let Some(label) = attrs.label {
-                    write!(f, "[{}]", label)?;
+                match item.checked {
+                    Some(true) => self.write("[x] "),
+                    Some(false) => self.write("[ ] "),
+                    None => {}
                 }
 
-                Ok(())
+                let separator = if item.spread { "\n\n" } else { "\n" };
+                let indent = " ".repeat(marker.len());
+                self.with_prefix(&indent, |serializer| serializer.blocks(node, separator));
             }
-            super::NodeAttributes::Code(attrs) => {
-                write!(f, "```")?;
+            NodeAttributes::FrontMatter(frontmatter) => {
+                let fence = match frontmatter.format {
+                    FrontMatterFormat::Yaml => "---",
+                    FrontMatterFormat::Toml => "+++",
+                };
+                let properties = match frontmatter.properties.is_empty() {
+                    true => String::default(),
+                    false => frontmatter.to_string(),
+                };
 
-                if let Some(lang) = attrs.lang {
-                    write!(f, "{} ", lang)?;
+                self.write(&format!("{}\n{}{}", fence, properties, fence));
+            }
+            NodeAttributes::Html(value) => self.write(value),
+            NodeAttributes::ThematicBreak => self.write("***"),
+            NodeAttributes::Break => self.write("\\\n"),
+            NodeAttributes::InlineCode(attrs) => match self.in_table {
+                true => self.write(&inline_code(&attrs.value.replace('|', "\\|"))),
+                false => self.write(&inline_code(&attrs.value)),
+            },
+            NodeAttributes::InlineMath(attrs) => self.write(&format!("${}$", attrs.value)),
+            NodeAttributes::Text(value) => {
+                let escaped = escape(value, self.in_table, self.at_line_start());
+                self.write(&escaped);
+            }
+            NodeAttributes::InlineField(field) => self.write(&match field.kind {
+                InlineFieldKind::Line => format!("{}:: {}", field.key, field.value),
+                InlineFieldKind::Bracket => format!("[{}:: {}]", field.key, field.value),
+                InlineFieldKind::Paren => format!("({}:: {})", field.key, field.value),
+            }),
+            NodeAttributes::WikiLink(link) => self.write(&wikilink(link)),
+            NodeAttributes::Embed(link) => self.write(&format!("!{}", wikilink(link))),
+            NodeAttributes::Tag(name) => self.write(&format!("#{}", name)),
+            NodeAttributes::Delete => self.wrap(node, "~~"),
+            NodeAttributes::Emphasis => self.wrap(node, "*"),
+            NodeAttributes::Strong => self.wrap(node, "**"),
+            NodeAttributes::Image(attrs) => self.write(&format!(
+                "![{}]({})",
+                escape(&attrs.alt, self.in_table, false),
+                resource(&attrs.url, attrs.title.as_deref())
+            )),
+            NodeAttributes::ImageReference(attrs) => {
+                self.write(&format!("![{}]", escape(&attrs.alt, self.in_table, false)));
+                self.write(&reference(
+                    &attrs.reference_kind,
+                    attrs.label.as_ref().unwrap_or(&attrs.identifier),
+                ));
+            }
+            NodeAttributes::Link(attrs) => {
+                self.write("[");
+                self.children(node);
+                self.write(&format!(
+                    "]({})",
+                    resource(&attrs.url, attrs.title.as_deref())
+                ));
+            }
+            NodeAttributes::LinkReference(attrs) => {
+                self.write("[");
+                self.children(node);
+                self.write("]");
+                self.write(&reference(
+                    &attrs.reference_kind,
+                    attrs.label.as_ref().unwrap_or(&attrs.identifier),
+                ));
+            }
+            NodeAttributes::Code(attrs) => {
+                let fence = "`".repeat(longest_run(&attrs.value, '`').max(2) + 1);
+                let info = [attrs.lang.as_deref(), attrs.meta.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                self.write(&format!("{}{}\n", fence, info));
+
+                if !attrs.value.is_empty() {
+                    self.write(&format!("{}\n", attrs.value));
                 }
 
-                if let Some(meta) = attrs.meta {
-                    write!(f, "\"{}\"", meta)?;
+                self.write(&fence);
+            }
+            NodeAttributes::Math(attrs) => {
+                let meta = attrs.meta.as_deref().unwrap_or_default();
+                self.write(&format!("$${}\n", meta));
+
+                if !attrs.value.is_empty() {
+                    self.write(&format!("{}\n", attrs.value));
+                }
+
+                self.write("$$");
+            }
+            NodeAttributes::Heading(attrs) => {
+                self.write(&format!("{} ", "#".repeat(attrs.depth.into())));
+                self.children(node);
+            }
+            NodeAttributes::Definition(attrs) => self.write(&format!(
+                "[{}]: {}",
+                attrs.label.as_ref().unwrap_or(&attrs.identifier),
+                resource(&attrs.url, attrs.title.as_deref())
+            )),
+            NodeAttributes::Table(table) => {
+                let in_table = std::mem::replace(&mut self.in_table, true);
+
+                for (position, &row) in node.children.iter().enumerate() {
+                    if position > 0 {
+                        self.write("\n");
+                    }
+
+                    self.node(row);
+
+                    if position == 0 {
+                        let delimiters = table
+                            .align
+                            .iter()
+                            .map(|align| match align {
+                                AlignKind::Left => ":--",
+                                AlignKind::Right => "--:",
+                                AlignKind::Center => ":-:",
+                                AlignKind::None => "---",
+                            })
+                            .collect::<Vec<_>>();
+
+                        self.write(&format!("\n| {} |", delimiters.join(" | ")));
+                    }
+                }
+
+                self.in_table = in_table;
+            }
+            NodeAttributes::TableRow => {
+                let in_table = std::mem::replace(&mut self.in_table, true);
+                self.write("|");
+
+                for &cell in &node.children {
+                    self.write(" ");
+                    self.cell(cell);
+                    self.write(" |");
                 }
 
-                writeln!(f)?;
-                writeln!(f, "{}", attrs.value)?;
-                write!(f, "```")?;
+                self.in_table = in_table;
+            }
+            NodeAttributes::TableCell => self.children(node),
+            NodeAttributes::Paragraph => self.children(node),
+        }
+    }
+
+    /// Write a cell of a generated row.
+    ///
+    /// The source of a cell holds its pipes and padding, an untouched cell is written
+    /// as the source of its content.
+    fn cell(&mut self, index: NodeIndex) {
+        let Some(node) = self.ast.get(index) else {
+            return;
+        };
 
-                Ok(())
+        match self.source(&node) {
+            Some(source) if !self.touched.contains(&index) => {
+                self.write_source(cell_content(source))
             }
-            super::NodeAttributes::Math(attrs) => {
-                writeln!(f, "$$")?;
-                writeln!(f, "{}", attrs.value)?;
-                writeln!(f, "$$")
+            _ => self.node(index),
+        }
+    }
+
+    fn wrap(&mut self, node: &NodeRef<'tree>, delimiter: &str) {
+        self.write(delimiter);
+        self.children(node);
+        self.write(delimiter);
+    }
+
+    fn jsx(&mut self, node: &NodeRef<'tree>, name: Option<&str>, attributes: &[AttributeContent]) {
+        let name = name.unwrap_or_default();
+        let mut tag = format!("<{}", name);
+
+        for attribute in attributes {
+            match attribute {
+                AttributeContent::Expression { value, .. } => {
+                    tag.push_str(&format!(" {{{}}}", value))
+                }
+                AttributeContent::Property(property) => {
+                    tag.push(' ');
+                    tag.push_str(&property.name);
+
+                    match &property.value {
+                        Some(AttributeValue::Literal(value)) => {
+                            tag.push_str(&format!("=\"{}\"", value.replace('"', "&quot;")))
+                        }
+                        Some(AttributeValue::Expression(value)) => {
+                            tag.push_str(&format!("={{{}}}", value.value))
+                        }
+                        None => {}
+                    }
+                }
             }
-            super::NodeAttributes::Heading(attrs) => {
-                write!(f, "{} ", "#".repeat(attrs.depth.into()))?;
-                fmt_node_refs(f, self.iter_children())
+        }
+
+        if node.children.is_empty() {
+            return self.write(&format!("{} />", tag));
+        }
+
+        self.write(&format!("{}>", tag));
+        self.children(node);
+        self.write(&format!("</{}>", name));
+    }
+}
+
+/// Escape the characters of the text which would be parsed as markup.
+///
+/// The markers of the blocks are escaped at the start of the lines, the first line of the
+/// text included if it starts a line, as `\# not a heading` or `1\. not a list`.
+/// Within the lines, so are the `#` starting a tag, the `(` opening an inline field,
+/// the `&` starting a character reference and the `~` of the strikethroughs.
+fn escape(text: &str, in_table: bool, line_start: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut previous = None;
+
+    for (index, mut line) in text.split_inclusive('\n').enumerate() {
+        let mut marker = false;
+
+        if !in_table && (line_start || index > 0) {
+            let content = line.trim_start_matches(' ');
+            escaped.push_str(&line[..line.len() - content.len()]);
+            line = content;
+
+            let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+            if line.starts_with(['#', '-', '+', '>', '=']) {
+                escaped.push('\\');
+                marker = true;
+            } else if digits > 0 && line[digits..].starts_with(['.', ')']) {
+                escaped.push_str(&line[..digits]);
+                escaped.push('\\');
+                line = &line[digits..];
             }
-            super::NodeAttributes::Definition(attrs) => {
-                write!(f, "[{}]: {}", attrs.identifier, attrs.url)
+        }
+
+        for (offset, c) in line.char_indices() {
+            let rest = &line[offset..];
+
+            let markup = match c {
+                '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '~' => true,
+                '|' => in_table,
+                '#' => {
+                    !previous.is_some_and(char::is_alphanumeric)
+                        && rest[1..].starts_with(inline::is_tag_char)
+                }
+                '(' => inline::starts_field(rest),
+                '&' => is_reference(rest),
+                _ => false,
+            };
+
+            // The marker of the block is already escaped.
+            if markup && !(marker && offset == 0) {
+                escaped.push('\\');
             }
-            super::NodeAttributes::Table(_) => todo!(),
-            super::NodeAttributes::TableRow => todo!(),
-            super::NodeAttributes::TableCell => todo!(),
 
-            super::NodeAttributes::Paragraph => fmt_node_refs(f, self.iter_children()),
+            escaped.push(c);
+            previous = Some(c);
         }
     }
+
+    escaped
+}
+
+/// Returns true if the text starts with a character reference, as `&copy;` or `&#35;`.
+fn is_reference(text: &str) -> bool {
+    let Some(body) = text.strip_prefix('&') else {
+        return false;
+    };
+
+    let name = body.strip_prefix('#').unwrap_or(body);
+    let end = name
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(name.len());
+
+    end > 0 && name[end..].starts_with(';')
+}
+
+/// The marker of a list item, as `-` or `1.`.
+fn list_marker(marker: &str) -> bool {
+    let number = marker.trim_end_matches(['.', ')']);
+
+    matches!(marker, "-" | "*" | "+")
+        || (number.len() + 1 == marker.len()
+            && !number.is_empty()
+            && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    text.split(|other| other != c)
+        .map(str::len)
+        .max()
+        .unwrap_or_default()
+}
+
+fn inline_code(value: &str) -> String {
+    let ticks = "`".repeat(longest_run(value, '`') + 1);
+    let padding = if value.starts_with('`') || value.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+
+    format!("{ticks}{padding}{value}{padding}{ticks}")
+}
+
+/// The destination and the title of a link, an image or a definition.
+fn resource(url: &str, title: Option<&str>) -> String {
+    let destination = if url.is_empty() || url.contains([' ', '(', ')', '<', '>']) {
+        format!("<{}>", url.replace('<', "\\<").replace('>', "\\>"))
+    } else {
+        url.to_string()
+    };
+
+    match title {
+        Some(title) => format!("{} \"{}\"", destination, title.replace('"', "\\\"")),
+        None => destination,
+    }
+}
+
+/// The label of a reference, after its text.
+fn reference(kind: &ReferenceKind, label: &str) -> String {
+    match kind {
+        ReferenceKind::Full => format!("[{}]", label),
+        ReferenceKind::Collapsed => "[]".to_string(),
+        ReferenceKind::Shortcut => String::default(),
+    }
+}
+
+fn wikilink(link: &WikiLink) -> String {
+    let mut written = format!("[[{}", link.target);
+
+    match &link.anchor {
+        Some(WikiLinkAnchor::Heading(heading)) => written.push_str(&format!("#{}", heading)),
+        Some(WikiLinkAnchor::Block(block)) => written.push_str(&format!("#^{}", block)),
+        None => {}
+    }
+
+    if let Some(alias) = &link.alias {
+        written.push_str(&format!("|{}", alias));
+    }
+
+    written.push_str("]]");
+    written
+}
+
+/// The content of the source of a table cell, without its pipes and padding.
+fn cell_content(source: &str) -> &str {
+    let content = source.trim();
+    let content = content.strip_prefix('|').unwrap_or(content);
+
+    // The last pipe is escaped by an odd number of backslashes.
+    let escaped = content
+        .strip_suffix('|')
+        .map(|rest| rest.len() - rest.trim_end_matches('\\').len())
+        .is_some_and(|backslashes| backslashes % 2 == 1);
+
+    match content.strip_suffix('|') {
+        Some(rest) if !escaped => rest.trim(),
+        _ => content.trim(),
+    }
 }
 
 #[cfg(test)]
@@ -191,7 +715,7 @@ This is a content [property:: value]
 "#;
 
         let ast = Ast::from_str(content)?;
-        println!("{}", ast);
+        assert_eq!(ast.to_string(), content);
         Ok(())
     }
 }
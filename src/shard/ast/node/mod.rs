@@ -1,7 +1,4 @@
-use std::error::Error;
-
-use indexmap::IndexMap;
-use markdown::mdast;
+use super::FrontMatter;
 pub use markdown::unist::Position;
 pub mod debug;
 pub mod display;
@@ -12,7 +9,7 @@ pub use r#ref::NodeRef;
 #[macro_export]
 /// Implement node conversion
 ///
-/// ```
+/// ```ignore
 /// fn convert(
 ///        strategy: impl NodeConverterStrategy,
 ///        node: markdown::mdast::Node
@@ -85,13 +82,13 @@ macro_rules! convert {
 /// Automatically defines all per-node type converter function
 /// for NodeConverter trait.
 ///
-/// ´´´
+/// ```ignore
 /// impl NodeConverter {
 ///     [...]
 ///
 ///     def_from_node_types!{}
 /// }
-/// ´´´
+/// ```
 macro_rules! def_from_node_types {
     () => {
         def_from_node_type! {Root}
@@ -105,8 +102,8 @@ macro_rules! def_from_node_types {
         def_from_node_type! {MdxTextExpression, $crate::shard::ast::MdxTextExpression}
         def_from_node_type! {List, $crate::shard::ast::List}
         def_from_node_type! {ListItem, $crate::shard::ast::ListItem}
-        def_from_node_type! {Yaml, serde_yaml::Value}
-        def_from_node_type! {Toml, toml::Value}
+        def_from_node_type! {Yaml, $crate::shard::ast::FrontMatter}
+        def_from_node_type! {Toml, $crate::shard::ast::FrontMatter}
         def_from_node_type! {Html, String}
         def_from_node_type! {ThematicBreak}
         def_from_node_type! {Break}
@@ -134,12 +131,12 @@ macro_rules! def_from_node_types {
 #[macro_export]
 /// Create a new node type builder
 ///
-/// ´´´
+/// ```ignore
 /// pub trait NodeConverter {
 ///     // ... //
 ///     def_from_node_type{Root}
 /// }
-/// ´´´
+/// ```
 macro_rules! def_from_node_type {
     ($typ:ident) => {
         paste::paste! {
@@ -163,11 +160,11 @@ macro_rules! def_from_node_type {
 #[macro_export]
 /// Automatically implements all node builders per-type
 ///
-/// ´´´
+/// ```ignore
 /// impl Node {
 ///     from_node_types!()
 /// }
-/// ´´´
+/// ```
 macro_rules! from_node_types {
     () => {
         from_node_type! {Root}
@@ -184,7 +181,7 @@ macro_rules! from_node_types {
 
         /// Convert YAML node into a FrontMatter node
         fn from_yaml(
-            value: serde_yaml::Value,
+            value: $crate::shard::ast::FrontMatter,
             children: Vec<Self::NodeRef>,
             position: Option<$crate::shard::ast::Position>,
         ) -> Self::Node {
@@ -192,15 +189,13 @@ macro_rules! from_node_types {
                 position,
                 children,
                 r#type: $crate::shard::ast::NodeType::FrontMatter,
-                attributes: $crate::shard::ast::NodeAttributes::FrontMatter(
-                    $crate::shard::ast::FrontMatter::from(value),
-                ),
+                attributes: $crate::shard::ast::NodeAttributes::FrontMatter(value),
             }
         }
 
         /// Convert TOML node into a FrontMatter node
         fn from_toml(
-            value: toml::Value,
+            value: $crate::shard::ast::FrontMatter,
             children: Vec<Self::NodeRef>,
             position: Option<$crate::shard::ast::Position>,
         ) -> Self::Node {
@@ -208,9 +203,7 @@ macro_rules! from_node_types {
                 position,
                 children,
                 r#type: $crate::shard::ast::NodeType::FrontMatter,
-                attributes: $crate::shard::ast::NodeAttributes::FrontMatter(
-                    $crate::shard::ast::FrontMatter::from(value),
-                ),
+                attributes: $crate::shard::ast::NodeAttributes::FrontMatter(value),
             }
         }
 
@@ -241,7 +234,7 @@ macro_rules! from_node_types {
 #[macro_export]
 /// Create a new specific node type converter function.
 ///
-/// ```
+/// ```ignore
 /// impl NodeConverter for Foo {
 ///     from_node_type{Root}
 ///     from_node_type{Yaml, serde_yaml::Value}
@@ -284,7 +277,7 @@ macro_rules! from_node_type {
 pub mod arena;
 pub use arena::{Ast, Node};
 
-mod traits {
+pub mod traits {
     pub trait Node {
         /// Returns the node type.
         fn get_type(&self) -> super::NodeType;
@@ -330,13 +323,7 @@ mod traits {
                 markdown::mdast::Node::Root(attr) => convert!(Root, attr, self),
                 markdown::mdast::Node::BlockQuote(attr) => convert!(BlockQuote, attr, self),
                 markdown::mdast::Node::FootnoteDefinition(attr) => {
-                    convert!(
-                        FootnoteDefinition,
-                        attr,
-                        self,
-                        no_children,
-                        [identifier, label]
-                    )
+                    convert!(FootnoteDefinition, attr, self, [identifier, label])
                 }
 
                 markdown::mdast::Node::MdxJsxFlowElement(attr) => {
@@ -355,14 +342,14 @@ mod traits {
                     attr,
                     self,
                     no_children,
-                    toml::from_str(&attr.value).ok()?
+                    crate::shard::ast::FrontMatter::try_from(&attr).ok()?
                 ),
                 markdown::mdast::Node::Yaml(attr) => convert!(
                     Yaml,
                     attr,
                     self,
                     no_children,
-                    serde_yaml::from_str(&attr.value).ok()?
+                    crate::shard::ast::FrontMatter::try_from(&attr).ok()?
                 ),
                 markdown::mdast::Node::Break(attr) => convert!(Break, attr, self, no_children),
                 markdown::mdast::Node::InlineCode(attr) => {
@@ -432,9 +419,7 @@ mod traits {
                     convert!(ThematicBreak, attr, self, no_children)
                 }
                 markdown::mdast::Node::TableRow(attr) => convert!(TableRow, attr, self),
-                markdown::mdast::Node::TableCell(attr) => {
-                    convert!(TableCell, attr, self, no_children)
-                }
+                markdown::mdast::Node::TableCell(attr) => convert!(TableCell, attr, self),
                 markdown::mdast::Node::ListItem(attr) => {
                     convert!(ListItem, attr, self, [checked, spread])
                 }
@@ -473,6 +458,11 @@ pub enum NodeAttributes {
     InlineCode(InlineCode),
     InlineMath(InlineMath),
     Text(String),
+    InlineField(InlineField),
+    WikiLink(WikiLink),
+    Embed(WikiLink),
+    /// A tag `#area/sub-area`, its name is without the `#`.
+    Tag(String),
     Delete,
     Emphasis,
     Strong,
@@ -525,6 +515,7 @@ pub enum NodeType {
     Emphasis,
     Strong,
     Text,
+    InlineField,
 
     Html,
 
@@ -533,6 +524,9 @@ pub enum NodeType {
 
     Link,
     LinkReference,
+    WikiLink,
+    Embed,
+    Tag,
 
     Table,
     TableRow,
@@ -604,6 +598,46 @@ pub struct InlineMath {
     pub value: String,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// The form of an inline field.
+pub enum InlineFieldKind {
+    /// A whole line `key:: value`.
+    Line,
+    /// `[key:: value]`
+    Bracket,
+    /// `(key:: value)`, the key is hidden when rendered.
+    Paren,
+}
+
+#[derive(Debug, Clone)]
+/// A Dataview-style inline field, a property of the shard within its content.
+pub struct InlineField {
+    pub key: String,
+    /// The raw text of the value.
+    pub value: String,
+    pub kind: InlineFieldKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The part of the target a wikilink points to.
+pub enum WikiLinkAnchor {
+    /// `[[target#heading]]`
+    Heading(String),
+    /// `[[target#^block]]`
+    Block(String),
+}
+
+#[derive(Debug, Clone)]
+/// A wikilink `[[target#anchor|alias]]`, or an embed `![[target]]`.
+///
+/// The target is resolved within the jewel by [crate::fs::resolve_wikilink],
+/// an empty target points to the shard itself.
+pub struct WikiLink {
+    pub target: String,
+    pub anchor: Option<WikiLinkAnchor>,
+    pub alias: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Image {
     pub alt: String,
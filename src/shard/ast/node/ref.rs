@@ -7,6 +7,13 @@ pub struct NodeMut<'tree> {
     pub(super) content: &'tree mut Node,
 }
 
+impl NodeMut<'_> {
+    /// The attributes of the node, to edit it.
+    pub fn attributes_mut(&mut self) -> &mut super::NodeAttributes {
+        &mut self.content.attributes
+    }
+}
+
 impl<'tree> Deref for NodeMut<'tree> {
     type Target = Node;
 
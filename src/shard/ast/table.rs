@@ -0,0 +1,109 @@
+//! Typed view of the GFM tables.
+//!
+//! ```markdown
+//! | Name  | Rating |
+//! | :---- | -----: |
+//! | Alpha |      4 |
+//! ```
+pub use markdown::mdast::AlignKind;
+
+use indexmap::IndexMap;
+
+use super::node::arena::NodeIndex;
+use crate::shard::Value;
+
+/// The content of a table: its header, the text of its cells and the alignment
+/// of its columns.
+///
+/// Read it with [super::Ast::table], write it back with [super::Ast::set_table]
+/// or [super::Ast::insert_table].
+///
+/// A view read from a table remembers the cells of the table, so that
+/// [super::Ast::set_table] keeps the markup of the cells whose text is unchanged.
+/// Two views are equal if their content is, wherever they were read from.
+#[derive(Debug, Clone, Default)]
+pub struct TableView {
+    /// The names of the columns.
+    pub header: Vec<String>,
+    /// The text of the cells, row by row. A row may be shorter than the header.
+    pub rows: Vec<Vec<String>>,
+    /// The alignment of the columns.
+    pub align: Vec<AlignKind>,
+    /// The cells the view was read from, the header first.
+    pub(crate) cells: Vec<Vec<NodeIndex>>,
+}
+
+impl PartialEq for TableView {
+    fn eq(&self, other: &Self) -> bool {
+        self.header == other.header && self.rows == other.rows && self.align == other.align
+    }
+}
+
+impl Eq for TableView {}
+
+impl TableView {
+    /// Build a table from records, the columns are the keys in order of appearance.
+    ///
+    /// A null value is an empty cell, the items of an array are separated by commas.
+    pub fn from_records(records: &[IndexMap<String, Value>]) -> Self {
+        let mut header = Vec::<String>::default();
+
+        for key in records.iter().flat_map(|record| record.keys()) {
+            if !header.contains(key) {
+                header.push(key.clone());
+            }
+        }
+
+        let rows = records
+            .iter()
+            .map(|record| {
+                header
+                    .iter()
+                    .map(|key| record.get(key).map(cell).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            align: vec![AlignKind::None; header.len()],
+            header,
+            rows,
+            cells: vec![],
+        }
+    }
+
+    /// The values of the cells, row by row, inferred as the values of the inline fields.
+    pub fn values(&self) -> Vec<Vec<Value>> {
+        self.rows
+            .iter()
+            .map(|row| row.iter().map(|text| Value::from_inline(text)).collect())
+            .collect()
+    }
+
+    /// The rows as records keyed by the header, a missing cell is null.
+    pub fn records(&self) -> Vec<IndexMap<String, Value>> {
+        self.values()
+            .into_iter()
+            .map(|mut row| {
+                row.resize(self.header.len(), Value::Null);
+                self.header.iter().cloned().zip(row).collect()
+            })
+            .collect()
+    }
+}
+
+/// The text of the value within a cell.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::default(),
+        Value::Boolean(value) => value.to_string(),
+        Value::String(value) => value.clone(),
+        Value::Number(value) => value.to_string(),
+        Value::Array(values) => values.iter().map(cell).collect::<Vec<_>>().join(", "),
+        Value::Map(map) => map
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key, cell(value)))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
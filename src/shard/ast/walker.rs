@@ -6,19 +6,15 @@ pub struct Cursor {
     index: NodeIndex,
 }
 
+#[derive(Default)]
 pub enum WalkerMode {
     /// Depth-first walking
     Depth,
     /// Breadth-first walking
+    #[default]
     Breadth,
 }
 
-impl Default for WalkerMode {
-    fn default() -> Self {
-        Self::Breadth
-    }
-}
-
 /// Recursively iterate over all nodes in the AST
 pub struct RefWalker<'tree> {
     ast: &'tree Ast,
@@ -89,4 +85,3 @@ impl<'tree> Iterator for RefWalker<'tree> {
         None
     }
 }
-
@@ -1,12 +1,17 @@
-mod ast;
+pub mod ast;
 mod value;
 
-use std::{error::Error, str::FromStr};
+use std::str::FromStr;
 
 pub use value::Value;
 
 use ast::Ast;
 
+use crate::{
+    config::MarkdownConfig,
+    error::{Error, Result},
+};
+
 use self::ast::walker::RefWalker;
 
 /// A shard is a piece of data within a Jewel.
@@ -16,10 +21,10 @@ pub struct Shard {
 }
 
 impl FromStr for Shard {
-    type Err = Box<dyn Error>;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let ast = ast::Ast::from_str(s).unwrap();
+    fn from_str(s: &str) -> Result<Self> {
+        let ast = ast::Ast::from_str(s)?;
         Ok(Self { ast })
     }
 }
@@ -28,12 +33,60 @@ pub struct Chapter {}
 
 impl Shard {
     /// Read the shard from a stream.
-    pub fn read<R: std::io::Read>(mut stream: R) -> Result<Self, Box<dyn Error>> {
+    pub fn read<R: std::io::Read>(mut stream: R) -> Result<Self> {
         let mut doc = String::default();
         stream.read_to_string(&mut doc)?;
         Self::from_str(&doc)
     }
 
+    /// Parse the shard, with the markdown constructs of the configuration.
+    pub fn parse(s: &str, config: &MarkdownConfig) -> Result<Self> {
+        let ast = Ast::parse(s, config.constructs())?;
+        Ok(Self { ast })
+    }
+
+    /// The title of the shard: the `title` property of the frontmatter,
+    /// or else the text of the first heading.
+    pub fn title(&self) -> Option<String> {
+        self.ast.title()
+    }
+
+    /// The properties of the shard: the frontmatter's properties merged with the
+    /// inline fields (`key:: value`, `[key:: value]` and `(key:: value)`).
+    ///
+    /// The values of a repeated key are accumulated into an array.
+    ///
+    /// ```
+    /// use emerald::shard::{Shard, Value};
+    ///
+    /// let shard: Shard = "---\ntags: [rust]\n---\nstatus:: draft\n\nMore [tags:: emerald] (rating:: 4)\n"
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// let properties = shard.properties().into_map().unwrap();
+    /// assert!(matches!(&properties["status"], Value::String(status) if status == "draft"));
+    /// assert!(matches!(&properties["tags"], Value::Array(tags) if tags.len() == 2));
+    /// assert!(matches!(&properties["rating"], Value::Number(_)));
+    /// ```
+    pub fn properties(&self) -> Value {
+        self.ast.properties()
+    }
+
+    /// The tags of the shard: the `tags` of the frontmatter, then the `#tags` of the content,
+    /// without duplicates. A nested tag is written `#area/sub-area`.
+    ///
+    /// ```
+    /// use emerald::shard::Shard;
+    ///
+    /// let shard: Shard = "---\ntags: [rust]\n---\nWorking on #project/emerald, #rust\n"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(shard.tags(), vec!["rust", "project/emerald"]);
+    /// ```
+    pub fn tags(&self) -> Vec<String> {
+        self.ast.tags()
+    }
+
     /// Read the shard from a string.
     pub fn walk_ref(&self) -> RefWalker<'_> {
         self.ast.walk_ref()
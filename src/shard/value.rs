@@ -1,7 +1,7 @@
-use core::panicking::panic;
-
 pub use indexmap::IndexMap;
 
+use crate::error::{Error, Result};
+
 #[derive(Debug, Clone)]
 pub enum Number {
     Integer(i64),
@@ -39,13 +39,12 @@ impl From<Number> for serde_yaml::Number {
 }
 
 impl From<serde_yaml::Number> for Number {
+    /// Integers out of the `i64` range are converted into floats.
     fn from(value: serde_yaml::Number) -> Self {
-        if let Some(unsigned) = value.as_u64() {
-            Self::Integer(unsigned.try_into().unwrap())
-        } else if let Some(signed) = value.as_i64() {
-            Self::Integer(signed)
-        } else {
-            Self::Float(value.as_f64().unwrap())
+        match (value.as_i64(), value.as_f64()) {
+            (Some(signed), _) => Self::Integer(signed),
+            (None, Some(float)) => Self::Float(float),
+            (None, None) => Self::Float(f64::NAN),
         }
     }
 }
@@ -62,12 +61,59 @@ pub enum Value {
 }
 
 impl Value {
-    pub fn expect_map(self) -> IndexMap<String, Value> {
+    /// Infer the value of an inline field: a boolean, a number, or else a string.
+    /// An empty value is null.
+    pub fn from_inline(raw: &str) -> Self {
+        let raw = raw.trim();
+        let numeric = raw
+            .strip_prefix('-')
+            .unwrap_or(raw)
+            .starts_with(|c: char| c.is_ascii_digit());
+
+        match raw {
+            "" => Self::Null,
+            "true" => Self::Boolean(true),
+            "false" => Self::Boolean(false),
+            _ if numeric => match (raw.parse::<i64>(), raw.parse::<f64>()) {
+                (Ok(integer), _) => Self::Number(integer.into()),
+                (_, Ok(float)) => Self::Number(float.into()),
+                _ => Self::String(raw.to_string()),
+            },
+            _ => Self::String(raw.to_string()),
+        }
+    }
+
+    /// Insert the value, the values of a repeated key are accumulated into an array.
+    pub(crate) fn accumulate(map: &mut IndexMap<String, Value>, key: &str, value: Value) {
+        match map.get_mut(key) {
+            None => {
+                map.insert(key.to_string(), value);
+            }
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => {
+                let first = std::mem::replace(existing, Value::Null);
+                *existing = Value::Array(vec![first, value]);
+            }
+        }
+    }
+
+    /// Returns the map, or None if the value is not a map.
+    pub fn into_map(self) -> Option<IndexMap<String, Value>> {
         match self {
-            Self::Map(map) => map,
-            _ => panic!("not a map"),
+            Self::Map(map) => Some(map),
+            _ => None,
         }
     }
+
+    /// Returns the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not a map.
+    #[deprecated(note = "use `Value::into_map`, which does not panic")]
+    pub fn expect_map(self) -> IndexMap<String, Value> {
+        self.into_map().expect("not a map")
+    }
 }
 
 impl From<Value> for serde_yaml::Value {
@@ -90,24 +136,47 @@ impl From<Value> for serde_yaml::Value {
     }
 }
 
-impl From<serde_yaml::Value> for Value {
-    fn from(value: serde_yaml::Value) -> Self {
-        match value {
+/// Convert a YAML value.
+///
+/// The tags are dropped (`!date 2024-01-01` is the string `2024-01-01`),
+/// the boolean and number keys are converted into strings,
+/// the other keys cannot be represented and are rejected.
+impl TryFrom<serde_yaml::Value> for Value {
+    type Error = Error;
+
+    fn try_from(value: serde_yaml::Value) -> Result<Self> {
+        Ok(match value {
             serde_yaml::Value::Null => Self::Null,
             serde_yaml::Value::Bool(value) => Self::Boolean(value),
             serde_yaml::Value::Number(value) => Self::Number(value.into()),
             serde_yaml::Value::String(value) => Self::String(value),
-            serde_yaml::Value::Sequence(value) => {
-                Self::Array(value.into_iter().map(Self::from).collect())
-            }
+            serde_yaml::Value::Sequence(value) => Self::Array(
+                value
+                    .into_iter()
+                    .map(Self::try_from)
+                    .collect::<Result<_>>()?,
+            ),
             serde_yaml::Value::Mapping(value) => Self::Map(
                 value
                     .into_iter()
-                    .map(|(k, v)| (k.as_str().unwrap().to_owned(), Self::from(v)))
-                    .collect(),
+                    .map(|(k, v)| Ok((yaml_key(k)?, Self::try_from(v)?)))
+                    .collect::<Result<_>>()?,
             ),
-            serde_yaml::Value::Tagged(_) => panic!("Yaml tagging is not handled"),
-        }
+            serde_yaml::Value::Tagged(tagged) => Self::try_from(tagged.value)?,
+        })
+    }
+}
+
+fn yaml_key(key: serde_yaml::Value) -> Result<String> {
+    match key {
+        serde_yaml::Value::String(key) => Ok(key),
+        serde_yaml::Value::Bool(key) => Ok(key.to_string()),
+        serde_yaml::Value::Number(key) => Ok(key.to_string()),
+        serde_yaml::Value::Tagged(tagged) => yaml_key(tagged.value),
+        key => Err(Error::frontmatter(format!(
+            "unsupported key: {}",
+            serde_yaml::to_string(&key).unwrap_or_default().trim()
+        ))),
     }
 }
 
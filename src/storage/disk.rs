@@ -0,0 +1,99 @@
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use super::{Metadata, Storage};
+
+/// Stores the jewel in a directory of the host filesystem.
+pub struct DiskStorage {
+    root: PathBuf,
+}
+
+impl DiskStorage {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Storage for DiskStorage {
+    fn root(&self) -> &std::path::Path {
+        &self.root
+    }
+
+    fn metadata(&self, path: &std::path::Path) -> std::io::Result<Metadata> {
+        let meta = std::fs::metadata(path)?;
+
+        Ok(Metadata {
+            is_dir: meta.is_dir(),
+            is_file: meta.is_file(),
+            len: meta.len(),
+            created: meta.created().ok().map(Into::into),
+            modified: meta.modified().ok().map(Into::into),
+            accessed: meta.accessed().ok().map(Into::into),
+        })
+    }
+
+    fn read_dir(&self, path: &std::path::Path) -> std::io::Result<Vec<String>> {
+        let mut names = Vec::default();
+
+        for entry in std::fs::read_dir(path)? {
+            if let Some(name) = entry?.file_name().to_str() {
+                names.push(name.to_string());
+            }
+        }
+
+        Ok(names)
+    }
+
+    fn open(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(std::fs::File::open(path)?))
+    }
+
+    fn create(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(std::fs::File::create(path)?))
+    }
+
+    fn create_new(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)?,
+        ))
+    }
+
+    fn create_dir(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::create_dir(path)
+    }
+
+    fn rename(&self, from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::remove_dir_all(path)
+    }
+
+    fn sync(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::File::open(path)?.sync_all()
+    }
+
+    fn copy_permissions(
+        &self,
+        from: &std::path::Path,
+        to: &std::path::Path,
+    ) -> std::io::Result<()> {
+        std::fs::set_permissions(to, std::fs::metadata(from)?.permissions())
+    }
+
+    fn read(&self, path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn write(&self, path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+        std::fs::write(path, contents)
+    }
+}
@@ -0,0 +1,382 @@
+use std::collections::{btree_map::Entry, BTreeMap};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Component, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+
+use super::{Metadata, Storage};
+use crate::path::InvalidPath;
+
+#[derive(Clone)]
+enum Kind {
+    Dir,
+    File(Vec<u8>),
+}
+
+#[derive(Clone)]
+struct Node {
+    kind: Kind,
+    created: DateTime<Utc>,
+    modified: DateTime<Utc>,
+}
+
+impl Node {
+    fn new(kind: Kind) -> Self {
+        let now = Utc::now();
+
+        Self {
+            kind,
+            created: now,
+            modified: now,
+        }
+    }
+
+    fn dir() -> Self {
+        Self::new(Kind::Dir)
+    }
+
+    fn file(contents: Vec<u8>) -> Self {
+        Self::new(Kind::File(contents))
+    }
+}
+
+type Nodes = Arc<RwLock<BTreeMap<PathBuf, Node>>>;
+
+#[derive(Clone)]
+/// Stores the jewel in memory, the root is `/`.
+///
+/// ```
+/// use emerald::{storage::MemoryStorage, Emerald};
+///
+/// let storage = MemoryStorage::from_iter([
+///     ("/index.md", "# Index"),
+///     ("/projects/alpha.md", "# Alpha"),
+/// ]);
+///
+/// let jewel = Emerald::from_storage(storage);
+/// ```
+pub struct MemoryStorage {
+    root: PathBuf,
+    nodes: Nodes,
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryStorage {
+    /// Creates an empty storage.
+    pub fn new() -> Self {
+        let root = PathBuf::from("/");
+        let nodes = BTreeMap::from([(root.clone(), Node::dir())]);
+
+        Self {
+            root,
+            nodes: Arc::new(RwLock::new(nodes)),
+        }
+    }
+
+    /// Inserts a file, and creates all its missing parent directories.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path has a `..` component.
+    pub fn insert<P, C>(&self, path: P, contents: C)
+    where
+        P: AsRef<std::path::Path>,
+        C: Into<Vec<u8>>,
+    {
+        let path = Self::key(self.root.join(path).as_path()).expect("a path without `..`");
+        let mut nodes = self.nodes.write().unwrap();
+
+        for ancestor in path.ancestors().skip(1) {
+            nodes.entry(ancestor.to_owned()).or_insert_with(Node::dir);
+        }
+
+        nodes.insert(path, Node::file(contents.into()));
+    }
+
+    /// Normalize the path to be used as a key.
+    ///
+    /// The `..` components are rejected, as the canonical paths of a jewel never hold any.
+    fn key(path: &std::path::Path) -> std::io::Result<PathBuf> {
+        path.components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .map(|c| match c {
+                Component::ParentDir => Err(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    InvalidPath(path.display().to_string()),
+                )),
+                c => Ok(c),
+            })
+            .collect()
+    }
+
+    fn get(&self, path: &std::path::Path) -> std::io::Result<Node> {
+        self.nodes
+            .read()
+            .unwrap()
+            .get(&Self::key(path)?)
+            .cloned()
+            .ok_or_else(|| not_found(path))
+    }
+
+    /// Checks the parent of the path is an existing directory.
+    fn check_parent(&self, path: &std::path::Path) -> std::io::Result<()> {
+        match path
+            .parent()
+            .map(|parent| self.get(parent).map(|node| node.kind))
+        {
+            Some(Ok(Kind::Dir)) => Ok(()),
+            Some(Ok(Kind::File(_))) => Err(ErrorKind::NotADirectory.into()),
+            Some(Err(err)) => Err(err),
+            None => Err(ErrorKind::InvalidInput.into()),
+        }
+    }
+}
+
+impl<P, C> FromIterator<(P, C)> for MemoryStorage
+where
+    P: AsRef<std::path::Path>,
+    C: Into<Vec<u8>>,
+{
+    fn from_iter<T: IntoIterator<Item = (P, C)>>(iter: T) -> Self {
+        let storage = Self::new();
+
+        for (path, contents) in iter {
+            storage.insert(path, contents);
+        }
+
+        storage
+    }
+}
+
+fn not_found(path: &std::path::Path) -> std::io::Error {
+    std::io::Error::new(
+        ErrorKind::NotFound,
+        format!("{} does not exist", path.display()),
+    )
+}
+
+impl Storage for MemoryStorage {
+    fn root(&self) -> &std::path::Path {
+        &self.root
+    }
+
+    fn metadata(&self, path: &std::path::Path) -> std::io::Result<Metadata> {
+        let node = self.get(path)?;
+
+        Ok(Metadata {
+            is_dir: matches!(node.kind, Kind::Dir),
+            is_file: matches!(node.kind, Kind::File(_)),
+            len: match &node.kind {
+                Kind::File(contents) => contents.len() as u64,
+                Kind::Dir => 0,
+            },
+            created: Some(node.created),
+            modified: Some(node.modified),
+            accessed: None,
+        })
+    }
+
+    fn read_dir(&self, path: &std::path::Path) -> std::io::Result<Vec<String>> {
+        if let Kind::File(_) = self.get(path)?.kind {
+            return Err(ErrorKind::NotADirectory.into());
+        }
+
+        let path = Self::key(path)?;
+
+        Ok(self
+            .nodes
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|key| key.parent() == Some(path.as_path()))
+            .filter_map(|key| Some(key.file_name()?.to_str()?.to_string()))
+            .collect())
+    }
+
+    fn open(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Read + Send>> {
+        match self.get(path)?.kind {
+            Kind::File(contents) => Ok(Box::new(std::io::Cursor::new(contents))),
+            Kind::Dir => Err(ErrorKind::IsADirectory.into()),
+        }
+    }
+
+    fn create(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Write + Send>> {
+        self.check_parent(path)?;
+
+        // Truncating an existing file keeps its creation time.
+        let created = match self.get(path) {
+            Ok(Node {
+                kind: Kind::Dir, ..
+            }) => return Err(ErrorKind::IsADirectory.into()),
+            Ok(node) => Some(node.created),
+            Err(_) => None,
+        };
+
+        let path = Self::key(path)?;
+        let mut node = Node::file(Vec::default());
+        node.created = created.unwrap_or(node.created);
+
+        self.nodes.write().unwrap().insert(path.clone(), node);
+
+        Ok(Box::new(MemoryWriter {
+            nodes: self.nodes.clone(),
+            path,
+            buf: Vec::default(),
+        }))
+    }
+
+    fn create_new(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Write + Send>> {
+        self.check_parent(path)?;
+
+        let path = Self::key(path)?;
+
+        match self.nodes.write().unwrap().entry(path.clone()) {
+            Entry::Occupied(_) => return Err(ErrorKind::AlreadyExists.into()),
+            Entry::Vacant(entry) => {
+                entry.insert(Node::file(Vec::default()));
+            }
+        }
+
+        Ok(Box::new(MemoryWriter {
+            nodes: self.nodes.clone(),
+            path,
+            buf: Vec::default(),
+        }))
+    }
+
+    fn create_dir(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.check_parent(path)?;
+
+        if self.exists(path) {
+            return Err(ErrorKind::AlreadyExists.into());
+        }
+
+        self.nodes
+            .write()
+            .unwrap()
+            .insert(Self::key(path)?, Node::dir());
+        Ok(())
+    }
+
+    /// Renames as POSIX does: a file replaces a file, and a directory an empty directory.
+    fn rename(&self, from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+        let source = self.get(from)?;
+        self.check_parent(to)?;
+
+        let from = Self::key(from)?;
+        let to = Self::key(to)?;
+
+        if to == from {
+            return Ok(());
+        }
+
+        if to.starts_with(&from) {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+
+        let mut nodes = self.nodes.write().unwrap();
+
+        match (&source.kind, nodes.get(&to).map(|node| &node.kind)) {
+            (Kind::File(_), Some(Kind::Dir)) => return Err(ErrorKind::IsADirectory.into()),
+            (Kind::Dir, Some(Kind::File(_))) => return Err(ErrorKind::NotADirectory.into()),
+            (Kind::Dir, Some(Kind::Dir)) => {
+                if nodes.keys().any(|key| key.parent() == Some(to.as_path())) {
+                    return Err(ErrorKind::DirectoryNotEmpty.into());
+                }
+
+                nodes.remove(&to);
+            }
+            _ => {}
+        }
+
+        let moved = nodes
+            .keys()
+            .filter(|key| key.starts_with(&from))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Replacing a file keeps its creation time, as an atomic save is a replacement.
+        let created = match nodes.get(&to) {
+            Some(Node {
+                kind: Kind::File(_),
+                created,
+                ..
+            }) => Some(*created),
+            _ => None,
+        };
+
+        for key in moved {
+            let mut node = nodes.remove(&key).unwrap();
+            let dest = to.join(key.strip_prefix(&from).unwrap());
+
+            if key == from {
+                node.created = created.unwrap_or(node.created);
+            }
+
+            nodes.insert(Self::key(&dest)?, node);
+        }
+
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        match self.get(path)?.kind {
+            Kind::File(_) => {
+                self.nodes.write().unwrap().remove(&Self::key(path)?);
+                Ok(())
+            }
+            Kind::Dir => Err(ErrorKind::IsADirectory.into()),
+        }
+    }
+
+    fn remove_dir_all(&self, path: &std::path::Path) -> std::io::Result<()> {
+        if let Kind::File(_) = self.get(path)?.kind {
+            return Err(ErrorKind::NotADirectory.into());
+        }
+
+        let path = Self::key(path)?;
+        self.nodes
+            .write()
+            .unwrap()
+            .retain(|key, _| !key.starts_with(&path));
+
+        Ok(())
+    }
+}
+
+/// Buffers the written bytes, and commits them to the storage when flushed or dropped.
+struct MemoryWriter {
+    nodes: Nodes,
+    path: PathBuf,
+    buf: Vec<u8>,
+}
+
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let mut nodes = self.nodes.write().unwrap();
+        let node = nodes
+            .entry(self.path.clone())
+            .or_insert_with(|| Node::file(Vec::default()));
+
+        node.kind = Kind::File(self.buf.clone());
+        node.modified = Utc::now();
+        Ok(())
+    }
+}
+
+impl Drop for MemoryWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
@@ -0,0 +1,104 @@
+//! Storage backends of a jewel.
+//!
+//! The jewel filesystem ([crate::fs]) resolves jewel paths into canonical paths,
+//! and delegates the actual I/O to the [Storage] owned by the [crate::Emerald].
+mod disk;
+mod memory;
+
+use std::io::{Read, Write};
+
+use chrono::{DateTime, Utc};
+
+pub use disk::DiskStorage;
+pub use memory::MemoryStorage;
+
+#[derive(Clone, Debug)]
+/// Raw metadata returned by a storage backend.
+pub struct Metadata {
+    pub is_dir: bool,
+    pub is_file: bool,
+    /// The size of the file, in bytes.
+    pub len: u64,
+    /// The timestamps are None if the backend does not support them.
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub accessed: Option<DateTime<Utc>>,
+}
+
+/// A storage backend, operating on canonical paths located under its root.
+pub trait Storage: Send + Sync {
+    /// The canonical path of the jewel's root.
+    fn root(&self) -> &std::path::Path;
+
+    /// Returns the metadata of the file or directory.
+    fn metadata(&self, path: &std::path::Path) -> std::io::Result<Metadata>;
+
+    /// Returns the names of the entries within the directory.
+    fn read_dir(&self, path: &std::path::Path) -> std::io::Result<Vec<String>>;
+
+    /// Opens the file in read-only mode.
+    fn open(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Read + Send>>;
+
+    /// Opens the file in write-only mode, creates it if it does not exist, and truncates it if it does.
+    fn create(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Write + Send>>;
+
+    /// Opens a new file in write-only mode, fails if it already exists.
+    ///
+    /// The backends should check and create the file atomically, the default implementation does not.
+    fn create_new(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Write + Send>> {
+        if self.exists(path) {
+            return Err(std::io::ErrorKind::AlreadyExists.into());
+        }
+
+        self.create(path)
+    }
+
+    /// Creates a new, empty directory.
+    fn create_dir(&self, path: &std::path::Path) -> std::io::Result<()>;
+
+    /// Renames a file or a directory.
+    fn rename(&self, from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()>;
+
+    /// Removes a file.
+    fn remove_file(&self, path: &std::path::Path) -> std::io::Result<()>;
+
+    /// Removes a directory after removing all its contents.
+    fn remove_dir_all(&self, path: &std::path::Path) -> std::io::Result<()>;
+
+    /// Flushes the file or directory to the device.
+    ///
+    /// The default implementation does nothing, for the backends without a device.
+    fn sync(&self, _path: &std::path::Path) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Gives the permissions of a file to another one.
+    ///
+    /// The default implementation does nothing, for the backends without permissions.
+    fn copy_permissions(
+        &self,
+        _from: &std::path::Path,
+        _to: &std::path::Path,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Returns true if the file or directory exists.
+    fn exists(&self, path: &std::path::Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    /// Reads the entire contents of a file.
+    fn read(&self, path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+        let mut buf = Vec::default();
+        self.open(path)?.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Writes a slice as the entire contents of a file.
+    fn write(&self, path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+        let mut file = self.create(path)?;
+        file.write_all(contents)?;
+        file.flush()
+    }
+}
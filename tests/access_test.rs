@@ -0,0 +1,216 @@
+use emerald::{path::Path, script::ScriptEngine, storage::MemoryStorage, Emerald, Error};
+
+mod common;
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        ("/index.md", "# Index"),
+        ("/projects/alpha.md", "# Alpha"),
+        ("/projects/secret.md", "# Secret"),
+        ("/projects/drafts/beta.md", "# Beta"),
+        ("/private/diary.md", "# Diary"),
+        ("/projects/diary", "@/>/private/diary.md"),
+    ]))
+}
+
+fn denied<T>(result: emerald::Result<T>) -> bool {
+    matches!(result, Err(Error::PermissionDenied(_)))
+}
+
+fn names(jewel: &Emerald, dir: &str) -> Vec<String> {
+    let mut names = emerald::fs::read_dir(jewel, &path(dir))
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_read_only() {
+    let jewel = memory_jewel();
+    let reader = jewel.read_only();
+    let index = path("/index.md");
+
+    assert!(reader.access().is_read_only());
+    assert!(emerald::fs::open(&reader, &index).is_ok());
+    assert!(denied(emerald::fs::write(&reader, &index, "# Changed")));
+    assert!(denied(emerald::fs::create(&reader, &path("/new.md"))));
+    assert!(denied(emerald::fs::remove_file(&reader, &index)));
+    assert!(denied(emerald::fs::create_dir_all(&reader, &path("/new"))));
+    assert!(denied(emerald::fs::rename(
+        &reader,
+        &index,
+        &path("/home.md")
+    )));
+    assert!(denied(emerald::fs::symlink(
+        &reader,
+        &path("/home"),
+        "/index.md"
+    )));
+    assert!(denied(emerald::fs::trash(&reader, &index)));
+
+    // The original handle is unrestricted.
+    assert!(jewel.access().is_unrestricted());
+    emerald::fs::write(&jewel, &index, "# Changed").unwrap();
+}
+
+#[test]
+fn test_scoped_to() {
+    let jewel = memory_jewel();
+    let projects = jewel.scoped_to(&path("/projects"));
+
+    assert_eq!(
+        names(&projects, "/projects"),
+        vec![
+            "/projects/alpha.md",
+            "/projects/diary",
+            "/projects/drafts",
+            "/projects/secret.md"
+        ]
+    );
+    assert!(denied(emerald::fs::read_dir(&projects, &Path::root())));
+    assert!(denied(emerald::fs::open(&projects, &path("/index.md"))));
+
+    // A symbolic link does not escape the scope.
+    assert!(denied(emerald::fs::open(
+        &projects,
+        &path("/projects/diary")
+    )));
+
+    // The writes within the scope are recorded in the history.
+    let alpha = path("/projects/alpha.md");
+    emerald::fs::write(&projects, &alpha, "# Alpha 2").unwrap();
+    assert_eq!(emerald::fs::history(&projects, &alpha).unwrap().len(), 1);
+    assert!(denied(emerald::fs::write(
+        &projects,
+        &path("/index.md"),
+        ""
+    )));
+
+    // A scope can only be narrowed.
+    let drafts = projects.scoped_to(&path("/projects/drafts"));
+    assert!(denied(emerald::fs::open(&drafts, &alpha)));
+    assert!(!projects
+        .scoped_to(&path("/private"))
+        .access()
+        .can_read(&path("/private/diary.md")));
+}
+
+#[test]
+fn test_allow_deny() {
+    let jewel = memory_jewel();
+    let handle = jewel
+        .allow(&[path("/projects"), path("/index.md")])
+        .deny(&[path("/projects/secret.md"), path("/projects/drafts")]);
+
+    assert_eq!(names(&handle, "/"), vec!["/index.md", "/projects"]);
+    assert_eq!(
+        names(&handle, "/projects"),
+        vec!["/projects/alpha.md", "/projects/diary"]
+    );
+    assert!(denied(emerald::fs::open(
+        &handle,
+        &path("/projects/secret.md")
+    )));
+    assert!(denied(emerald::fs::metadata(
+        &handle,
+        &path("/projects/drafts/beta.md")
+    )));
+
+    let walked = emerald::fs::walk(&handle, &Path::root())
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .count();
+    assert_eq!(walked, 4);
+
+    // The parents of the allowed paths cannot be modified.
+    assert!(denied(emerald::fs::write(&handle, &path("/new.md"), "")));
+    emerald::fs::write(&handle, &path("/projects/new.md"), "").unwrap();
+}
+
+#[test]
+fn test_write_through_symlink() {
+    let jewel = memory_jewel();
+    emerald::fs::symlink(&jewel, &path("/projects/drafts/up"), "/projects").unwrap();
+    emerald::fs::symlink(&jewel, &path("/projects/drafts/index"), "/index.md").unwrap();
+    let handle = jewel.allow(&[path("/projects/drafts")]);
+
+    // The links can be read through, but not written through, out of the allowed paths.
+    assert!(emerald::fs::open(&handle, &path("/projects/drafts/up/drafts/beta.md")).is_ok());
+    assert!(denied(emerald::fs::write(
+        &handle,
+        &path("/projects/drafts/up/x.md"),
+        ""
+    )));
+    assert!(denied(emerald::fs::write(
+        &handle,
+        &path("/projects/drafts/index"),
+        ""
+    )));
+    assert!(denied(emerald::fs::create_dir_all(
+        &handle,
+        &path("/projects/drafts/up/new")
+    )));
+    assert!(emerald::fs::metadata(&jewel, &path("/projects/x.md")).is_err());
+
+    // A link to an allowed path is still written through.
+    emerald::fs::symlink(&jewel, &path("/projects/drafts/here"), ".").unwrap();
+    emerald::fs::write(&handle, &path("/projects/drafts/here/gamma.md"), "").unwrap();
+    assert!(emerald::fs::metadata(&jewel, &path("/projects/drafts/gamma.md")).is_ok());
+}
+
+#[test]
+fn test_storage_symlink() {
+    let root = temp_emerald!("access_storage_symlink");
+    let target = temp_emerald!("access_storage_symlink_target");
+    std::fs::write(target.join("host.md"), "# Host").unwrap();
+    std::fs::write(root.join("link"), format!("@/>{}", target.display())).unwrap();
+
+    let jewel = emerald::open(&root).unwrap();
+    let host = path("/link/host.md");
+
+    // A restricted handle cannot follow a link out of the jewel, where its rules cannot apply.
+    for handle in [jewel.read_only(), jewel.deny(&[path("/private")])] {
+        assert!(denied(emerald::fs::open(&handle, &host)));
+        assert!(denied(emerald::fs::write(&handle, &host, "# Changed")));
+    }
+
+    // An unrestricted handle reads through it, but writes only if explicitly allowed.
+    assert!(emerald::fs::open(&jewel, &host).is_ok());
+    assert!(denied(emerald::fs::write(&jewel, &host, "# Changed")));
+    assert!(denied(emerald::fs::write(
+        &jewel.allow_storage_writes().deny(&[path("/private")]),
+        &host,
+        "# Changed"
+    )));
+    assert_eq!(
+        std::fs::read_to_string(target.join("host.md")).unwrap(),
+        "# Host"
+    );
+
+    emerald::fs::write(&jewel.allow_storage_writes(), &host, "# Changed").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(target.join("host.md")).unwrap(),
+        "# Changed"
+    );
+}
+
+#[test]
+fn test_script_bindings() -> Result<(), Box<dyn std::error::Error>> {
+    let jewel = memory_jewel().read_only().scoped_to(&path("/projects"));
+    let mut scripts = ScriptEngine::new();
+    let inst = scripts.new_instance(&jewel)?;
+
+    inst.execute(r#"assert(emerald.fs.open("/projects/alpha.md", 0))"#)?;
+    assert!(inst.execute(r#"emerald.fs.open("/index.md", 0)"#).is_err());
+    assert!(inst
+        .execute(r#"emerald.fs.trash("/projects/alpha.md")"#)
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "async")]
+
+use std::{future::poll_fn, pin::Pin};
+
+use emerald::{
+    fs::{aio, WalkBuilder},
+    path::Path,
+    storage::MemoryStorage,
+    Emerald, Error,
+};
+use futures_core::Stream;
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        ("/index.md", "# Index"),
+        ("/projects/alpha.md", "# Alpha"),
+        ("/projects/beta.md", "# Beta"),
+        ("/latest", "@/>projects/beta.md"),
+    ]))
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+#[test]
+fn test_read_dir_stream() {
+    let jewel = memory_jewel();
+
+    block_on(async {
+        let mut stream = aio::read_dir(&jewel, &path("/projects")).await.unwrap();
+        let mut names = vec![];
+
+        while let Some(entry) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            names.push(entry.unwrap().path().to_string());
+        }
+
+        names.sort();
+        assert_eq!(names, vec!["/projects/alpha.md", "/projects/beta.md"]);
+
+        assert!(matches!(
+            aio::read_dir(&jewel, &path("/missing")).await,
+            Err(Error::NotFound(_))
+        ));
+    });
+}
+
+#[test]
+fn test_walk_stream() {
+    let jewel = memory_jewel();
+
+    block_on(async {
+        let builder = WalkBuilder::new(&jewel, &Path::root()).include("*.md");
+        let mut walk = aio::walk_with(builder).await.unwrap();
+        let mut paths = vec![];
+
+        while let Some(entry) = walk.next().await {
+            paths.push(entry.unwrap().path().to_string());
+        }
+
+        assert_eq!(
+            paths,
+            vec!["/index.md", "/projects/alpha.md", "/projects/beta.md"]
+        );
+
+        // The stream can be dropped before its end.
+        let mut walk = aio::walk(&jewel, &Path::root()).await.unwrap();
+        assert!(walk.next().await.is_some());
+        drop(walk);
+    });
+}
+
+#[test]
+fn test_read_write() {
+    let jewel = memory_jewel();
+
+    block_on(async {
+        // The symbolic links are resolved like the sync API.
+        let shard = aio::read_shard(&jewel, &path("/latest")).await.unwrap();
+        assert_eq!(shard.title().as_deref(), Some("Beta"));
+
+        let draft = path("/drafts/gamma.md");
+        aio::create_dir_all(&jewel, &path("/drafts")).await.unwrap();
+        aio::write(&jewel, &draft, "# Gamma").await.unwrap();
+        assert_eq!(aio::read(&jewel, &draft).await.unwrap(), b"# Gamma");
+        assert_eq!(aio::metadata(&jewel, &draft).await.unwrap().len(), 7);
+
+        let copy = path("/drafts/delta.md");
+        assert_eq!(aio::copy(&jewel, &draft, &copy).await.unwrap(), 7);
+        aio::rename(&jewel, &copy, &path("/delta.md"))
+            .await
+            .unwrap();
+        aio::remove_file(&jewel, &path("/delta.md")).await.unwrap();
+        aio::remove_dir_all(&jewel, &path("/drafts")).await.unwrap();
+        assert!(aio::metadata(&jewel, &draft).await.is_err());
+
+        assert!(matches!(
+            aio::write(&jewel.read_only(), &path("/index.md"), "").await,
+            Err(Error::PermissionDenied(_))
+        ));
+    });
+}
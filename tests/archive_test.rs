@@ -0,0 +1,411 @@
+use std::io::{Read, Write};
+
+use emerald::{
+    fs::{ConflictPolicy, ExportOptions, ImportOptions},
+    path::Path,
+    storage::{MemoryStorage, Metadata, Storage},
+    Emerald,
+};
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        ("/.emerald/config.toml", ""),
+        ("/.hidden/notes.md", "# Notes"),
+        ("/index.md", "# Index"),
+        ("/projects/alpha.md", "# Alpha"),
+        ("/projects/data.json", "{}"),
+        ("/latest", "@/>projects/alpha.md"),
+    ]))
+}
+
+fn read(jewel: &Emerald, value: &str) -> String {
+    String::from_utf8(
+        jewel
+            .storage()
+            .read(&jewel.get_root().join(&value[1..]))
+            .unwrap(),
+    )
+    .unwrap()
+}
+
+/// A tar archive with a raw entry name, which the builder would refuse.
+fn raw_archive(name: &str) -> Vec<u8> {
+    let mut header = tar::Header::new_old();
+    header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+    header.set_size(4);
+    header.set_mode(0o644);
+    header.set_cksum();
+
+    let mut builder = tar::Builder::new(Vec::default());
+    builder.append(&header, "evil".as_bytes()).unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(4);
+    header.set_mode(0o644);
+    builder
+        .append_data(&mut header, "safe.md", "safe".as_bytes())
+        .unwrap();
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn test_export_import() {
+    let jewel = memory_jewel();
+
+    for gzip in [false, true] {
+        let mut archive = Vec::default();
+        let options = ExportOptions {
+            gzip,
+            ..ExportOptions::default()
+        };
+        assert_eq!(
+            jewel
+                .export_archive(&mut archive, &options)
+                .unwrap()
+                .archived,
+            5
+        );
+        assert_eq!(archive.starts_with(&[0x1f, 0x8b]), gzip);
+
+        let copy = Emerald::from_storage(MemoryStorage::new());
+        let report = copy
+            .import_archive(
+                archive.as_slice(),
+                &path("/backup"),
+                ImportOptions::default(),
+            )
+            .unwrap();
+
+        assert_eq!(report.imported.len(), 4);
+        assert_eq!(read(&copy, "/backup/projects/alpha.md"), "# Alpha");
+
+        // The symbolic link is kept as a link, and the hidden files are not archived.
+        assert_eq!(read(&copy, "/backup/latest"), "@/>projects/alpha.md");
+        assert!(emerald::fs::metadata(&copy, &path("/backup/latest"))
+            .unwrap()
+            .is_symlink());
+        assert!(emerald::fs::metadata(&copy, &path("/backup/.emerald")).is_err());
+    }
+}
+
+/// A reader returning a single byte per read.
+struct ByteByByte<'a>(&'a [u8]);
+
+impl Read for ByteByByte<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = buf.len().min(self.0.len()).min(1);
+        buf[..count].copy_from_slice(&self.0[..count]);
+        self.0 = &self.0[count..];
+        Ok(count)
+    }
+}
+
+#[test]
+fn test_import_short_reads() {
+    let jewel = memory_jewel();
+    let mut archive = Vec::default();
+    let options = ExportOptions {
+        gzip: true,
+        ..ExportOptions::default()
+    };
+    jewel.export_archive(&mut archive, &options).unwrap();
+
+    let copy = Emerald::from_storage(MemoryStorage::new());
+    let report = copy
+        .import_archive(
+            ByteByByte(&archive),
+            &Path::root(),
+            ImportOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(report.imported.len(), 4);
+    assert_eq!(read(&copy, "/projects/alpha.md"), "# Alpha");
+
+    // An empty stream is an empty archive.
+    let report = copy
+        .import_archive(ByteByByte(&[]), &Path::root(), ImportOptions::default())
+        .unwrap();
+    assert!(report.imported.is_empty());
+}
+
+#[test]
+fn test_export_filter() {
+    let jewel = memory_jewel();
+    let mut archive = Vec::default();
+    let options = ExportOptions {
+        hidden: true,
+        include: vec!["*.md".to_string(), "/.emerald/*".to_string()],
+        exclude: vec!["/projects".to_string()],
+        ..ExportOptions::default()
+    };
+    jewel.export_archive(&mut archive, &options).unwrap();
+
+    let copy = Emerald::from_storage(MemoryStorage::new());
+    let report = copy
+        .import_archive(archive.as_slice(), &Path::root(), ImportOptions::default())
+        .unwrap();
+    // The internal directory of the jewel is never archived.
+    assert_eq!(
+        report.imported,
+        vec![path("/.hidden/notes.md"), path("/index.md")]
+    );
+}
+
+#[test]
+fn test_import_rejects_internal_entries() {
+    let mut builder = tar::Builder::new(Vec::default());
+
+    for name in [".emerald/config.toml", "notes/.emerald/kept.md"] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, name, "pwned".as_bytes())
+            .unwrap();
+    }
+
+    let archive = builder.into_inner().unwrap();
+    let jewel = memory_jewel();
+    let report = jewel
+        .import_archive(archive.as_slice(), &Path::root(), ImportOptions::default())
+        .unwrap();
+
+    assert_eq!(report.rejected, vec![".emerald/config.toml".to_string()]);
+    assert_eq!(report.imported, vec![path("/notes/.emerald/kept.md")]);
+    assert_eq!(read(&jewel, "/.emerald/config.toml"), "");
+
+    let report = jewel
+        .import_archive(
+            archive.as_slice(),
+            &path("/.emerald"),
+            ImportOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(report.rejected.len(), 2);
+    assert!(report.imported.is_empty());
+}
+
+#[test]
+fn test_import_rejects_escaping_entries() {
+    for name in ["../evil.md", "/evil.md", "notes/../../evil.md"] {
+        let jewel = Emerald::from_storage(MemoryStorage::new());
+        let report = jewel
+            .import_archive(
+                raw_archive(name).as_slice(),
+                &path("/into"),
+                ImportOptions::default(),
+            )
+            .unwrap();
+
+        assert_eq!(report.rejected, vec![name.to_string()]);
+        assert_eq!(report.imported, vec![path("/into/safe.md")]);
+        assert!(emerald::fs::metadata(&jewel, &path("/evil.md")).is_err());
+    }
+}
+
+#[test]
+fn test_import_conflicts() {
+    let jewel = memory_jewel();
+    let mut archive = Vec::default();
+    jewel
+        .export_archive(&mut archive, &ExportOptions::default())
+        .unwrap();
+    emerald::fs::write(&jewel, &path("/index.md"), "# Changed").unwrap();
+
+    let import = |conflict| {
+        jewel
+            .import_archive(
+                archive.as_slice(),
+                &Path::root(),
+                ImportOptions { conflict },
+            )
+            .unwrap()
+    };
+
+    let report = import(ConflictPolicy::Skip);
+    assert_eq!(report.skipped.len(), 4);
+    assert_eq!(read(&jewel, "/index.md"), "# Changed");
+
+    let report = import(ConflictPolicy::Rename);
+    assert!(report.imported.contains(&path("/index (1).md")));
+    assert!(report.imported.contains(&path("/latest (1)")));
+    assert_eq!(read(&jewel, "/index (1).md"), "# Index");
+    assert_eq!(read(&jewel, "/index.md"), "# Changed");
+
+    let report = import(ConflictPolicy::Overwrite);
+    assert_eq!(report.imported.len(), 4);
+    assert_eq!(read(&jewel, "/index.md"), "# Index");
+    assert_eq!(read(&jewel, "/latest"), "@/>projects/alpha.md");
+
+    // The overwritten files are moved into the trash, the import can be undone.
+    let trashed = emerald::fs::list_trash(&jewel).unwrap();
+    let index = trashed
+        .iter()
+        .find(|entry| entry.path == path("/index.md"))
+        .unwrap();
+    emerald::fs::remove_file(&jewel, &path("/index.md")).unwrap();
+    emerald::fs::restore(&jewel, &index.id).unwrap();
+    assert_eq!(read(&jewel, "/index.md"), "# Changed");
+}
+
+#[test]
+fn test_import_rename_exhausted() {
+    let jewel = Emerald::from_storage(MemoryStorage::from_iter(
+        std::iter::once("/safe.md".to_string())
+            .chain((1..=1000).map(|count| format!("/safe ({}).md", count)))
+            .map(|name| (name, "")),
+    ));
+
+    assert!(matches!(
+        jewel.import_archive(
+            raw_archive("../evil.md").as_slice(),
+            &Path::root(),
+            ImportOptions {
+                conflict: ConflictPolicy::Rename
+            },
+        ),
+        Err(emerald::Error::AlreadyExists(_))
+    ));
+}
+
+/// A tar archive of symbolic links and files, in order.
+fn link_archive(entries: &[(&str, Option<&str>)]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::default());
+
+    for (name, target) in entries {
+        let mut header = tar::Header::new_gnu();
+
+        match target {
+            Some(target) => {
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_size(0);
+                builder.append_link(&mut header, name, target).unwrap();
+            }
+            None => {
+                header.set_size(5);
+                header.set_mode(0o644);
+                builder
+                    .append_data(&mut header, name, "pwned".as_bytes())
+                    .unwrap();
+            }
+        }
+    }
+
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn test_import_rejects_symlink_traversal() {
+    let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+        ("/into/notes/.keep", ""),
+        ("/into/existing", "@/>../../outside"),
+    ]));
+
+    let archive = link_archive(&[
+        ("absolute", Some("/tmp/outside")),
+        ("escaping", Some("../../outside")),
+        ("docs", Some("notes")),
+        ("docs/pwned.md", None),
+        ("existing/pwned.md", None),
+        ("absolute/pwned.md", None),
+    ]);
+    let report = jewel
+        .import_archive(archive.as_slice(), &path("/into"), ImportOptions::default())
+        .unwrap();
+
+    assert_eq!(
+        report.rejected,
+        vec!["absolute", "escaping", "docs/pwned.md", "existing/pwned.md"]
+    );
+    // The relative link within the jewel is kept, the file is not written through any link.
+    assert_eq!(
+        report.imported,
+        vec![path("/into/docs"), path("/into/absolute/pwned.md")]
+    );
+    assert!(emerald::fs::metadata(&jewel, &path("/into/notes/pwned.md")).is_err());
+    assert!(!emerald::fs::metadata(&jewel, &path("/into/absolute"))
+        .unwrap()
+        .is_symlink());
+}
+
+/// A memory storage which cannot read the `secret.md` files, nor list the `locked` directories.
+struct Unreadable(MemoryStorage);
+
+fn denied() -> std::io::Error {
+    std::io::Error::other("unreadable")
+}
+
+impl Storage for Unreadable {
+    fn root(&self) -> &std::path::Path {
+        self.0.root()
+    }
+
+    fn metadata(&self, path: &std::path::Path) -> std::io::Result<Metadata> {
+        self.0.metadata(path)
+    }
+
+    fn read_dir(&self, path: &std::path::Path) -> std::io::Result<Vec<String>> {
+        match path.ends_with("locked") {
+            true => Err(denied()),
+            false => self.0.read_dir(path),
+        }
+    }
+
+    fn open(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Read + Send>> {
+        match path.ends_with("secret.md") {
+            true => Err(denied()),
+            false => self.0.open(path),
+        }
+    }
+
+    fn create(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Write + Send>> {
+        self.0.create(path)
+    }
+
+    fn create_dir(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.0.create_dir(path)
+    }
+
+    fn rename(&self, from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+        self.0.rename(from, to)
+    }
+
+    fn remove_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.0.remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.0.remove_dir_all(path)
+    }
+}
+
+#[test]
+fn test_export_skips_unreadable_entries() {
+    let jewel = Emerald::from_storage(Unreadable(MemoryStorage::from_iter([
+        ("/index.md", "# Index"),
+        ("/secret.md", "# Secret"),
+        ("/locked/alpha.md", "# Alpha"),
+    ])));
+
+    let mut archive = Vec::default();
+    let report = jewel
+        .export_archive(&mut archive, &ExportOptions::default())
+        .unwrap();
+
+    // The locked directory itself is archived, not its content.
+    assert_eq!(report.archived, 2);
+    assert_eq!(report.skipped.len(), 2);
+    assert!(report
+        .skipped
+        .iter()
+        .any(|entry| entry.contains("/secret.md")));
+
+    let copy = Emerald::from_storage(MemoryStorage::new());
+    let report = copy
+        .import_archive(archive.as_slice(), &Path::root(), ImportOptions::default())
+        .unwrap();
+    assert_eq!(report.imported, vec![path("/index.md")]);
+}
@@ -11,3 +11,13 @@ macro_rules! test_emerald {
         test_case!("jewel")
     };
 }
+
+#[macro_export]
+macro_rules! temp_emerald {
+    ($name:expr) => {{
+        let root = std::env::temp_dir().join("emerald-tests").join($name);
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }};
+}
@@ -0,0 +1,140 @@
+use std::error::Error;
+
+use chrono::NaiveDate;
+use emerald::{path::Path, script::ScriptEngine, storage::MemoryStorage, Config, Emerald};
+
+mod common;
+
+const CONFIG: &str = r#"
+frontmatter = "toml"
+attachments = "assets"
+ignore = ["*.tmp", "/archive"]
+theme = "dark"
+
+[markdown]
+math = true
+mermaid = true
+
+[daily_notes]
+folder = "/journal"
+date_format = "%Y/%m-%d"
+
+[scripts]
+memory_limit = 16777216
+instruction_limit = 100000
+
+[plugins.calendar]
+week_start = "monday"
+"#;
+
+fn memory_jewel() -> Emerald {
+    Emerald::load(MemoryStorage::from_iter([
+        ("/.emerald/config.toml", CONFIG),
+        ("/index.md", ""),
+        ("/cache.tmp", ""),
+        ("/archive/old.md", ""),
+    ]))
+    .unwrap()
+}
+
+#[test]
+fn test_config() {
+    let jewel = memory_jewel();
+    let config = jewel.config();
+
+    assert!(config.markdown.gfm);
+    assert!(config.markdown.math);
+    assert_eq!(
+        config
+            .daily_notes
+            .path(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()),
+        Path::new("/journal/2024/03-01.md")
+    );
+    assert_eq!(config.scripts.instruction_limit, Some(100000));
+
+    // Unknown keys are preserved.
+    assert_eq!(config.extra["theme"].as_str(), Some("dark"));
+    assert_eq!(config.extra["frontmatter"].as_str(), Some("toml"));
+    assert_eq!(
+        config.extra["plugins"]["calendar"]["week_start"].as_str(),
+        Some("monday")
+    );
+    assert_eq!(config.markdown.extra["mermaid"].as_bool(), Some(true));
+}
+
+#[test]
+fn test_default_config() {
+    let root = temp_emerald!("default_config");
+    let jewel = emerald::open(&root).unwrap();
+
+    assert_eq!(jewel.config(), &Config::default());
+    assert_eq!(jewel.config().daily_notes.folder, Path::root());
+}
+
+#[test]
+fn test_invalid_config() {
+    let root = temp_emerald!("invalid_config");
+    std::fs::create_dir_all(root.join(".emerald")).unwrap();
+    std::fs::write(root.join(".emerald/config.toml"), "ignore = \"*.tmp\"").unwrap();
+
+    assert!(matches!(
+        emerald::open(&root),
+        Err(emerald::Error::Config(_))
+    ));
+}
+
+#[test]
+fn test_ignore_patterns() {
+    let jewel = memory_jewel();
+
+    let paths = emerald::fs::walk(&jewel, &Path::root())
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(paths, vec!["/index.md"]);
+}
+
+#[test]
+fn test_script_config() -> Result<(), Box<dyn Error>> {
+    let jewel = memory_jewel();
+    let mut scripts = ScriptEngine::new();
+    let inst = scripts.new_instance(&jewel)?;
+    inst.execute(
+        r#"
+        assert(emerald.config.frontmatter == "toml")
+        assert(emerald.config.daily_notes.folder == "/journal")
+        assert(emerald.config.theme == "dark")
+        assert(emerald.config.plugins.calendar.week_start == "monday")
+    "#,
+    )?;
+
+    // The instruction limit applies to each execution.
+    assert!(inst.execute("while true do end").is_err());
+    inst.execute("local x = 1")?;
+
+    Ok(())
+}
+
+#[test]
+fn test_script_dirs() -> Result<(), Box<dyn Error>> {
+    let root = temp_emerald!("script_dirs");
+    std::fs::create_dir_all(root.join(".emerald/scripts")).unwrap();
+    std::fs::write(
+        root.join(".emerald/config.toml"),
+        "[scripts]\ndirs = [\"/.emerald/scripts\"]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join(".emerald/scripts/greet.lua"),
+        "return { hello = function() return 'hello' end }",
+    )
+    .unwrap();
+
+    let jewel = emerald::open(&root)?;
+    let mut scripts = ScriptEngine::new();
+    let inst = scripts.new_instance(&jewel)?;
+    inst.execute(r#"assert(require("greet").hello() == "hello")"#)?;
+
+    Ok(())
+}
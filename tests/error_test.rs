@@ -0,0 +1,127 @@
+use emerald::{
+    fs::{MetadataOptions, WalkBuilder},
+    path::Path,
+    shard::Shard,
+    storage::MemoryStorage,
+    Emerald, Error,
+};
+
+mod common;
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        ("/index.md", "# Index"),
+        ("/broken.md", "---\ntitle: [unclosed\n---\n# Broken"),
+        ("/projects/alpha.md", "# Alpha"),
+    ]))
+}
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+#[test]
+fn test_fs_errors() {
+    let jewel = memory_jewel();
+
+    assert!(matches!(
+        emerald::fs::read_dir(&jewel, &path("/index.md")),
+        Err(Error::NotADirectory(path)) if path.as_str() == "/index.md"
+    ));
+    assert!(matches!(
+        emerald::fs::metadata(&jewel, &path("/missing.md")),
+        Err(Error::NotFound(path)) if path.as_str() == "/missing.md"
+    ));
+    assert!(matches!(
+        emerald::fs::open(&jewel, &path("/missing/index.md")),
+        Err(Error::NotFound(path)) if path.as_str() == "/missing"
+    ));
+    assert!(matches!(
+        emerald::fs::remove_dir_all(&jewel, &Path::root()),
+        Err(Error::InvalidInput(_))
+    ));
+    assert!(matches!(
+        emerald::fs::glob(&jewel, "/[a"),
+        Err(Error::Pattern(_))
+    ));
+}
+
+#[test]
+fn test_symlink_errors() {
+    let jewel = memory_jewel();
+
+    emerald::fs::symlink(&jewel, &path("/dangling"), "/missing").unwrap();
+    emerald::fs::symlink(&jewel, &path("/a"), "/b").unwrap();
+    emerald::fs::symlink(&jewel, &path("/b"), "/a").unwrap();
+
+    assert!(matches!(
+        emerald::fs::read_dir(&jewel, &path("/dangling")),
+        Err(Error::BrokenSymlink { link, target }) if link.as_str() == "/dangling" && target == "/missing"
+    ));
+    assert!(matches!(
+        emerald::fs::canonicalize(&jewel, &path("/a")),
+        Err(Error::SymlinkLoop(_))
+    ));
+    assert!(matches!(
+        emerald::fs::read_link(&jewel, &path("/index.md")),
+        Err(Error::NotASymlink(_))
+    ));
+    assert!(matches!(
+        emerald::fs::symlink(&jewel, &path("/a"), "/index.md"),
+        Err(Error::AlreadyExists(_))
+    ));
+}
+
+#[test]
+fn test_open_not_a_directory() {
+    let root = temp_emerald!("open_not_a_directory");
+    std::fs::write(root.join("file.md"), "").unwrap();
+
+    assert!(matches!(
+        emerald::open(&root.join("file.md")),
+        Err(Error::Io(err)) if err.kind() == std::io::ErrorKind::NotADirectory
+    ));
+}
+
+#[test]
+fn test_frontmatter_errors() {
+    let err = "---\ntitle: [unclosed\n---\n# Broken"
+        .parse::<Shard>()
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err,
+        Error::FrontMatter {
+            position: Some(_),
+            ..
+        }
+    ));
+
+    let err = "---\n- a\n- b\n---\n".parse::<Shard>().err().unwrap();
+    assert!(matches!(err, Error::FrontMatter { .. }));
+
+    // Tags are dropped, and scalar keys are converted into strings.
+    let shard = "---\ntitle: !custom Tagged\n2024: year\n---\n"
+        .parse::<Shard>()
+        .unwrap();
+    assert_eq!(shard.title().as_deref(), Some("Tagged"));
+}
+
+#[test]
+fn test_walk_goes_on() {
+    let jewel = memory_jewel();
+    let options = MetadataOptions {
+        hash: false,
+        title: true,
+    };
+
+    let (entries, errors): (Vec<_>, Vec<_>) = WalkBuilder::new(&jewel, &Path::root())
+        .metadata(options)
+        .build()
+        .unwrap()
+        .partition(Result::is_ok);
+
+    assert_eq!(entries.len(), 3);
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], Err(Error::FrontMatter { .. })));
+}
@@ -1,3 +1,5 @@
+use std::io::{Read, Write};
+
 mod common;
 
 #[test]
@@ -21,4 +23,102 @@ fn test_valid_read_dir() {
     let entries = emerald::fs::read_dir(&jewel, &emerald::path::Path::default())
         .unwrap()
         .collect::<Vec<_>>();
+
+    assert!(!entries.is_empty());
+    assert!(entries.iter().all(Result::is_ok));
+}
+
+#[test]
+fn test_write_and_read() {
+    let root = temp_emerald!("write_and_read");
+    let jewel = emerald::open(&root).unwrap();
+    let path = emerald::path::Path::new("/shard.md").unwrap();
+
+    emerald::fs::write(&jewel, &path, "# Heading").unwrap();
+
+    let mut content = String::default();
+    emerald::fs::open(&jewel, &path)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+
+    assert_eq!(content, "# Heading");
+    assert!(emerald::fs::metadata(&jewel, &path).unwrap().is_shard());
+
+    let mut file = emerald::fs::create(&jewel, &path).unwrap();
+    file.write_all(b"truncated").unwrap();
+    drop(file);
+
+    assert_eq!(
+        std::fs::read_to_string(root.join("shard.md")).unwrap(),
+        "truncated"
+    );
+}
+
+#[test]
+fn test_create_dir_all() {
+    let root = temp_emerald!("create_dir_all");
+    let jewel = emerald::open(&root).unwrap();
+    let path = emerald::path::Path::new("/projects/alpha").unwrap();
+
+    emerald::fs::create_dir_all(&jewel, &path).unwrap();
+    emerald::fs::create_dir_all(&jewel, &path).unwrap();
+
+    assert!(emerald::fs::metadata(&jewel, &path).unwrap().is_dir());
+
+    emerald::fs::write(&jewel, &path.join("file.txt").unwrap(), "").unwrap();
+    assert!(emerald::fs::create_dir_all(&jewel, &path.join("file.txt").unwrap()).is_err());
+}
+
+#[test]
+fn test_rename_copy_remove() {
+    let root = temp_emerald!("rename_copy_remove");
+    let jewel = emerald::open(&root).unwrap();
+    let from = emerald::path::Path::new("/people/alice.md").unwrap();
+    let to = emerald::path::Path::new("/contacts/alice.md").unwrap();
+
+    emerald::fs::create_dir_all(&jewel, &from.parent().unwrap()).unwrap();
+    emerald::fs::create_dir_all(&jewel, &to.parent().unwrap()).unwrap();
+    emerald::fs::write(&jewel, &from, "alice").unwrap();
+
+    emerald::fs::rename(&jewel, &from, &to).unwrap();
+    assert!(emerald::fs::metadata(&jewel, &from).is_err());
+
+    assert_eq!(emerald::fs::copy(&jewel, &to, &from).unwrap(), 5);
+    assert!(emerald::fs::metadata(&jewel, &from).unwrap().is_shard());
+
+    emerald::fs::remove_file(&jewel, &from).unwrap();
+    assert!(emerald::fs::metadata(&jewel, &from).is_err());
+
+    emerald::fs::remove_dir_all(&jewel, &to.parent().unwrap()).unwrap();
+    assert!(!root.join("contacts").exists());
+    assert!(emerald::fs::remove_dir_all(&jewel, &emerald::path::Path::root()).is_err());
+}
+
+#[test]
+fn test_write_through_symlink() {
+    let root = temp_emerald!("write_through_symlink");
+    let target = temp_emerald!("write_through_symlink_target");
+    let jewel = emerald::open(&root).unwrap();
+    let link = emerald::path::Path::new("/link").unwrap();
+
+    std::fs::write(root.join("link"), format!("@/>{}", target.display())).unwrap();
+    assert!(emerald::fs::metadata(&jewel, &link).unwrap().is_symlink());
+
+    // Writing out of the jewel must be explicitly allowed.
+    let shard = link.join("shard.md").unwrap();
+    assert!(matches!(
+        emerald::fs::write(&jewel, &shard, "through"),
+        Err(emerald::Error::PermissionDenied(_))
+    ));
+    assert!(!target.join("shard.md").exists());
+
+    emerald::fs::write(&jewel.allow_storage_writes(), &shard, "through").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(target.join("shard.md")).unwrap(),
+        "through"
+    );
+
+    emerald::fs::remove_file(&jewel, &link).unwrap();
+    assert!(target.join("shard.md").exists());
 }
@@ -0,0 +1,107 @@
+use std::error::Error;
+
+use emerald::{path::Path, script::ScriptEngine, storage::MemoryStorage, Emerald};
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        ("/index.md", ""),
+        ("/.hidden.md", ""),
+        ("/projects/2023-12.md", ""),
+        ("/projects/2024-01.md", ""),
+        ("/projects/2024-02.txt", ""),
+        ("/projects/alpha/2024-03.md", ""),
+        ("/projects/alpha/deep/2024-04.md", ""),
+        ("/people/bob.md", ""),
+        ("/people/bea.md", ""),
+        ("/people/carl.md", ""),
+    ]))
+}
+
+fn paths(jewel: &Emerald, pattern: &str) -> Vec<String> {
+    emerald::fs::glob(jewel, pattern)
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string())
+        .collect()
+}
+
+#[test]
+fn test_wildcards() {
+    let jewel = memory_jewel();
+
+    assert_eq!(paths(&jewel, "/*.md"), vec!["/index.md"]);
+    assert_eq!(
+        paths(&jewel, "/projects/2024-*"),
+        vec!["/projects/2024-01.md", "/projects/2024-02.txt"]
+    );
+    assert_eq!(paths(&jewel, "/people/b?b.md"), vec!["/people/bob.md"]);
+    assert_eq!(
+        paths(&jewel, "/people/[a-b]*.md"),
+        vec!["/people/bea.md", "/people/bob.md"]
+    );
+    assert_eq!(paths(&jewel, "/people/[!b]*.md"), vec!["/people/carl.md"]);
+    assert_eq!(
+        paths(&jewel, "/{people,projects}/*.md"),
+        vec![
+            "/people/bea.md",
+            "/people/bob.md",
+            "/people/carl.md",
+            "/projects/2023-12.md",
+            "/projects/2024-01.md"
+        ]
+    );
+}
+
+#[test]
+fn test_recursive() {
+    let jewel = memory_jewel();
+
+    assert_eq!(
+        paths(&jewel, "/projects/**/2024-*.md"),
+        vec![
+            "/projects/2024-01.md",
+            "/projects/alpha/2024-03.md",
+            "/projects/alpha/deep/2024-04.md"
+        ]
+    );
+    assert_eq!(paths(&jewel, "/**/carl.md"), vec!["/people/carl.md"]);
+    assert_eq!(paths(&jewel, "/projects/*"), paths(&jewel, "projects/*"));
+}
+
+#[test]
+fn test_no_match() {
+    let jewel = memory_jewel();
+
+    assert!(paths(&jewel, "/missing/*.md").is_empty());
+    assert!(paths(&jewel, "/index.md/*").is_empty());
+    assert_eq!(paths(&jewel, "/index.md"), vec!["/index.md"]);
+}
+
+#[test]
+fn test_through_symlink() {
+    let jewel = memory_jewel();
+    emerald::fs::symlink(&jewel, &Path::new("/alpha").unwrap(), "/projects/alpha").unwrap();
+
+    assert_eq!(
+        paths(&jewel, "/alpha/**/*.md"),
+        vec!["/alpha/2024-03.md", "/alpha/deep/2024-04.md"]
+    );
+}
+
+#[test]
+fn test_script_glob() -> Result<(), Box<dyn Error>> {
+    let jewel = memory_jewel();
+    let mut scripts = ScriptEngine::new();
+    let inst = scripts.new_instance(&jewel)?;
+    inst.execute(
+        r#"
+        local paths = {}
+        for entry in emerald.fs.glob("/projects/**/2024-*.md") do
+            table.insert(paths, entry.path:tostring())
+        end
+        assert(#paths == 3)
+        assert(paths[1] == "/projects/2024-01.md")
+    "#,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use std::{io::Write, time::Duration};
+
+use emerald::{
+    config::HistoryConfig,
+    fs::{DiffLine, Retention},
+    path::Path,
+    storage::MemoryStorage,
+    Config, Emerald,
+};
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::new())
+}
+
+#[test]
+fn test_record_versions() {
+    let jewel = memory_jewel();
+    let index = path("/index.md");
+
+    emerald::fs::write(&jewel, &index, "# Index\n").unwrap();
+    emerald::fs::write(&jewel, &index, "# Index\n").unwrap();
+    emerald::fs::write(&jewel, &index, "# Index\n\nUpdated\n").unwrap();
+
+    let mut file = emerald::fs::create(&jewel, &index).unwrap();
+    file.write_all(b"# Home\n").unwrap();
+    drop(file);
+
+    emerald::fs::copy(&jewel, &index, &path("/copy.md")).unwrap();
+    emerald::fs::write(&jewel, &path("/data.json"), "{}").unwrap();
+
+    // Identical consecutive writes are recorded once.
+    let versions = emerald::fs::history(&jewel, &index).unwrap();
+    assert_eq!(versions.len(), 3);
+    assert_eq!(versions[2].len, 7);
+    assert_eq!(
+        emerald::fs::history(&jewel, &path("/copy.md"))
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(emerald::fs::history(&jewel, &path("/data.json"))
+        .unwrap()
+        .is_empty());
+
+    let shard = emerald::fs::read_version(&jewel, &index, &versions[0].hash).unwrap();
+    assert_eq!(shard.title().as_deref(), Some("Index"));
+}
+
+#[test]
+fn test_history_best_effort() {
+    // The history cannot be recorded, `/.emerald/history` being a file.
+    let jewel = Emerald::from_storage(MemoryStorage::from_iter([("/.emerald/history", "")]));
+    let index = path("/index.md");
+
+    emerald::fs::write(&jewel, &index, "# Index\n").unwrap();
+    assert_eq!(
+        emerald::fs::copy(&jewel, &index, &path("/copy.md")).unwrap(),
+        8
+    );
+    assert!(!emerald::fs::history(&jewel, &index).is_ok_and(|versions| !versions.is_empty()));
+    assert!(emerald::fs::metadata(&jewel, &path("/copy.md")).is_ok());
+}
+
+#[test]
+fn test_diff_and_restore() {
+    let jewel = memory_jewel();
+    let index = path("/index.md");
+
+    emerald::fs::write(&jewel, &index, "# Index\n\nFirst\nSecond\n").unwrap();
+    emerald::fs::write(&jewel, &index, "# Index\n\nFirst\nThird\n").unwrap();
+    let versions = emerald::fs::history(&jewel, &index).unwrap();
+
+    let diff =
+        emerald::fs::diff_versions(&jewel, &index, &versions[0].hash, &versions[1].hash).unwrap();
+    assert_eq!(
+        diff,
+        vec![
+            DiffLine::Equal("# Index".to_string()),
+            DiffLine::Equal("".to_string()),
+            DiffLine::Equal("First".to_string()),
+            DiffLine::Delete("Second".to_string()),
+            DiffLine::Insert("Third".to_string()),
+        ]
+    );
+    assert_eq!(diff[3].to_string(), "-Second");
+
+    emerald::fs::restore_version(&jewel, &index, &versions[0].hash).unwrap();
+    assert_eq!(
+        emerald::fs::read_version_bytes(&jewel, &index, &versions[0].hash).unwrap(),
+        b"# Index\n\nFirst\nSecond\n"
+    );
+
+    let versions = emerald::fs::history(&jewel, &index).unwrap();
+    assert_eq!(versions.len(), 3);
+    assert_eq!(versions[0].hash, versions[2].hash);
+
+    assert!(emerald::fs::read_version(&jewel, &path("/other.md"), &versions[0].hash).is_err());
+    assert!(emerald::fs::read_version(&jewel, &index, "../../index.md").is_err());
+}
+
+#[test]
+fn test_diff_large_versions() {
+    let jewel = memory_jewel();
+    let index = path("/index.md");
+
+    let version = |name: &str| {
+        let lines = (0..3000).map(|line| format!("{} {}", name, line));
+        std::iter::once("# Index".to_string())
+            .chain(lines)
+            .chain(std::iter::once("End".to_string()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    emerald::fs::write(&jewel, &index, version("Old")).unwrap();
+    emerald::fs::write(&jewel, &index, version("New")).unwrap();
+    let versions = emerald::fs::history(&jewel, &index).unwrap();
+
+    // The changed lines are too many to be compared, they are deleted then inserted.
+    let diff =
+        emerald::fs::diff_versions(&jewel, &index, &versions[0].hash, &versions[1].hash).unwrap();
+    assert_eq!(diff.len(), 6002);
+    assert_eq!(diff[0], DiffLine::Equal("# Index".to_string()));
+    assert_eq!(diff[1], DiffLine::Delete("Old 0".to_string()));
+    assert_eq!(diff[3001], DiffLine::Insert("New 0".to_string()));
+    assert_eq!(diff[6001], DiffLine::Equal("End".to_string()));
+}
+
+#[test]
+fn test_history_follows_renames() {
+    let jewel = memory_jewel();
+    let index = path("/index.md");
+    let home = path("/home.md");
+
+    emerald::fs::write(&jewel, &index, "# Index\n").unwrap();
+    emerald::fs::write(&jewel, &index, "# Home\n").unwrap();
+    emerald::fs::rename(&jewel, &index, &home).unwrap();
+    assert_eq!(emerald::fs::history(&jewel, &home).unwrap().len(), 2);
+    assert!(emerald::fs::history(&jewel, &index).unwrap().is_empty());
+
+    // The shards of a renamed directory keep their history.
+    emerald::fs::create_dir_all(&jewel, &path("/notes/daily")).unwrap();
+    emerald::fs::write(&jewel, &path("/notes/daily/monday.md"), "# Monday\n").unwrap();
+    emerald::fs::rename(&jewel, &path("/notes"), &path("/archive")).unwrap();
+    let versions = emerald::fs::history(&jewel, &path("/archive/daily/monday.md")).unwrap();
+    assert_eq!(versions.len(), 1);
+    let shard =
+        emerald::fs::read_version(&jewel, &path("/archive/daily/monday.md"), &versions[0].hash)
+            .unwrap();
+    assert_eq!(shard.title().as_deref(), Some("Monday"));
+
+    // A moved shard keeps its history, followed by its rewritten version.
+    let alice = path("/people/alice.md");
+    let contacts = path("/contacts/people/alice.md");
+    emerald::fs::create_dir_all(&jewel, &path("/people")).unwrap();
+    emerald::fs::write(&jewel, &alice, "# Alice\n\n[Home](../home.md)\n").unwrap();
+    emerald::fs::move_shard(&jewel, &alice, &contacts).unwrap();
+    let versions = emerald::fs::history(&jewel, &contacts).unwrap();
+    assert_eq!(versions.len(), 2);
+    assert_eq!(
+        emerald::fs::read_version_bytes(&jewel, &contacts, &versions[0].hash).unwrap(),
+        b"# Alice\n\n[Home](../home.md)\n"
+    );
+}
+
+#[test]
+fn test_retention() {
+    let jewel = memory_jewel();
+    let index = path("/index.md");
+
+    for count in 0..5 {
+        emerald::fs::write(&jewel, &index, format!("# Version {}", count)).unwrap();
+    }
+
+    let retention = Retention {
+        keep: Some(2),
+        older_than: None,
+    };
+    assert_eq!(
+        emerald::fs::prune_history_with(&jewel, retention).unwrap(),
+        3
+    );
+
+    let versions = emerald::fs::history(&jewel, &index).unwrap();
+    assert_eq!(versions.len(), 2);
+    let shard = emerald::fs::read_version(&jewel, &index, &versions[0].hash).unwrap();
+    assert_eq!(shard.title().as_deref(), Some("Version 3"));
+
+    // The most recent version is always kept.
+    let retention = Retention {
+        keep: None,
+        older_than: Some(Duration::ZERO),
+    };
+    assert_eq!(
+        emerald::fs::prune_history_with(&jewel, retention).unwrap(),
+        1
+    );
+    assert_eq!(emerald::fs::history(&jewel, &index).unwrap().len(), 1);
+
+    // The unreferenced contents are removed.
+    let objects = emerald::fs::read_dir(&jewel, &path("/.emerald/history/objects"))
+        .unwrap()
+        .count();
+    assert_eq!(objects, 1);
+}
+
+#[test]
+fn test_concurrent_record_and_prune() {
+    let jewel = memory_jewel();
+    let index = path("/index.md");
+
+    // The writers share the contents of their versions, while the history is pruned.
+    let writers = (0..4)
+        .map(|writer| {
+            let jewel = jewel.clone();
+            let index = index.clone();
+            std::thread::spawn(move || {
+                for count in 0..20 {
+                    let content = format!("# Version {}", (writer + count) % 5);
+                    emerald::fs::write(&jewel, &index, content).unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let retention = Retention {
+        keep: Some(1),
+        older_than: None,
+    };
+    for _ in 0..10 {
+        emerald::fs::prune_history_with(&jewel, retention).unwrap();
+    }
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    // Every recorded version can be read.
+    let versions = emerald::fs::history(&jewel, &index).unwrap();
+    assert!(!versions.is_empty());
+    for version in versions {
+        assert!(emerald::fs::read_version(&jewel, &index, &version.hash).is_ok());
+    }
+}
+
+#[test]
+fn test_config() {
+    let config = Config {
+        history: HistoryConfig {
+            keep: Some(1),
+            ..HistoryConfig::default()
+        },
+        ..Config::default()
+    };
+    let jewel = Emerald::with_config(MemoryStorage::new(), config);
+    let index = path("/index.md");
+
+    emerald::fs::write(&jewel, &index, "# A").unwrap();
+    emerald::fs::write(&jewel, &index, "# B").unwrap();
+    assert_eq!(emerald::fs::prune_history(&jewel).unwrap(), 1);
+
+    let config = Config {
+        history: HistoryConfig {
+            enabled: false,
+            ..HistoryConfig::default()
+        },
+        ..Config::default()
+    };
+    let jewel = Emerald::with_config(MemoryStorage::new(), config);
+    emerald::fs::write(&jewel, &index, "# A").unwrap();
+    assert!(emerald::fs::history(&jewel, &index).unwrap().is_empty());
+}
@@ -0,0 +1,52 @@
+use std::io::Read;
+
+use emerald::{path::Path, Config, Emerald, Error};
+
+mod common;
+
+#[test]
+fn test_init() {
+    let root = temp_emerald!("init").join("notes");
+    let jewel = Emerald::init(&root).unwrap();
+
+    assert!(root.join(".emerald").is_dir());
+    assert_eq!(jewel.config(), &Config::default());
+
+    let mut index = String::default();
+    emerald::fs::open(&jewel, &Path::new("/index.md").unwrap())
+        .unwrap()
+        .read_to_string(&mut index)
+        .unwrap();
+    assert_eq!(index, "# notes\n");
+
+    assert!(matches!(
+        Emerald::init(&root),
+        Err(Error::AlreadyExists(path)) if path.as_str() == "/.emerald"
+    ));
+}
+
+#[test]
+fn test_init_keeps_index() {
+    let root = temp_emerald!("init_keeps_index");
+    std::fs::write(root.join("index.md"), "# My index").unwrap();
+
+    emerald::init(&root).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(root.join("index.md")).unwrap(),
+        "# My index"
+    );
+}
+
+#[test]
+fn test_discover() {
+    let root = temp_emerald!("discover");
+    let jewel = emerald::init(&root.join("jewel")).unwrap();
+    let deep = root.join("jewel/projects/alpha");
+    std::fs::create_dir_all(&deep).unwrap();
+
+    let found = emerald::discover(&deep).unwrap();
+    assert_eq!(found.get_root(), jewel.get_root().canonicalize().unwrap());
+
+    assert!(emerald::discover(&root.join("jewel")).is_ok());
+    assert!(matches!(emerald::discover(&root), Err(Error::NotAJewel(_))));
+}
@@ -0,0 +1,220 @@
+use std::{io::Write, time::Duration};
+
+use emerald::{
+    fs::{MetadataOptions, Revision},
+    path::Path,
+    storage::MemoryStorage,
+    Emerald, Error,
+};
+
+mod common;
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([("/index.md", "# Index")]))
+}
+
+fn read(jewel: &Emerald, path: &Path) -> String {
+    String::from_utf8(
+        jewel
+            .storage()
+            .read(&emerald::fs::canonicalize(jewel, path).unwrap())
+            .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_atomic_write() {
+    let root = temp_emerald!("atomic_write");
+    let jewel = emerald::open(&root).unwrap();
+    let index = path("/index.md");
+
+    emerald::fs::write(&jewel, &index, "# Index").unwrap();
+
+    // The content replaces the file once it is closed.
+    let mut file = emerald::fs::create(&jewel, &index).unwrap();
+    file.write_all(b"# Home").unwrap();
+    assert_eq!(read(&jewel, &index), "# Index");
+    file.close().unwrap();
+    assert_eq!(read(&jewel, &index), "# Home");
+
+    // No temporary file is left behind.
+    let names = std::fs::read_dir(&root)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".tmp"))
+        .collect::<Vec<_>>();
+    assert!(names.is_empty());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_atomic_write_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = temp_emerald!("atomic_write_permissions");
+    let jewel = emerald::open(&root).unwrap();
+    let index = path("/index.md");
+
+    emerald::fs::write(&jewel, &index, "# Index").unwrap();
+    let file = root.join("index.md");
+    std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+    emerald::fs::write(&jewel, &index, "# Home").unwrap();
+    let mode = std::fs::metadata(&file).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(read(&jewel, &index), "# Home");
+}
+
+#[test]
+fn test_lock() {
+    let root = temp_emerald!("lock");
+    let jewel = emerald::open(&root).unwrap();
+    let index = path("/index.md");
+
+    let lock = emerald::fs::try_lock(&jewel, &index).unwrap();
+    assert_eq!(lock.path(), &index);
+
+    // The lock is shared by the handles, and by the jewels opened on the same directory.
+    let other = emerald::open(&root).unwrap();
+    assert!(matches!(
+        emerald::fs::try_lock(&other, &index),
+        Err(Error::Locked(_))
+    ));
+    assert!(matches!(
+        emerald::fs::try_lock(&jewel.read_only(), &index),
+        Err(Error::Locked(_))
+    ));
+    assert!(matches!(
+        emerald::fs::lock(&jewel, &index, Duration::from_millis(30)),
+        Err(Error::Locked(_))
+    ));
+    assert!(emerald::fs::try_lock(&jewel, &path("/other.md")).is_ok());
+
+    drop(lock);
+    assert!(emerald::fs::try_lock(&jewel, &index).is_ok());
+}
+
+#[test]
+fn test_lock_wait() {
+    let jewel = memory_jewel();
+    let index = path("/index.md");
+    let lock = emerald::fs::try_lock(&jewel, &index).unwrap();
+
+    let waiter = {
+        let jewel = jewel.clone();
+        let index = index.clone();
+        std::thread::spawn(move || {
+            emerald::fs::lock(&jewel, &index, Duration::from_secs(5)).map(|_| ())
+        })
+    };
+
+    std::thread::sleep(Duration::from_millis(50));
+    drop(lock);
+    assert!(waiter.join().unwrap().is_ok());
+}
+
+#[test]
+fn test_write_if_unchanged() {
+    let jewel = memory_jewel();
+    let index = path("/index.md");
+
+    let hashed = MetadataOptions {
+        hash: true,
+        title: false,
+    };
+    let by_hash =
+        Revision::of(&emerald::fs::metadata_with(&jewel, &index, hashed).unwrap()).unwrap();
+    let by_time = Revision::of(&emerald::fs::metadata(&jewel, &index).unwrap()).unwrap();
+    assert!(matches!(by_hash, Revision::Hash(_)));
+    assert!(matches!(by_time, Revision::Modified(_)));
+
+    emerald::fs::write_if_unchanged(&jewel, &index, &by_hash, "# Editor").unwrap();
+    assert_eq!(read(&jewel, &index), "# Editor");
+
+    // The cron job read the shard before the editor wrote it.
+    assert!(matches!(
+        emerald::fs::write_if_unchanged(&jewel, &index, &by_time, "# Cron"),
+        Err(Error::Conflict(_))
+    ));
+    assert_eq!(read(&jewel, &index), "# Editor");
+
+    let new = path("/new.md");
+    emerald::fs::write_if_unchanged(&jewel, &new, &Revision::Absent, "# New").unwrap();
+    assert!(matches!(
+        emerald::fs::write_if_unchanged(&jewel, &new, &Revision::Absent, "# New"),
+        Err(Error::Conflict(_))
+    ));
+
+    // The lock is released.
+    assert!(emerald::fs::try_lock(&jewel, &index).is_ok());
+}
+
+/// The lock files of the jewel, on the disk.
+fn lock_files(root: &std::path::Path) -> Vec<std::path::PathBuf> {
+    std::fs::read_dir(root.join(".emerald/locks"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect()
+}
+
+#[test]
+fn test_lock_owner() {
+    let root = temp_emerald!("lock_owner");
+    let jewel = emerald::open(&root).unwrap();
+    let index = path("/index.md");
+
+    let lock = emerald::fs::try_lock(&jewel, &index).unwrap();
+    let [file] = lock_files(&root).try_into().unwrap();
+    let content = std::fs::read_to_string(&file).unwrap();
+    assert!(content.starts_with("/index.md\n"));
+    drop(lock);
+
+    // Each owner writes its own token.
+    let lock = emerald::fs::try_lock(&jewel, &index).unwrap();
+    assert_ne!(std::fs::read_to_string(&file).unwrap(), content);
+
+    // A lock file replaced by another owner is not removed.
+    std::fs::write(&file, "/index.md\nother\n").unwrap();
+    drop(lock);
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "/index.md\nother\n"
+    );
+}
+
+#[test]
+fn test_lock_stale() {
+    let root = temp_emerald!("lock_stale");
+    let jewel = emerald::open(&root).unwrap();
+    let index = path("/index.md");
+
+    let crashed = emerald::fs::try_lock(&jewel, &index).unwrap();
+    let [file] = lock_files(&root).try_into().unwrap();
+    let old = std::time::SystemTime::now() - Duration::from_secs(3600);
+    std::fs::File::options()
+        .write(true)
+        .open(&file)
+        .unwrap()
+        .set_modified(old)
+        .unwrap();
+
+    // The stale lock is broken, and the tombstone is removed.
+    let lock = emerald::fs::try_lock(&jewel, &index).unwrap();
+    assert_eq!(lock_files(&root), vec![file.clone()]);
+
+    // The former owner does not release the new lock.
+    drop(crashed);
+    assert!(file.exists());
+    assert!(matches!(
+        emerald::fs::try_lock(&jewel, &index),
+        Err(Error::Locked(_))
+    ));
+
+    drop(lock);
+    assert!(lock_files(&root).is_empty());
+}
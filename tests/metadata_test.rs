@@ -0,0 +1,147 @@
+use std::error::Error;
+
+use emerald::{
+    fs::{MetadataOptions, SymlinkTarget, WalkBuilder},
+    path::Path,
+    script::ScriptEngine,
+    storage::MemoryStorage,
+    Emerald,
+};
+
+mod common;
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        (
+            "/projects/alpha.md",
+            "---\ntitle: Project Alpha\n---\n# Alpha",
+        ),
+        (
+            "/people/bob.md",
+            "Intro\n\n# Bob *the* `builder`\n\n# Other",
+        ),
+        ("/people/notes.txt", "hello"),
+    ]))
+}
+
+#[test]
+fn test_size_and_timestamps() -> Result<(), Box<dyn Error>> {
+    let jewel = memory_jewel();
+    let path = Path::new("/people/notes.txt").unwrap();
+
+    let meta = emerald::fs::metadata(&jewel, &path)?;
+    assert_eq!(meta.len(), 5);
+    assert!(meta.created().is_some());
+    assert!(meta.hash().is_none());
+    assert!(meta.title().is_none());
+
+    let before = meta.modified().unwrap();
+    emerald::fs::write(&jewel, &path, "hello world")?;
+
+    let meta = emerald::fs::metadata(&jewel, &path)?;
+    assert_eq!(meta.len(), 11);
+    assert!(meta.modified().unwrap() >= before);
+    assert!(meta.created().unwrap() <= before);
+
+    Ok(())
+}
+
+#[test]
+fn test_disk_timestamps() -> Result<(), Box<dyn Error>> {
+    let root = temp_emerald!("metadata");
+    let jewel = emerald::open(&root)?;
+    let path = Path::new("/index.md").unwrap();
+    emerald::fs::write(&jewel, &path, "# Index")?;
+
+    let meta = emerald::fs::metadata(&jewel, &path)?;
+    assert_eq!(meta.len(), 7);
+    assert!(meta.modified().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_hash_and_title() -> Result<(), Box<dyn Error>> {
+    let jewel = memory_jewel();
+    let options = MetadataOptions {
+        hash: true,
+        title: true,
+    };
+
+    let notes =
+        emerald::fs::metadata_with(&jewel, &Path::new("/people/notes.txt").unwrap(), options)?;
+    assert_eq!(
+        notes.hash(),
+        Some("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+    );
+    assert!(notes.title().is_none());
+
+    let alpha =
+        emerald::fs::metadata_with(&jewel, &Path::new("/projects/alpha.md").unwrap(), options)?;
+    assert_eq!(alpha.title(), Some("Project Alpha"));
+
+    let titles = WalkBuilder::new(&jewel, &Path::new("/people").unwrap())
+        .include("*.md")
+        .metadata(options)
+        .build()?
+        .map(|entry| entry.unwrap().metadata().title().map(str::to_string))
+        .collect::<Vec<_>>();
+    assert_eq!(titles, vec![Some("Bob the builder".to_string())]);
+
+    Ok(())
+}
+
+#[test]
+fn test_symlink_target() -> Result<(), Box<dyn Error>> {
+    let jewel = memory_jewel();
+    let link = Path::new("/people/alpha").unwrap();
+    emerald::fs::symlink(&jewel, &link, "../projects/alpha.md")?;
+
+    let meta = emerald::fs::metadata(&jewel, &link)?;
+    assert!(meta.is_symlink());
+    assert_eq!(
+        meta.symlink_target(),
+        Some(&SymlinkTarget::Jewel(
+            Path::new("/projects/alpha.md").unwrap()
+        ))
+    );
+
+    let shard = emerald::fs::metadata(&jewel, &Path::new("/projects/alpha.md").unwrap())?;
+    assert!(shard.symlink_target().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_serde() -> Result<(), Box<dyn Error>> {
+    let jewel = memory_jewel();
+    let entry = emerald::fs::read_dir(&jewel, &Path::new("/projects").unwrap())?
+        .next()
+        .unwrap()?;
+
+    let json = serde_json::to_value(&entry)?;
+    assert_eq!(json["metadata"]["len"], 36);
+    assert!(json["metadata"]["modified"].is_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_script_metadata() -> Result<(), Box<dyn Error>> {
+    let jewel = memory_jewel();
+    let mut scripts = ScriptEngine::new();
+    let inst = scripts.new_instance(&jewel)?;
+    inst.execute(
+        r#"
+        for entry in emerald.fs.walk("/projects", { hash = true, title = true }) do
+            assert(entry.metadata.len == 36)
+            assert(entry.metadata.modified >= entry.metadata.created)
+            assert(entry.metadata.title == "Project Alpha")
+            assert(#entry.metadata.hash == 64)
+            assert(entry.metadata.symlink_target == nil)
+        end
+    "#,
+    )?;
+
+    Ok(())
+}
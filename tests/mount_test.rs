@@ -0,0 +1,134 @@
+use std::io::Read;
+
+use emerald::{path::Path, Error};
+
+mod common;
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+const CONFIG: &str = r#"
+[[mounts]]
+path = "/shared"
+target = "../shared"
+
+[[mounts]]
+path = "/notes/archive"
+target = "../archive"
+read_only = true
+"#;
+
+fn mounted_jewel(name: &str) -> emerald::Emerald {
+    let root = temp_emerald!(name);
+    let files = [
+        ("jewel/.emerald/config.toml", CONFIG),
+        ("jewel/index.md", "# Index"),
+        ("jewel/notes/today.md", "# Today"),
+        ("shared/.emerald/config.toml", ""),
+        ("shared/people/alice.md", "# Alice"),
+        ("archive/2020.md", "# 2020"),
+    ];
+
+    for (file, content) in files {
+        let file = root.join(file);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, content).unwrap();
+    }
+
+    emerald::open(&root.join("jewel")).unwrap()
+}
+
+#[test]
+fn test_mount_resolution() {
+    let jewel = mounted_jewel("mount_resolution");
+
+    let mut content = String::default();
+    emerald::fs::open(&jewel, &path("/shared/people/alice.md"))
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "# Alice");
+
+    assert!(emerald::fs::metadata(&jewel, &path("/notes/archive"))
+        .unwrap()
+        .is_dir());
+
+    let names = emerald::fs::read_dir(&jewel, &path("/notes"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string())
+        .collect::<std::collections::BTreeSet<_>>();
+    assert_eq!(
+        names,
+        ["/notes/archive", "/notes/today.md"]
+            .map(str::to_string)
+            .into()
+    );
+}
+
+#[test]
+fn test_mount_walk() {
+    let jewel = mounted_jewel("mount_walk");
+
+    let entries = emerald::fs::walk(&jewel, &Path::root())
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (entry.path().to_string(), entry.mount().map(Path::to_string))
+        })
+        .filter(|(path, _)| path.ends_with(".md"))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        entries,
+        vec![
+            ("/index.md".to_string(), None),
+            (
+                "/notes/archive/2020.md".to_string(),
+                Some("/notes/archive".to_string())
+            ),
+            ("/notes/today.md".to_string(), None),
+            (
+                "/shared/people/alice.md".to_string(),
+                Some("/shared".to_string())
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_read_only_mount() {
+    let jewel = mounted_jewel("read_only_mount");
+    let archived = path("/notes/archive/2020.md");
+
+    assert!(matches!(
+        emerald::fs::write(&jewel, &archived, "# Changed"),
+        Err(Error::PermissionDenied(_))
+    ));
+    assert!(matches!(
+        emerald::fs::remove_file(&jewel, &archived),
+        Err(Error::PermissionDenied(_))
+    ));
+    assert!(matches!(
+        emerald::fs::create_dir_all(&jewel, &path("/notes/archive/2021")),
+        Err(Error::PermissionDenied(_))
+    ));
+    assert!(matches!(
+        emerald::fs::rename(&jewel, &archived, &path("/2020.md")),
+        Err(Error::PermissionDenied(_))
+    ));
+
+    // A symbolic link does not bypass the mount.
+    emerald::fs::symlink(&jewel, &path("/old"), "/notes/archive/2020.md").unwrap();
+    assert!(matches!(
+        emerald::fs::write(&jewel, &path("/old"), "# Changed"),
+        Err(Error::PermissionDenied(_))
+    ));
+
+    // The mount points themselves cannot be removed.
+    assert!(emerald::fs::remove_dir_all(&jewel, &path("/shared")).is_err());
+
+    // The writable mounts are written through.
+    emerald::fs::write(&jewel, &path("/shared/people/bob.md"), "# Bob").unwrap();
+    assert!(jewel.get_root().join("../shared/people/bob.md").is_file());
+}
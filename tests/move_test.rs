@@ -0,0 +1,228 @@
+use std::io::{Read, Write};
+
+use emerald::{
+    fs::MoveOptions,
+    path::Path,
+    storage::{MemoryStorage, Metadata, Storage},
+    Emerald, Error,
+};
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(memory_storage())
+}
+
+fn memory_storage() -> MemoryStorage {
+    MemoryStorage::from_iter([
+        (
+            "/people/alice.md",
+            "# Alice\n\nWorks with [Bob](bob.md) on [alpha](/projects/alpha.md).\n",
+        ),
+        ("/people/bob.md", "# Bob\n\nKnows [Alice](./alice.md \"Alice\").\n"),
+        (
+            "/index.md",
+            "# Index\n\n- [Alice](people/alice.md#bio)\n- [Absolute](/people/alice.md)\n- [Site](https://example.com/people/alice.md)\n- [Ref][alice]\n\n![Avatar](people/alice.md)\n\n[alice]: <people/alice.md> \"Alice\"\n",
+        ),
+        ("/projects/alpha.md", "# Alpha\n\nLed by [Alice](../people/alice.md?v=1).\n"),
+    ])
+}
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+fn read(jewel: &Emerald, value: &str) -> String {
+    let mut content = String::default();
+    emerald::fs::open(jewel, &path(value))
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    content
+}
+
+#[test]
+fn test_move_shard() {
+    let jewel = memory_jewel();
+
+    let edits = emerald::fs::move_shard(
+        &jewel,
+        &path("/people/alice.md"),
+        &path("/contacts/alice.md"),
+    )
+    .unwrap();
+    assert_eq!(edits.len(), 7);
+
+    assert_eq!(
+        read(&jewel, "/index.md"),
+        "# Index\n\n- [Alice](contacts/alice.md#bio)\n- [Absolute](/contacts/alice.md)\n- [Site](https://example.com/people/alice.md)\n- [Ref][alice]\n\n![Avatar](contacts/alice.md)\n\n[alice]: <contacts/alice.md> \"Alice\"\n"
+    );
+    assert_eq!(
+        read(&jewel, "/people/bob.md"),
+        "# Bob\n\nKnows [Alice](../contacts/alice.md \"Alice\").\n"
+    );
+    assert_eq!(
+        read(&jewel, "/projects/alpha.md"),
+        "# Alpha\n\nLed by [Alice](../contacts/alice.md?v=1).\n"
+    );
+
+    // The relative links of the moved shard are rebased, the absolute ones are kept.
+    assert_eq!(
+        read(&jewel, "/contacts/alice.md"),
+        "# Alice\n\nWorks with [Bob](../people/bob.md) on [alpha](/projects/alpha.md).\n"
+    );
+    assert!(emerald::fs::open(&jewel, &path("/people/alice.md")).is_err());
+}
+
+#[test]
+fn test_dry_run() {
+    let jewel = memory_jewel();
+    let options = MoveOptions { dry_run: true };
+
+    let edits = emerald::fs::move_shard_with(
+        &jewel,
+        &path("/people/alice.md"),
+        &path("/contacts/alice.md"),
+        options,
+    )
+    .unwrap();
+
+    let bob = edits
+        .iter()
+        .find(|edit| edit.shard.as_str() == "/people/bob.md")
+        .unwrap();
+    assert_eq!(bob.old, "./alice.md");
+    assert_eq!(bob.new, "../contacts/alice.md");
+    assert_eq!(
+        bob.to_string(),
+        "/people/bob.md:3:7: ./alice.md -> ../contacts/alice.md"
+    );
+
+    // Nothing moved.
+    assert!(read(&jewel, "/index.md").contains("(people/alice.md#bio)"));
+    assert!(emerald::fs::open(&jewel, &path("/people/alice.md")).is_ok());
+    assert!(emerald::fs::open(&jewel, &path("/contacts/alice.md")).is_err());
+}
+
+#[test]
+fn test_rename_in_place() {
+    let jewel = Emerald::from_storage(MemoryStorage::from_iter([
+        (
+            "/notes/draft.md",
+            "See [next](./next.md) and [me](draft.md).",
+        ),
+        (
+            "/notes/next.md",
+            "Back to [draft](draft%20.md), [draft](draft.md).",
+        ),
+    ]));
+
+    let edits = emerald::fs::move_shard(
+        &jewel,
+        &path("/notes/draft.md"),
+        &path("/notes/my draft.md"),
+    )
+    .unwrap();
+    assert_eq!(edits.len(), 2);
+
+    assert_eq!(
+        read(&jewel, "/notes/my draft.md"),
+        "See [next](./next.md) and [me](my%20draft.md)."
+    );
+    assert_eq!(
+        read(&jewel, "/notes/next.md"),
+        "Back to [draft](draft%20.md), [draft](my%20draft.md)."
+    );
+}
+
+#[test]
+fn test_move_errors() {
+    let jewel = memory_jewel();
+
+    assert!(matches!(
+        emerald::fs::move_shard(&jewel, &path("/people/carl.md"), &path("/carl.md")),
+        Err(Error::NotFound(_))
+    ));
+    assert!(matches!(
+        emerald::fs::move_shard(&jewel, &path("/people/alice.md"), &path("/people/bob.md")),
+        Err(Error::AlreadyExists(_))
+    ));
+    assert!(matches!(
+        emerald::fs::move_shard(&jewel, &path("/people"), &path("/contacts")),
+        Err(Error::IsADirectory(_))
+    ));
+}
+
+/// A memory storage which cannot write `/projects/alpha.md`.
+struct ReadOnlyAlpha(MemoryStorage);
+
+impl Storage for ReadOnlyAlpha {
+    fn root(&self) -> &std::path::Path {
+        self.0.root()
+    }
+
+    fn metadata(&self, path: &std::path::Path) -> std::io::Result<Metadata> {
+        self.0.metadata(path)
+    }
+
+    fn read_dir(&self, path: &std::path::Path) -> std::io::Result<Vec<String>> {
+        self.0.read_dir(path)
+    }
+
+    fn open(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Read + Send>> {
+        self.0.open(path)
+    }
+
+    fn create(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Write + Send>> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        match name.starts_with(".alpha.md.") {
+            true => Err(std::io::Error::other("read-only")),
+            false => self.0.create(path),
+        }
+    }
+
+    fn create_dir(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.0.create_dir(path)
+    }
+
+    fn rename(&self, from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+        self.0.rename(from, to)
+    }
+
+    fn remove_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.0.remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.0.remove_dir_all(path)
+    }
+}
+
+#[test]
+fn test_partial_move() {
+    let jewel = Emerald::from_storage(ReadOnlyAlpha(memory_storage()));
+    let result = emerald::fs::move_shard(
+        &jewel,
+        &path("/people/alice.md"),
+        &path("/contacts/alice.md"),
+    );
+
+    let Err(Error::PartialMove {
+        moved, rewritten, ..
+    }) = result
+    else {
+        panic!("the move did not partially fail");
+    };
+
+    // The documents are rewritten in order, up to the failing one.
+    assert_eq!(moved, path("/contacts/alice.md"));
+    assert_eq!(
+        rewritten,
+        vec![
+            path("/contacts/alice.md"),
+            path("/index.md"),
+            path("/people/bob.md")
+        ]
+    );
+    assert!(read(&jewel, "/index.md").contains("(contacts/alice.md#bio)"));
+    assert!(read(&jewel, "/projects/alpha.md").contains("(../people/alice.md?v=1)"));
+}
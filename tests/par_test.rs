@@ -0,0 +1,183 @@
+#![cfg(feature = "parallel")]
+
+use std::io::{Read, Write};
+
+use emerald::{
+    fs::{SortOrder, WalkBuilder},
+    path::Path,
+    shard::Shard,
+    storage::{MemoryStorage, Metadata, Storage},
+    Emerald,
+};
+
+fn memory_jewel() -> Emerald {
+    let mut files = vec![
+        ("/index.md".to_string(), "# Index".to_string()),
+        ("/broken.md".to_string(), "---\ntitle: [\n---\n".to_string()),
+        ("/notes.txt".to_string(), "notes".to_string()),
+        ("/latest".to_string(), "@/>projects".to_string()),
+    ];
+
+    for project in 0..8 {
+        for shard in 0..4 {
+            files.push((
+                format!("/projects/{project}/{shard}.md"),
+                format!("# Shard {project}.{shard}"),
+            ));
+        }
+    }
+
+    Emerald::from_storage(MemoryStorage::from_iter(files))
+}
+
+fn paths(entries: Vec<emerald::Result<emerald::fs::DirEntry>>) -> Vec<String> {
+    entries
+        .into_iter()
+        .map(|entry| entry.unwrap().path().to_string())
+        .collect()
+}
+
+#[test]
+fn test_par_walk() {
+    let jewel = memory_jewel();
+
+    for order in [Some(SortOrder::Name), Some(SortOrder::Natural)] {
+        let builder = || {
+            WalkBuilder::new(&jewel, &Path::root())
+                .follow_symlinks(false)
+                .sort(order)
+        };
+        let sequential = builder()
+            .build()
+            .unwrap()
+            .map(|entry| entry.map(|entry| entry.path().to_string()).unwrap())
+            .collect::<Vec<_>>();
+
+        for threads in [1, 4] {
+            let parallel = emerald::fs::par_walk(builder(), threads).unwrap();
+            assert_eq!(paths(parallel), sequential);
+        }
+    }
+
+    // The linked directory is walked through the link, and through its own path,
+    // as the sequential walk does.
+    let linked = paths(emerald::fs::par_walk(WalkBuilder::new(&jewel, &Path::root()), 4).unwrap());
+    assert!(linked.contains(&"/latest/7/3.md".to_string()));
+    assert!(linked.contains(&"/projects/7/3.md".to_string()));
+    assert_eq!(
+        linked,
+        paths(
+            WalkBuilder::new(&jewel, &Path::root())
+                .build()
+                .unwrap()
+                .collect()
+        )
+    );
+
+    let filtered = WalkBuilder::new(&jewel, &Path::root())
+        .follow_symlinks(false)
+        .max_depth(2)
+        .include("*.txt");
+    assert_eq!(
+        paths(emerald::fs::par_walk(filtered, 2).unwrap()),
+        vec!["/notes.txt"]
+    );
+}
+
+#[test]
+fn test_load_all() {
+    let jewel = memory_jewel();
+    let builder = WalkBuilder::new(&jewel, &Path::root()).follow_symlinks(false);
+    let shards = emerald::fs::load_all(builder, 3)
+        .unwrap()
+        .into_iter()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+
+    // The shards only, in the walk order, with the parse errors next to their path.
+    assert_eq!(shards.len(), 34);
+    assert_eq!(shards[0].0.to_string(), "/broken.md");
+    assert!(shards[0].1.is_err());
+    assert_eq!(shards[1].0.to_string(), "/index.md");
+    assert_eq!(shards[2].0.to_string(), "/projects/0/0.md");
+    assert_eq!(
+        shards[2].1.as_ref().unwrap().title().as_deref(),
+        Some("Shard 0.0")
+    );
+
+    let projects = jewel.load_all("/projects/3/**").unwrap();
+    let titles = projects
+        .into_iter()
+        .map(|entry| entry.unwrap().1.unwrap().title().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        titles,
+        vec!["Shard 3.0", "Shard 3.1", "Shard 3.2", "Shard 3.3"]
+    );
+}
+
+/// A memory storage which cannot list the `locked` directories.
+struct Unreadable(MemoryStorage);
+
+impl Storage for Unreadable {
+    fn root(&self) -> &std::path::Path {
+        self.0.root()
+    }
+
+    fn metadata(&self, path: &std::path::Path) -> std::io::Result<Metadata> {
+        self.0.metadata(path)
+    }
+
+    fn read_dir(&self, path: &std::path::Path) -> std::io::Result<Vec<String>> {
+        match path.ends_with("locked") {
+            true => Err(std::io::Error::other("unreadable")),
+            false => self.0.read_dir(path),
+        }
+    }
+
+    fn open(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Read + Send>> {
+        self.0.open(path)
+    }
+
+    fn create(&self, path: &std::path::Path) -> std::io::Result<Box<dyn Write + Send>> {
+        self.0.create(path)
+    }
+
+    fn create_dir(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.0.create_dir(path)
+    }
+
+    fn rename(&self, from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+        self.0.rename(from, to)
+    }
+
+    fn remove_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.0.remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.0.remove_dir_all(path)
+    }
+}
+
+#[test]
+fn test_load_all_skips_unreadable_directories() {
+    let jewel = Emerald::from_storage(Unreadable(MemoryStorage::from_iter([
+        ("/alpha.md", "# Alpha"),
+        ("/locked/secret.md", "# Secret"),
+        ("/projects/beta.md", "# Beta"),
+    ])));
+    let builder = WalkBuilder::new(&jewel, &Path::root()).sort(Some(SortOrder::Name));
+    let shards = emerald::fs::load_all(builder, 2).unwrap();
+
+    // The unreadable directory yields an error, the other shards are loaded.
+    assert_eq!(shards.len(), 3);
+    assert!(shards.iter().filter(|entry| entry.is_err()).count() == 1);
+
+    let titles = shards
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(|(_, shard)| shard.ok().as_ref().and_then(Shard::title))
+        .collect::<Vec<_>>();
+    assert_eq!(titles, vec![Some("Alpha".into()), Some("Beta".into())]);
+}
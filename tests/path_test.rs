@@ -0,0 +1,103 @@
+use emerald::path::Path;
+
+#[test]
+/// Test the normalization of separators and dot parts
+fn test_normalize() {
+    assert_eq!(Path::new("a//b").unwrap().to_string(), "/a/b");
+    assert_eq!(Path::new("./a").unwrap().to_string(), "/a");
+    assert_eq!(Path::new("/a/./b/../c/").unwrap().to_string(), "/a/c");
+    assert_eq!(Path::new("a\\b").unwrap().to_string(), "/a/b");
+    assert_eq!(Path::new("").unwrap(), Path::root());
+    assert_eq!(Path::default(), Path::root());
+}
+
+#[test]
+/// A path cannot escape the jewel's root
+fn test_escape_root() {
+    assert!(Path::new("../../etc").is_none());
+    assert!(Path::new("/a/../../b").is_none());
+    assert!(Path::new("a\0b").is_none());
+
+    let path = Path::new("/a").unwrap();
+    assert!(path.join("../..").is_none());
+    assert_eq!(path.join("..").unwrap(), Path::root());
+}
+
+#[test]
+fn test_join() {
+    let path = Path::new("/projects").unwrap();
+    assert_eq!(
+        path.join("alpha/index.md").unwrap().to_string(),
+        "/projects/alpha/index.md"
+    );
+    assert_eq!(path.join("/people").unwrap().to_string(), "/people");
+    assert_eq!(path.join("../people").unwrap().to_string(), "/people");
+}
+
+#[test]
+fn test_parent_and_file_name() {
+    let path = Path::new("/projects/alpha/index.md").unwrap();
+    assert_eq!(path.parent().unwrap().to_string(), "/projects/alpha");
+    assert_eq!(path.file_name(), Some("index.md"));
+    assert_eq!(Path::new("/a").unwrap().parent(), Some(Path::root()));
+    assert_eq!(Path::root().parent(), None);
+    assert_eq!(Path::root().file_name(), None);
+}
+
+#[test]
+fn test_extension() {
+    let path = Path::new("/archive/notes.tar.gz").unwrap();
+    assert_eq!(path.file_stem(), Some("notes.tar"));
+    assert_eq!(path.extension(), Some("gz"));
+
+    let hidden = Path::new("/.emeraldignore").unwrap();
+    assert_eq!(hidden.file_stem(), Some(".emeraldignore"));
+    assert_eq!(hidden.extension(), None);
+
+    let shard = Path::new("/index.txt")
+        .unwrap()
+        .with_extension("md")
+        .unwrap();
+    assert_eq!(shard.to_string(), "/index.md");
+    assert_eq!(shard.with_extension("").unwrap().to_string(), "/index");
+    assert!(Path::root().with_extension("md").is_none());
+}
+
+#[test]
+fn test_prefix() {
+    let path = Path::new("/projects/alpha/index.md").unwrap();
+    let base = Path::new("/projects").unwrap();
+
+    assert!(path.starts_with(&base));
+    assert!(path.starts_with(&Path::root()));
+    assert!(!path.starts_with(&Path::new("/proj").unwrap()));
+
+    assert_eq!(
+        path.strip_prefix(&base).unwrap().to_string(),
+        "/alpha/index.md"
+    );
+    assert_eq!(base.strip_prefix(&base).unwrap(), Path::root());
+    assert!(base.strip_prefix(&path).is_none());
+}
+
+#[test]
+fn test_relative_to() {
+    let path = Path::new("/projects/alpha/index.md").unwrap();
+
+    assert_eq!(
+        path.relative_to(&Path::new("/projects").unwrap()),
+        "alpha/index.md"
+    );
+    assert_eq!(
+        path.relative_to(&Path::new("/people/bob").unwrap()),
+        "../../projects/alpha/index.md"
+    );
+    assert_eq!(path.relative_to(&path), ".");
+}
+
+#[test]
+fn test_serde() {
+    let path: Path = serde_json::from_str("\"a//b/../c\"").unwrap();
+    assert_eq!(path.to_string(), "/a/c");
+    assert!(serde_json::from_str::<Path>("\"../a\"").is_err());
+}
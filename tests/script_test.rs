@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use emerald::script::ScriptEngine;
+use emerald::{script::ScriptEngine, storage::MemoryStorage, Emerald};
 
 mod common;
 
@@ -20,3 +20,67 @@ fn test_script() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_script_in_memory() -> Result<(), Box<dyn Error>> {
+    let storage = MemoryStorage::from_iter([("/index.md", "# Index"), ("/a/b.md", "# B")]);
+    let emerald = Emerald::from_storage(storage);
+    let mut scripts = ScriptEngine::new();
+    let inst = scripts.new_instance(&emerald)?;
+    inst.execute(
+        r#"
+        local count = 0
+        for entry in emerald.fs.walk("") do
+            if entry.metadata.is_shard then
+                count = count + 1
+            end
+        end
+        assert(count == 2)
+    "#,
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn test_script_walk_options() -> Result<(), Box<dyn Error>> {
+    let storage = MemoryStorage::from_iter([
+        ("/notes/10.md", ""),
+        ("/notes/2.md", ""),
+        ("/notes/deep/1.md", ""),
+        ("/notes/draft.txt", ""),
+    ]);
+    let emerald = Emerald::from_storage(storage);
+    let mut scripts = ScriptEngine::new();
+    let inst = scripts.new_instance(&emerald)?;
+    inst.execute(
+        r#"
+        local paths = {}
+        for entry in emerald.fs.walk("/notes", { max_depth = 1, include = { "*.md" }, sort = "natural" }) do
+            table.insert(paths, entry.path:tostring())
+        end
+        assert(#paths == 2)
+        assert(paths[1] == "/notes/2.md")
+        assert(paths[2] == "/notes/10.md")
+    "#,
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn test_script_restricted_sandbox() -> Result<(), Box<dyn Error>> {
+    let emerald = Emerald::from_storage(MemoryStorage::from_iter([("/index.md", "# Index")]));
+    let mut scripts = ScriptEngine::new();
+
+    // A restricted handle cannot reach the host files around its access checks.
+    let inst = scripts.new_instance(&emerald.read_only())?;
+    inst.execute("assert(io == nil and os == nil and dofile == nil and loadfile == nil)")?;
+    assert!(inst.execute(r#"io.open("/etc/hostname")"#).is_err());
+    inst.execute(r#"for entry in emerald.fs.walk("/") do assert(entry.metadata.is_shard) end"#)?;
+
+    let inst = scripts.new_instance(&emerald)?;
+    inst.execute("assert(io ~= nil and os ~= nil)")?;
+
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use emerald::shard::{
+    ast::{traits::Node as _, Ast, NodeAttributes},
+    Shard, Value,
+};
+
+const DOCUMENT: &str = r#"---
+title: Round trip
+tags: [markdown]
+---
+
+# Heading  with *emphasis* #tag
+
+Some __strong__, `code`, ~~deleted~~ and an escaped \*star\*.
+A [link](https://example.com "Title") and ![an image](image.png),
+a [reference][ref], a [[wikilink|alias]] and [field:: value].
+
+> A quote
+> - with a list
+>   continued
+>
+> > nested
+
+1. First
+2. Second
+   - [ ] task
+   - [x] done
+
+* loose
+
+* list
+
+```rust title="main"
+fn main() {}
+```
+
+    indented code
+
+| Left | Center | Right |
+| :--- | :----: | ----: |
+| a    | `b\|c` | **d** |
+
+Text with a footnote[^1].
+
+[^1]: The footnote.
+
+[ref]: https://example.com/ref 'Reference'
+
+<div>html</div>
+
+***
+Trailing spaces  
+and a hard break.
+"#;
+
+fn edit(ast: &mut Ast, matches: impl Fn(&NodeAttributes) -> bool, attributes: NodeAttributes) {
+    let index = ast
+        .walk_ref()
+        .find(|node| matches(node.get_attributes()))
+        .unwrap()
+        .index;
+    *ast.get_mut(index).unwrap().attributes_mut() = attributes;
+}
+
+fn text(value: &str) -> impl Fn(&NodeAttributes) -> bool + '_ {
+    move |attributes| matches!(attributes, NodeAttributes::Text(text) if text == value)
+}
+
+/// Serialize every node from its attributes.
+fn regenerate(ast: &mut Ast) -> String {
+    let indices = ast.walk_ref().map(|node| node.index).collect::<Vec<_>>();
+
+    for index in indices {
+        ast.get_mut(index);
+    }
+
+    ast.to_string()
+}
+
+#[test]
+fn test_round_trip() {
+    let shard: Shard = DOCUMENT.parse().unwrap();
+    assert_eq!(shard.ast.to_string(), DOCUMENT);
+
+    let crlf = "# Title\r\n\r\n- a\r\n- b\r\n";
+    assert_eq!(crlf.parse::<Ast>().unwrap().to_string(), crlf);
+}
+
+#[test]
+fn test_minimal_diff() {
+    let mut ast: Ast = DOCUMENT.parse().unwrap();
+
+    edit(
+        &mut ast,
+        text("A quote"),
+        NodeAttributes::Text("An *edited*\nquote".into()),
+    );
+    edit(
+        &mut ast,
+        text("with a list\ncontinued"),
+        NodeAttributes::Text("edited\nitem".into()),
+    );
+    edit(
+        &mut ast,
+        text("Second"),
+        NodeAttributes::Text("Deuxième".into()),
+    );
+
+    let expected = DOCUMENT
+        .replace("> A quote\n", "> An \\*edited\\*\n> quote\n")
+        .replace("> - with a list\n>   continued\n", "> - edited\n>   item\n")
+        .replace("2. Second", "2. Deuxième");
+    assert_eq!(ast.to_string(), expected);
+}
+
+#[test]
+fn test_edit_frontmatter() {
+    let mut ast: Ast = "+++\ntitle = \"Old\"\n+++\n\nBody\n".parse().unwrap();
+    let index = ast.get_root().unwrap().children[0];
+
+    if let Some(mut node) = ast.get_mut(index) {
+        if let NodeAttributes::FrontMatter(frontmatter) = node.attributes_mut() {
+            frontmatter
+                .properties
+                .insert("title".into(), Value::String("New".into()));
+            frontmatter.properties.insert("draft".into(), Value::Null);
+        }
+    }
+
+    assert_eq!(ast.to_string(), "+++\ntitle = \"New\"\n+++\n\nBody\n");
+}
+
+#[test]
+fn test_generate() {
+    let mut ast: Ast = DOCUMENT.parse().unwrap();
+    let generated = regenerate(&mut ast);
+
+    assert!(generated.starts_with(
+        "---\ntitle: Round trip\ntags:\n- markdown\n---\n\n# Heading  with *emphasis* #tag\n"
+    ));
+    assert!(
+        generated.contains("\n> A quote\n>\n> - with a list\n>   continued\n"),
+        "{}",
+        generated
+    );
+    assert!(generated
+        .contains("| Left | Center | Right |\n| :-- | :-: | --: |\n| a | `b\\|c` | **d** |"));
+    assert!(generated.contains("1. First\n2. Second\n   - [ ] task\n   - [x] done"));
+    assert!(generated.contains("[^1]: The footnote."));
+
+    // The generated markdown is stable.
+    let mut reparsed: Ast = generated.parse().unwrap();
+    assert_eq!(reparsed.to_string(), generated);
+    assert_eq!(regenerate(&mut reparsed), generated);
+}
+
+#[test]
+fn test_escape_block_markers() {
+    let mut ast: Ast = "Some text\n\n- item\n".parse().unwrap();
+
+    edit(
+        &mut ast,
+        text("Some text"),
+        NodeAttributes::Text("# not a heading\n- not a list\n  1. nor > a quote".into()),
+    );
+    edit(
+        &mut ast,
+        text("item"),
+        NodeAttributes::Text("+ still an item\n2) with text".into()),
+    );
+
+    let generated = ast.to_string();
+    assert_eq!(
+        generated,
+        "\\# not a heading\n\\- not a list\n  1\\. nor > a quote\n\n- \\+ still an item\n  2\\) with text\n"
+    );
+
+    // The edited text reads back as the same paragraphs.
+    let reparsed: Ast = generated.parse().unwrap();
+    let texts = reparsed
+        .walk_ref()
+        .filter_map(|node| match node.get_attributes() {
+            NodeAttributes::Text(value) => Some(value.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        texts,
+        vec![
+            "# not a heading\n- not a list\n1. nor > a quote",
+            "+ still an item\n2) with text",
+        ]
+    );
+}
+
+#[test]
+fn test_escape_inline_markup() {
+    let source = "See \\#tag, \\(key:: value), &amp;copy; and \\~\\~kept\\~\\~.\n\n#1 a#b (no field) &amp ~\n";
+    let nodes = |ast: &Ast| {
+        ast.walk_ref()
+            .map(|node| format!("{:?}", node.get_attributes()))
+            .collect::<Vec<_>>()
+    };
+    let parsed: Ast = source.parse().unwrap();
+    let value = "See #tag, (key:: value), &copy; and ~~kept~~.";
+    assert!(nodes(&parsed).contains(&format!("{:?}", NodeAttributes::Text(value.into()))));
+
+    // The edited text reads back as the same nodes.
+    let mut ast: Ast = source.parse().unwrap();
+    edit(&mut ast, text(value), NodeAttributes::Text(value.into()));
+    let generated = ast.to_string();
+    assert_eq!(
+        generated,
+        "See \\#tag, \\(key:: value), \\&copy; and \\~\\~kept\\~\\~.\n\n#1 a#b (no field) &amp ~\n"
+    );
+    let reparsed: Ast = generated.parse().unwrap();
+    assert_eq!(nodes(&reparsed), nodes(&parsed));
+
+    // So does the regenerated document.
+    let mut ast: Ast = source.parse().unwrap();
+    let generated = regenerate(&mut ast);
+    let reparsed: Ast = generated.parse().unwrap();
+    assert_eq!(nodes(&reparsed), nodes(&parsed), "{}", generated);
+}
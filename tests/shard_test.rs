@@ -0,0 +1,87 @@
+use emerald::shard::{
+    ast::{traits::Node as _, InlineFieldKind, NodeAttributes},
+    Shard, Value,
+};
+
+fn shard(content: &str) -> Shard {
+    content.parse().unwrap()
+}
+
+fn string(value: &Value) -> &str {
+    match value {
+        Value::String(value) => value,
+        value => panic!("not a string: {:?}", value),
+    }
+}
+
+#[test]
+fn test_inline_fields() {
+    let shard = shard("# Heading\nThis is a content [property:: value]\n\nstatus:: draft\nReviewed (by:: Alice), std::fs is not a field\n");
+    let fields = shard.ast.inline_fields();
+
+    assert_eq!(fields.len(), 3);
+    assert_eq!(
+        (fields[0].key.as_str(), fields[0].value.as_str()),
+        ("property", "value")
+    );
+    assert_eq!(fields[0].kind, InlineFieldKind::Bracket);
+    assert_eq!(fields[1].kind, InlineFieldKind::Line);
+    assert_eq!(fields[2].kind, InlineFieldKind::Paren);
+
+    // The field is a dedicated node, located in the document.
+    let node = shard
+        .walk_ref()
+        .find(|node| matches!(node.get_attributes(), NodeAttributes::InlineField(_)))
+        .unwrap();
+    let position = node.get_position().unwrap();
+    assert_eq!(position.start.line, 2);
+    assert_eq!(position.start.column, 19);
+    assert_eq!(
+        position.end.offset - position.start.offset,
+        "[property:: value]".len()
+    );
+
+    // The text around the fields is kept.
+    assert!(shard
+        .ast
+        .to_string()
+        .contains("This is a content [property:: value]"));
+    assert!(shard
+        .ast
+        .to_string()
+        .contains("Reviewed (by:: Alice), std::fs is not a field"));
+}
+
+#[test]
+fn test_properties() {
+    let shard = shard(
+        "---\ntitle: Fields\ntags: [rust]\n---\nstatus:: draft\nrating:: 4.5\n\n- [tags:: emerald] [tags:: markdown]\n- (done:: true) (count:: 3) (empty::)\n\n`[code:: ignored]`\n",
+    );
+    let properties = shard.properties().into_map().unwrap();
+
+    assert_eq!(
+        properties.keys().collect::<Vec<_>>(),
+        vec!["title", "tags", "status", "rating", "done", "count", "empty"]
+    );
+    assert_eq!(string(&properties["title"]), "Fields");
+    assert_eq!(string(&properties["status"]), "draft");
+    assert!(matches!(properties["rating"], Value::Number(_)));
+    assert!(matches!(properties["done"], Value::Boolean(true)));
+    assert!(matches!(properties["count"], Value::Number(_)));
+    assert!(matches!(properties["empty"], Value::Null));
+
+    // The repeated keys accumulate, after the values of the frontmatter.
+    let Value::Array(tags) = &properties["tags"] else {
+        panic!("tags is not an array");
+    };
+    assert_eq!(
+        tags.iter().map(string).collect::<Vec<_>>(),
+        vec!["rust", "emerald", "markdown"]
+    );
+
+    let repeated = self::shard("a:: 1\n\na:: 2\n")
+        .properties()
+        .into_map()
+        .unwrap();
+    assert!(matches!(&repeated["a"], Value::Array(values) if values.len() == 2));
+}
@@ -0,0 +1,156 @@
+use std::io::{ErrorKind, Read, Write};
+
+use emerald::{
+    path::Path,
+    storage::{DiskStorage, MemoryStorage, Storage},
+    Emerald, Error,
+};
+
+mod common;
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        ("/index.md", "# Index"),
+        ("/projects/alpha.md", "# Alpha"),
+        ("/projects/beta/notes.txt", "notes"),
+        ("/shared", "@/>/projects"),
+    ]))
+}
+
+#[test]
+fn test_memory_read_dir() {
+    let jewel = memory_jewel();
+
+    let mut entries = emerald::fs::read_dir(&jewel, &Path::root())
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string())
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    assert_eq!(entries, vec!["/index.md", "/projects", "/shared"]);
+}
+
+#[test]
+fn test_memory_walk() {
+    let jewel = memory_jewel();
+
+    let entries = emerald::fs::walk(&jewel, &Path::new("/projects").unwrap())
+        .unwrap()
+        .map(Result::unwrap)
+        .filter(|entry| entry.metadata().is_shard())
+        .map(|entry| entry.path().to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(entries, vec!["/projects/alpha.md"]);
+}
+
+#[test]
+fn test_memory_symlink() {
+    let jewel = memory_jewel();
+    let link = Path::new("/shared").unwrap();
+
+    assert!(emerald::fs::metadata(&jewel, &link).unwrap().is_symlink());
+
+    let mut content = String::default();
+    emerald::fs::open(&jewel, &link.join("alpha.md").unwrap())
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+
+    assert_eq!(content, "# Alpha");
+}
+
+#[test]
+fn test_memory_write() {
+    let jewel = memory_jewel();
+    let path = Path::new("/people/alice.md").unwrap();
+
+    assert!(emerald::fs::write(&jewel, &path, "alice").is_err());
+
+    emerald::fs::create_dir_all(&jewel, &path.parent().unwrap()).unwrap();
+    emerald::fs::write(&jewel, &path, "alice").unwrap();
+    assert!(emerald::fs::metadata(&jewel, &path).unwrap().is_shard());
+
+    let mut file = emerald::fs::create(&jewel, &path).unwrap();
+    file.write_all(b"bob").unwrap();
+    drop(file);
+
+    assert_eq!(
+        jewel
+            .storage()
+            .read(&jewel.get_root().join("people/alice.md"))
+            .unwrap(),
+        b"bob"
+    );
+}
+
+#[test]
+fn test_memory_rename_and_remove() {
+    let jewel = memory_jewel();
+    let from = Path::new("/projects").unwrap();
+    let to = Path::new("/archive").unwrap();
+
+    emerald::fs::rename(&jewel, &from, &to).unwrap();
+    assert!(emerald::fs::metadata(&jewel, &from).is_err());
+    assert!(
+        emerald::fs::metadata(&jewel, &to.join("beta/notes.txt").unwrap())
+            .unwrap()
+            .is_file()
+    );
+
+    emerald::fs::remove_file(&jewel, &to.join("alpha.md").unwrap()).unwrap();
+    emerald::fs::remove_dir_all(&jewel, &to).unwrap();
+    assert!(emerald::fs::metadata(&jewel, &to.join("beta").unwrap()).is_err());
+}
+
+/// Rename onto existing entries, the backends fail as POSIX does.
+fn check_rename_conflicts(storage: &dyn Storage) {
+    let root = storage.root().to_path_buf();
+    let path = |name: &str| root.join(name);
+
+    for dir in ["a", "a/sub", "b", "b/sub", "empty"] {
+        storage.create_dir(&path(dir)).unwrap();
+    }
+
+    storage.write(&path("a/sub/note.md"), b"a").unwrap();
+    storage.write(&path("b/sub/other.md"), b"b").unwrap();
+    storage.write(&path("file.md"), b"file").unwrap();
+
+    let kind = |from: &str, to: &str| storage.rename(&path(from), &path(to)).unwrap_err().kind();
+
+    assert_eq!(kind("file.md", "b"), ErrorKind::IsADirectory);
+    assert_eq!(kind("a", "file.md"), ErrorKind::NotADirectory);
+    assert_eq!(kind("a", "b"), ErrorKind::DirectoryNotEmpty);
+
+    // Nothing moved, the trees are not merged.
+    assert!(storage.exists(&path("b/sub/other.md")));
+    assert!(!storage.exists(&path("b/sub/note.md")));
+    assert!(storage.exists(&path("a/sub/note.md")));
+    assert_eq!(storage.read(&path("file.md")).unwrap(), b"file");
+
+    // An empty directory is replaced.
+    storage.rename(&path("a"), &path("empty")).unwrap();
+    assert!(!storage.exists(&path("a")));
+    assert_eq!(storage.read(&path("empty/sub/note.md")).unwrap(), b"a");
+    assert_eq!(storage.read_dir(&path("empty")).unwrap(), vec!["sub"]);
+}
+
+#[test]
+fn test_rename_conflicts() {
+    check_rename_conflicts(&MemoryStorage::new());
+    check_rename_conflicts(&DiskStorage::new(temp_emerald!("rename_conflicts")));
+}
+
+#[test]
+fn test_memory_parent_components() {
+    let storage = MemoryStorage::from_iter([("/projects/alpha.md", "# Alpha")]);
+    let escaping = std::path::Path::new("/projects/../projects/alpha.md");
+
+    let err = storage.read(escaping).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(matches!(Error::from(err), Error::InvalidPath(_)));
+    assert!(storage
+        .rename(escaping, std::path::Path::new("/beta.md"))
+        .is_err());
+    assert!(storage.exists(std::path::Path::new("/./projects/alpha.md")));
+}
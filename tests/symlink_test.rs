@@ -0,0 +1,109 @@
+use emerald::{fs::SymlinkTarget, path::Path, storage::MemoryStorage, Emerald};
+
+mod common;
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        ("/projects/alpha/index.md", "# Alpha"),
+        ("/people/bob.md", "# Bob"),
+    ]))
+}
+
+#[test]
+fn test_create_and_read_link() {
+    let jewel = memory_jewel();
+    let link = Path::new("/alpha").unwrap();
+
+    emerald::fs::symlink(&jewel, &link, "/projects/alpha").unwrap();
+
+    assert!(emerald::fs::metadata(&jewel, &link).unwrap().is_symlink());
+    assert_eq!(
+        emerald::fs::read_link(&jewel, &link).unwrap(),
+        SymlinkTarget::Jewel(Path::new("/projects/alpha").unwrap())
+    );
+
+    let entries = emerald::fs::read_dir(&jewel, &link)
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(entries, vec!["/alpha/index.md"]);
+
+    assert!(emerald::fs::symlink(&jewel, &link, "/people").is_err());
+    assert!(emerald::fs::symlink(&jewel, &Path::new("/link.md").unwrap(), "/people").is_err());
+    assert!(emerald::fs::symlink(&jewel, &Path::new("/escape").unwrap(), "../..").is_err());
+}
+
+#[test]
+fn test_relative_link() {
+    let jewel = memory_jewel();
+    let link = Path::new("/people/alpha").unwrap();
+
+    emerald::fs::symlink(&jewel, &link, "../projects/alpha").unwrap();
+
+    assert_eq!(
+        emerald::fs::read_link(&jewel, &link).unwrap(),
+        SymlinkTarget::Jewel(Path::new("/projects/alpha").unwrap())
+    );
+    assert!(
+        emerald::fs::metadata(&jewel, &link.join("index.md").unwrap())
+            .unwrap()
+            .is_shard()
+    );
+}
+
+#[test]
+fn test_dangling_link() {
+    let jewel = memory_jewel();
+    let link = Path::new("/gone").unwrap();
+
+    emerald::fs::symlink(&jewel, &link, "/projects/gone").unwrap();
+
+    assert!(emerald::fs::is_dangling(&jewel, &link).unwrap());
+    // Not a symlink.
+    assert!(emerald::fs::is_dangling(&jewel, &Path::new("/projects").unwrap()).is_err());
+    assert!(emerald::fs::read_dir(&jewel, &link).is_err());
+
+    // Walking does not go through dangling links.
+    assert_eq!(emerald::fs::walk(&jewel, &Path::root()).unwrap().count(), 6);
+}
+
+#[test]
+fn test_walk_symlink_loop() {
+    let jewel = memory_jewel();
+
+    emerald::fs::symlink(&jewel, &Path::new("/projects/alpha/up").unwrap(), "/").unwrap();
+    emerald::fs::symlink(&jewel, &Path::new("/a").unwrap(), "/b").unwrap();
+    emerald::fs::symlink(&jewel, &Path::new("/b").unwrap(), "/a").unwrap();
+
+    let entries = emerald::fs::walk(&jewel, &Path::root())
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string())
+        .collect::<Vec<_>>();
+
+    assert!(entries.contains(&"/projects/alpha/up".to_string()));
+    assert!(!entries
+        .iter()
+        .any(|path| path.starts_with("/projects/alpha/up/")));
+    assert!(emerald::fs::is_dangling(&jewel, &Path::new("/a").unwrap()).unwrap());
+}
+
+#[test]
+fn test_storage_link() {
+    let root = temp_emerald!("storage_link");
+    let target = temp_emerald!("storage_link_target");
+    std::fs::write(target.join("shard.md"), "# Shard\n").unwrap();
+    std::fs::write(root.join("notes"), format!("@/>{}\n", target.display())).unwrap();
+
+    let jewel = emerald::open(&root).unwrap();
+    let link = Path::new("/notes").unwrap();
+
+    assert_eq!(
+        emerald::fs::read_link(&jewel, &link).unwrap(),
+        SymlinkTarget::Storage(target.clone())
+    );
+    assert!(!emerald::fs::is_dangling(&jewel, &link).unwrap());
+    assert_eq!(
+        emerald::fs::canonicalize(&jewel, &link.join("shard.md").unwrap()).unwrap(),
+        target.join("shard.md")
+    );
+}
@@ -0,0 +1,184 @@
+use emerald::{
+    error::Error,
+    shard::{
+        ast::{AlignKind, Ast, TableView},
+        Value,
+    },
+};
+use indexmap::IndexMap;
+
+const DOCUMENT: &str = r#"# Projects
+
+| Name        | Status | Rating |
+| :---------- | :----: | -----: |
+| **Alpha**   | draft  |      4 |
+| [[Beta]]    | `a\|b` |    4.5 |
+| Gamma       |        |
+
+Notes.
+"#;
+
+#[test]
+fn test_read_table() {
+    let ast: Ast = DOCUMENT.parse().unwrap();
+    let tables = ast.tables();
+    assert_eq!(tables.len(), 1);
+
+    let (_, table) = &tables[0];
+    assert_eq!(table.header, vec!["Name", "Status", "Rating"]);
+    assert_eq!(
+        table.align,
+        vec![AlignKind::Left, AlignKind::Center, AlignKind::Right]
+    );
+    assert_eq!(
+        table.rows,
+        vec![
+            vec!["Alpha", "draft", "4"],
+            vec!["Beta", "a|b", "4.5"],
+            vec!["Gamma", ""],
+        ]
+    );
+
+    let records = table.records();
+    assert!(matches!(&records[0]["Rating"], Value::Number(_)));
+    assert!(matches!(&records[1]["Status"], Value::String(status) if status == "a|b"));
+    assert!(matches!(records[2]["Rating"], Value::Null));
+
+    let heading = ast.get_root().unwrap().children[0];
+    assert!(ast.table(heading).is_none());
+}
+
+#[test]
+fn test_set_table() {
+    let mut ast: Ast = DOCUMENT.parse().unwrap();
+    let (index, mut table) = ast.tables().remove(0);
+
+    table.rows[2][1] = "done | archived".into();
+    table.rows.push(vec!["Delta".into()]);
+    ast.set_table(index, &table).unwrap();
+
+    assert_eq!(
+        ast.to_string(),
+        r#"# Projects
+
+| Name | Status | Rating |
+| :-- | :-: | --: |
+| **Alpha** | draft | 4 |
+| [[Beta]] | `a\|b` | 4.5 |
+| Gamma | done \| archived |  |
+| Delta |  |  |
+
+Notes.
+"#
+    );
+
+    // The serialized table reads back as the view.
+    let reparsed: Ast = ast.to_string().parse().unwrap();
+    assert_eq!(reparsed.tables()[0].1, table_padded(table));
+
+    let heading = ast.get_root().unwrap().children[0];
+    assert!(matches!(
+        ast.set_table(heading, &TableView::default()),
+        Err(Error::InvalidInput(_))
+    ));
+    assert!(matches!(
+        ast.set_table(index, &TableView::default()),
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn test_table_markup_round_trip() {
+    let source = r#"| Name | Links | Notes |
+| --- | :-: | --- |
+| *Alpha* | [site](https://example.com "Site") | #project, `a\|b` |
+| **Beta** | [[Projects/Beta\|Beta]] | ~~old~~ and <br> |
+"#;
+
+    // The unchanged cells are written back with their markup.
+    let mut ast: Ast = source.parse().unwrap();
+    let (index, table) = ast.tables().remove(0);
+    ast.set_table(index, &table).unwrap();
+    assert_eq!(ast.to_string(), source);
+
+    // A changed cell is generated, a moved row keeps its markup.
+    let (index, mut table) = ast.tables().remove(0);
+    table.rows.swap(0, 1);
+    table.rows[1][0] = "Gamma".into();
+    table.rows.insert(0, vec!["Delta".into()]);
+    ast.set_table(index, &table).unwrap();
+    assert_eq!(
+        ast.to_string(),
+        r#"| Name | Links | Notes |
+| --- | :-: | --- |
+| Delta |  |  |
+| **Beta** | [[Projects/Beta\|Beta]] | ~~old~~ and <br> |
+| Gamma | [site](https://example.com "Site") | #project, `a\|b` |
+"#
+    );
+
+    let reparsed: Ast = ast.to_string().parse().unwrap();
+    assert_eq!(reparsed.tables()[0].1, table_padded(table));
+}
+
+/// The rows padded to the width of the header.
+fn table_padded(mut table: TableView) -> TableView {
+    for row in &mut table.rows {
+        row.resize(table.header.len(), String::default());
+    }
+
+    table
+}
+
+#[test]
+fn test_insert_table() {
+    let mut ast: Ast = "# Title\n\n> Quote\n\nEnd\n".parse().unwrap();
+    let records = vec![
+        IndexMap::from([
+            ("name".to_string(), Value::String("Alpha".into())),
+            ("done".to_string(), Value::Boolean(true)),
+        ]),
+        IndexMap::from([
+            ("name".to_string(), Value::String("Beta".into())),
+            (
+                "tags".to_string(),
+                Value::Array(vec![Value::String("a".into()), Value::String("b".into())]),
+            ),
+        ]),
+    ];
+
+    let mut table = TableView::from_records(&records);
+    assert_eq!(table.header, vec!["name", "done", "tags"]);
+    table.align[1] = AlignKind::Center;
+
+    let root = ast.get_root().unwrap().index;
+    ast.insert_table(root, 1, &table).unwrap();
+
+    let quote = ast.get_root().unwrap().children[2];
+    ast.insert_table(quote, 1, &table).unwrap();
+
+    assert_eq!(
+        ast.to_string(),
+        r#"# Title
+
+| name | done | tags |
+| --- | :-: | --- |
+| Alpha | true |  |
+| Beta |  | a, b |
+
+> Quote
+>
+> | name | done | tags |
+> | --- | :-: | --- |
+> | Alpha | true |  |
+> | Beta |  | a, b |
+
+End
+"#
+    );
+
+    let reparsed: Ast = ast.to_string().parse().unwrap();
+    let records = reparsed.tables()[0].1.records();
+    assert!(matches!(records[0]["done"], Value::Boolean(true)));
+    assert!(matches!(records[1]["done"], Value::Null));
+}
@@ -0,0 +1,88 @@
+use emerald::{
+    fs::WalkBuilder,
+    path::Path,
+    shard::{
+        ast::{traits::Node as _, NodeAttributes},
+        Shard,
+    },
+    storage::MemoryStorage,
+    Emerald,
+};
+
+fn memory_jewel() -> Emerald {
+    let storage = MemoryStorage::from_iter([
+        ("/index.md", "---\ntags: todo, #home\n---\n# Index #home\n"),
+        (
+            "/projects/alpha.md",
+            "Working on #area/web/frontend and #todo",
+        ),
+        ("/projects/beta.md", "---\ntags: [area/web]\n---\n#area"),
+        ("/projects/broken.md", "---\ntags: [\n---\n#broken"),
+        ("/notes.txt", "#ignored"),
+    ]);
+
+    // A shard which is not valid UTF-8 is skipped.
+    storage.insert("/projects/binary.md", b"#binary \xff".to_vec());
+    Emerald::from_storage(storage)
+}
+
+fn names(paths: &[Path]) -> Vec<&str> {
+    paths.iter().map(Path::as_str).collect()
+}
+
+#[test]
+fn test_shard_tags() {
+    let shard: Shard = "---\ntags: [rust, \"#emerald\"]\n---\n# Notes #rust\n\nSee #area/sub-area/, `#code` and [the page](https://example.com/#anchor), issue #42 #todo\n"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        shard.tags(),
+        vec!["rust", "emerald", "area/sub-area", "todo"]
+    );
+
+    // The tags of the content are dedicated nodes.
+    let nodes = shard
+        .walk_ref()
+        .filter(|node| matches!(node.get_attributes(), NodeAttributes::Tag(_)))
+        .count();
+    assert_eq!(nodes, 3);
+    assert!(shard.ast.to_string().contains("See #area/sub-area/, "));
+
+    // An escaped `#` starts no tag.
+    let content = "Not a \\#tag, but #one\n> and \\#quoted\n";
+    let escaped: Shard = content.parse().unwrap();
+    assert_eq!(escaped.tags(), vec!["one"]);
+    assert_eq!(escaped.ast.to_string(), content);
+}
+
+#[test]
+fn test_jewel_tags() {
+    let jewel = memory_jewel();
+    let tags = emerald::fs::tags(&jewel).unwrap();
+
+    assert_eq!(
+        tags.iter().map(|tag| tag.name.as_str()).collect::<Vec<_>>(),
+        vec!["area", "area/web", "area/web/frontend", "home", "todo"]
+    );
+
+    let area = &tags[0];
+    assert_eq!(names(&area.shards), vec!["/projects/beta.md"]);
+    assert_eq!(
+        names(&area.rollup),
+        vec!["/projects/alpha.md", "/projects/beta.md"]
+    );
+    assert_eq!((area.count(), area.total()), (1, 2));
+
+    let web = &tags[1];
+    assert_eq!(names(&web.shards), vec!["/projects/beta.md"]);
+    assert_eq!(web.total(), 2);
+
+    let todo = &tags[4];
+    assert_eq!(names(&todo.shards), vec!["/index.md", "/projects/alpha.md"]);
+
+    // The walk can be restricted.
+    let builder = WalkBuilder::new(&jewel, &Path::new("/projects").unwrap());
+    let tags = emerald::fs::tags_with(builder).unwrap();
+    assert!(tags.iter().all(|tag| tag.name != "home"));
+}
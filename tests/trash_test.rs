@@ -0,0 +1,195 @@
+use std::{error::Error, time::Duration};
+
+use emerald::{path::Path, script::ScriptEngine, storage::MemoryStorage, Emerald};
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        ("/index.md", "# Index"),
+        ("/projects/alpha.md", "# Alpha"),
+        ("/projects/beta.md", "# Beta"),
+    ]))
+}
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+fn walk(jewel: &Emerald) -> Vec<String> {
+    emerald::fs::WalkBuilder::new(jewel, &Path::root())
+        .hidden(true)
+        .build()
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string())
+        .collect()
+}
+
+#[test]
+fn test_trash_and_restore() {
+    let jewel = memory_jewel();
+
+    let entry = emerald::fs::trash(&jewel, &path("/projects/alpha.md")).unwrap();
+    assert_eq!(entry.path.as_str(), "/projects/alpha.md");
+    assert!(!entry.is_symlink);
+    assert!(!entry.is_dir);
+    assert!(emerald::fs::metadata(&jewel, &path("/projects/alpha.md")).is_err());
+
+    // Trashed entries are hidden from the listings.
+    assert_eq!(
+        walk(&jewel),
+        vec!["/.emerald", "/index.md", "/projects", "/projects/beta.md"]
+    );
+    assert!(emerald::fs::glob(&jewel, "/**/alpha.md")
+        .unwrap()
+        .next()
+        .is_none());
+
+    assert_eq!(
+        emerald::fs::list_trash(&jewel).unwrap(),
+        vec![entry.clone()]
+    );
+
+    let restored = emerald::fs::restore(&jewel, &entry.id).unwrap();
+    assert_eq!(restored.as_str(), "/projects/alpha.md");
+    assert!(emerald::fs::metadata(&jewel, &restored).unwrap().is_shard());
+    assert!(emerald::fs::list_trash(&jewel).unwrap().is_empty());
+}
+
+#[test]
+fn test_trash_dir_and_symlink() {
+    let jewel = memory_jewel();
+    emerald::fs::symlink(&jewel, &path("/current"), "/projects").unwrap();
+
+    let link = emerald::fs::trash(&jewel, &path("/current")).unwrap();
+    assert!(link.is_symlink);
+    assert!(emerald::fs::metadata(&jewel, &path("/projects")).is_ok());
+
+    let dir = emerald::fs::trash(&jewel, &path("/projects")).unwrap();
+    assert!(dir.is_dir);
+    assert_eq!(walk(&jewel), vec!["/.emerald", "/index.md"]);
+
+    // The missing parents are created back.
+    emerald::fs::restore(&jewel, &dir.id).unwrap();
+    emerald::fs::restore(&jewel, &link.id).unwrap();
+    assert!(emerald::fs::read_link(&jewel, &path("/current")).is_ok());
+    assert_eq!(
+        emerald::fs::read_dir(&jewel, &path("/current"))
+            .unwrap()
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_restore_conflict() {
+    let jewel = memory_jewel();
+
+    let entry = emerald::fs::trash(&jewel, &path("/index.md")).unwrap();
+    emerald::fs::write(&jewel, &path("/index.md"), "# New index").unwrap();
+
+    assert!(matches!(
+        emerald::fs::restore(&jewel, &entry.id),
+        Err(emerald::Error::AlreadyExists(_))
+    ));
+    assert!(matches!(
+        emerald::fs::restore(&jewel, "../index.md"),
+        Err(emerald::Error::InvalidInput(_))
+    ));
+    assert!(matches!(
+        emerald::fs::trash(&jewel, &path("/.emerald/trash")),
+        Err(emerald::Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn test_empty_trash() {
+    let jewel = memory_jewel();
+
+    emerald::fs::trash(&jewel, &path("/index.md")).unwrap();
+    emerald::fs::trash(&jewel, &path("/projects/alpha.md")).unwrap();
+    assert_eq!(emerald::fs::list_trash(&jewel).unwrap().len(), 2);
+
+    assert_eq!(
+        emerald::fs::empty_trash(&jewel, Duration::from_secs(3600)).unwrap(),
+        0
+    );
+    assert_eq!(emerald::fs::empty_trash(&jewel, Duration::ZERO).unwrap(), 2);
+    assert!(emerald::fs::list_trash(&jewel).unwrap().is_empty());
+}
+
+#[test]
+fn test_remove_into_trash() {
+    let jewel = memory_jewel();
+
+    emerald::fs::remove_file(&jewel, &path("/index.md")).unwrap();
+    emerald::fs::remove_dir_all(&jewel, &path("/projects")).unwrap();
+    assert!(matches!(
+        emerald::fs::remove_file(&jewel, &path("/.emerald")),
+        Err(emerald::Error::IsADirectory(_))
+    ));
+
+    let entries = emerald::fs::list_trash(&jewel).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[1].is_dir);
+
+    for entry in &entries {
+        emerald::fs::restore(&jewel, &entry.id).unwrap();
+    }
+    assert!(emerald::fs::metadata(&jewel, &path("/projects/alpha.md")).is_ok());
+
+    // The permanent removals bypass the trash.
+    emerald::fs::remove_file_permanently(&jewel, &path("/index.md")).unwrap();
+    emerald::fs::remove_dir_all_permanently(&jewel, &path("/projects")).unwrap();
+    assert!(emerald::fs::list_trash(&jewel).unwrap().is_empty());
+    assert_eq!(walk(&jewel), vec!["/.emerald"]);
+}
+
+#[test]
+fn test_trash_restricted() {
+    let jewel = memory_jewel();
+    let projects = jewel.scoped_to(&path("/projects"));
+
+    let entry = emerald::fs::trash(&projects, &path("/projects/alpha.md")).unwrap();
+    assert!(matches!(
+        emerald::fs::trash(&projects, &path("/index.md")),
+        Err(emerald::Error::PermissionDenied(_))
+    ));
+    assert!(emerald::fs::trash(&jewel.read_only(), &path("/projects/beta.md")).is_err());
+
+    // The entries are listed for the handles which can read their original path.
+    emerald::fs::trash(&jewel, &path("/index.md")).unwrap();
+    assert_eq!(
+        emerald::fs::list_trash(&projects).unwrap(),
+        vec![entry.clone()]
+    );
+    assert_eq!(emerald::fs::list_trash(&jewel).unwrap().len(), 2);
+
+    emerald::fs::restore(&projects, &entry.id).unwrap();
+    assert!(emerald::fs::metadata(&jewel, &path("/projects/alpha.md")).is_ok());
+
+    assert_eq!(
+        emerald::fs::empty_trash(&projects, Duration::ZERO).unwrap(),
+        0
+    );
+    assert_eq!(emerald::fs::empty_trash(&jewel, Duration::ZERO).unwrap(), 1);
+}
+
+#[test]
+fn test_script_trash() -> Result<(), Box<dyn Error>> {
+    let jewel = memory_jewel();
+    let mut scripts = ScriptEngine::new();
+    let inst = scripts.new_instance(&jewel)?;
+    inst.execute(
+        r#"
+        local entry = emerald.fs.trash("/index.md")
+        assert(entry.path == "/index.md")
+        assert(#emerald.fs.list_trash() == 1)
+        assert(emerald.fs.restore(entry.id) == "/index.md")
+
+        emerald.fs.trash("/index.md")
+        assert(emerald.fs.empty_trash() == 1)
+    "#,
+    )?;
+
+    assert!(emerald::fs::metadata(&jewel, &path("/index.md")).is_err());
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use emerald::{
+    fs::{SortOrder, WalkBuilder},
+    path::Path,
+    storage::MemoryStorage,
+    Emerald,
+};
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        ("/.emeraldignore", "*.tmp\n/drafts/\n!keep.tmp\n"),
+        ("/.git/config", ""),
+        ("/index.md", ""),
+        ("/drafts/wip.md", ""),
+        ("/projects/10.md", ""),
+        ("/projects/2.md", ""),
+        ("/projects/cache.tmp", ""),
+        ("/projects/keep.tmp", ""),
+        ("/projects/archive/.emeraldignore", "*.md\n"),
+        ("/projects/archive/old.md", ""),
+        ("/projects/archive/old.txt", ""),
+    ]))
+}
+
+fn paths(builder: WalkBuilder) -> Vec<String> {
+    builder
+        .build()
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string())
+        .collect()
+}
+
+#[test]
+fn test_default_walk() {
+    let jewel = memory_jewel();
+
+    assert_eq!(
+        paths(WalkBuilder::new(&jewel, &Path::root())),
+        vec![
+            "/index.md",
+            "/projects",
+            "/projects/10.md",
+            "/projects/2.md",
+            "/projects/archive",
+            "/projects/archive/old.txt",
+            "/projects/keep.tmp",
+        ]
+    );
+}
+
+#[test]
+fn test_natural_sort() {
+    let jewel = memory_jewel();
+
+    assert_eq!(
+        paths(
+            WalkBuilder::new(&jewel, &Path::new("/projects").unwrap())
+                .max_depth(1)
+                .include("*.md")
+                .sort(SortOrder::Natural)
+        ),
+        vec!["/projects/2.md", "/projects/10.md"]
+    );
+}
+
+#[test]
+fn test_max_depth() {
+    let jewel = memory_jewel();
+
+    assert_eq!(
+        paths(WalkBuilder::new(&jewel, &Path::root()).max_depth(1)),
+        vec!["/index.md", "/projects"]
+    );
+    assert!(paths(WalkBuilder::new(&jewel, &Path::root()).max_depth(0)).is_empty());
+}
+
+#[test]
+fn test_hidden_and_ignore_files() {
+    let jewel = memory_jewel();
+
+    let all = paths(
+        WalkBuilder::new(&jewel, &Path::root())
+            .hidden(true)
+            .ignore_files(false),
+    );
+
+    assert!(all.contains(&"/.git/config".to_string()));
+    assert!(all.contains(&"/drafts/wip.md".to_string()));
+    assert!(all.contains(&"/projects/cache.tmp".to_string()));
+    assert!(all.contains(&"/projects/archive/old.md".to_string()));
+}
+
+#[test]
+fn test_include_exclude() {
+    let jewel = memory_jewel();
+
+    assert_eq!(
+        paths(
+            WalkBuilder::new(&jewel, &Path::root())
+                .include("/projects/**")
+                .exclude("archive")
+        ),
+        vec!["/projects/10.md", "/projects/2.md", "/projects/keep.tmp"]
+    );
+}
+
+#[test]
+fn test_symlinks() {
+    let jewel = memory_jewel();
+    emerald::fs::symlink(&jewel, &Path::new("/link").unwrap(), "/projects/archive").unwrap();
+
+    let followed = paths(
+        WalkBuilder::new(&jewel, &Path::root())
+            .max_depth(1)
+            .include("link/**"),
+    );
+    assert!(followed.is_empty());
+
+    let followed = paths(WalkBuilder::new(&jewel, &Path::root()).include("/link/*"));
+    assert_eq!(followed, vec!["/link/old.txt"]);
+
+    let not_followed = paths(
+        WalkBuilder::new(&jewel, &Path::root())
+            .follow_symlinks(false)
+            .include("/link/*"),
+    );
+    assert!(not_followed.is_empty());
+}
+
+#[test]
+fn test_symlinks_to_same_directory() {
+    let jewel = memory_jewel();
+    emerald::fs::symlink(&jewel, &Path::new("/first").unwrap(), "/projects/archive").unwrap();
+    emerald::fs::symlink(&jewel, &Path::new("/second").unwrap(), "/projects/archive").unwrap();
+    emerald::fs::symlink(&jewel, &Path::new("/projects/up").unwrap(), "/projects").unwrap();
+
+    // Each link is walked through, the loops are broken along the ancestors only.
+    let followed = paths(
+        WalkBuilder::new(&jewel, &Path::root())
+            .sort(Some(SortOrder::Name))
+            .include("*.txt"),
+    );
+    assert_eq!(
+        followed,
+        vec![
+            "/first/old.txt",
+            "/projects/archive/old.txt",
+            "/second/old.txt"
+        ]
+    );
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use emerald::{
+    fs::{Event, WatchBuilder, Watcher},
+    path::Path,
+    storage::MemoryStorage,
+    Emerald,
+};
+
+mod common;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn watcher(jewel: &Emerald) -> Watcher {
+    WatchBuilder::new(jewel, &Path::root())
+        .interval(Duration::from_millis(10))
+        .debounce(Duration::from_millis(50))
+        .build()
+        .unwrap()
+}
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+#[test]
+fn test_created_modified_removed() {
+    let root = temp_emerald!("watch");
+    let jewel = emerald::open(&root).unwrap();
+    let watcher = watcher(&jewel);
+
+    emerald::fs::write(&jewel, &path("/index.md"), "# Index").unwrap();
+    let event = watcher.recv_timeout(TIMEOUT).unwrap();
+    assert!(matches!(&event, Event::Created { path, .. } if path.as_str() == "/index.md"));
+    assert!(event.metadata().is_shard());
+
+    emerald::fs::write(&jewel, &path("/index.md"), "# Index\n\nUpdated").unwrap();
+    let event = watcher.recv_timeout(TIMEOUT).unwrap();
+    assert!(matches!(&event, Event::Modified { path, .. } if path.as_str() == "/index.md"));
+
+    emerald::fs::remove_file(&jewel, &path("/index.md")).unwrap();
+    let event = watcher.recv_timeout(TIMEOUT).unwrap();
+    assert!(matches!(&event, Event::Removed { path, .. } if path.as_str() == "/index.md"));
+
+    assert!(watcher.try_recv().is_none());
+}
+
+#[test]
+fn test_renamed() {
+    let jewel = Emerald::from_storage(MemoryStorage::from_iter([("/draft.md", "# Draft")]));
+    let watcher = watcher(&jewel);
+
+    emerald::fs::rename(&jewel, &path("/draft.md"), &path("/final.md")).unwrap();
+
+    let event = watcher.recv_timeout(TIMEOUT).unwrap();
+    assert!(matches!(
+        &event,
+        Event::Renamed { from, to, .. } if from.as_str() == "/draft.md" && to.as_str() == "/final.md"
+    ));
+}
+
+#[test]
+fn test_debounce() {
+    let jewel = Emerald::from_storage(MemoryStorage::new());
+    let watcher = WatchBuilder::new(&jewel, &Path::root())
+        .interval(Duration::from_millis(10))
+        .debounce(Duration::from_millis(200))
+        .build()
+        .unwrap();
+
+    emerald::fs::write(&jewel, &path("/index.md"), "# Index").unwrap();
+    emerald::fs::write(&jewel, &path("/index.md"), "# Index\n\nUpdated").unwrap();
+
+    let event = watcher.recv_timeout(TIMEOUT).unwrap();
+    assert!(matches!(&event, Event::Created { .. }));
+    assert!(watcher.recv_timeout(Duration::from_millis(300)).is_none());
+}
+
+#[test]
+fn test_ignore_rules() {
+    let jewel = Emerald::from_storage(MemoryStorage::from_iter([("/.emeraldignore", "*.tmp\n")]));
+    let watcher = watcher(&jewel);
+
+    emerald::fs::write(&jewel, &path("/cache.tmp"), "").unwrap();
+    emerald::fs::write(&jewel, &path("/.hidden.md"), "").unwrap();
+    emerald::fs::write(&jewel, &path("/index.md"), "").unwrap();
+
+    let event = watcher.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(event.path().as_str(), "/index.md");
+    assert!(watcher.recv_timeout(Duration::from_millis(200)).is_none());
+}
+
+#[test]
+fn test_emerald_watch() {
+    let jewel = Emerald::from_storage(MemoryStorage::new());
+    let watcher = jewel.watch().unwrap();
+    emerald::fs::create_dir_all(&jewel, &path("/projects")).unwrap();
+
+    let event = watcher.recv_timeout(TIMEOUT).unwrap();
+    assert!(event.metadata().is_dir());
+}
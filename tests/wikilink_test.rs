@@ -0,0 +1,127 @@
+use emerald::{
+    path::Path,
+    shard::{
+        ast::{traits::Node as _, NodeAttributes, WikiLinkAnchor},
+        Shard,
+    },
+    storage::MemoryStorage,
+    Emerald, Error,
+};
+
+fn path(value: &str) -> Path {
+    Path::new(value).unwrap()
+}
+
+fn memory_jewel() -> Emerald {
+    Emerald::from_storage(MemoryStorage::from_iter([
+        ("/index.md", "# Index"),
+        ("/Meetings.md", "# Meetings"),
+        ("/projects/alpha.md", "# Alpha"),
+        ("/projects/notes.md", "# Project notes"),
+        ("/projects/diagram.png", "png"),
+        ("/archive/projects/alpha.md", "# Old alpha"),
+        ("/archive/notes.md", "# Archived notes"),
+        ("/people/notes.md", "# People notes"),
+    ]))
+}
+
+#[test]
+fn test_wikilink_nodes() {
+    let content =
+        "See [[Meetings#Monday|the meeting]] and *[[alpha#^summary]]*\n\n![[diagram.png]]\n";
+    let shard: Shard = content.parse().unwrap();
+
+    let links = shard
+        .walk_ref()
+        .filter_map(|node| match node.get_attributes() {
+            NodeAttributes::WikiLink(link) => Some((false, link.clone())),
+            NodeAttributes::Embed(link) => Some((true, link.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(links.len(), 3);
+    let (embed, meeting) = &links[0];
+    assert!(!embed);
+    assert_eq!(meeting.target, "Meetings");
+    assert_eq!(
+        meeting.anchor,
+        Some(WikiLinkAnchor::Heading("Monday".into()))
+    );
+    assert_eq!(meeting.alias.as_deref(), Some("the meeting"));
+
+    // The walk is breadth-first, the embed's paragraph comes before the emphasis.
+    let (embed, diagram) = &links[1];
+    assert!(embed);
+    assert_eq!(diagram.target, "diagram.png");
+    let (_, alpha) = &links[2];
+    assert_eq!(alpha.anchor, Some(WikiLinkAnchor::Block("summary".into())));
+
+    // The alias is the text of the link.
+    let meeting: Shard = "# Meeting with [[people/bob|Bob]] #weekly".parse().unwrap();
+    assert_eq!(meeting.title().as_deref(), Some("Meeting with Bob #weekly"));
+
+    // They are rendered back as written.
+    let rendered = shard.ast.to_string();
+    assert!(rendered.contains("See [[Meetings#Monday|the meeting]] and *[[alpha#^summary]]*"));
+    assert!(rendered.contains("![[diagram.png]]"));
+
+    // Escaped brackets start no wikilink.
+    let escaped: Shard = "Not \\[\\[a link\\]\\] nor *\\[[an *emphasis* link]]*\n"
+        .parse()
+        .unwrap();
+    assert!(escaped.walk_ref().all(|node| !matches!(
+        node.get_attributes(),
+        NodeAttributes::WikiLink(_) | NodeAttributes::Embed(_)
+    )));
+}
+
+#[test]
+fn test_resolve_wikilink() {
+    let jewel = memory_jewel();
+    let index = path("/index.md");
+    let resolve = |from: &Path, target: &str| {
+        emerald::fs::resolve_wikilink(&jewel, from, target).map(|path| path.to_string())
+    };
+
+    // By file name, case-insensitively, the shortest match wins.
+    assert_eq!(resolve(&index, "meetings").unwrap(), "/Meetings.md");
+    assert_eq!(resolve(&index, "alpha").unwrap(), "/projects/alpha.md");
+    assert_eq!(
+        resolve(&index, "diagram.png").unwrap(),
+        "/projects/diagram.png"
+    );
+    assert_eq!(
+        resolve(&index, "archive/projects/alpha").unwrap(),
+        "/archive/projects/alpha.md"
+    );
+    assert_eq!(resolve(&index, "").unwrap(), "/index.md");
+
+    // The shortest matches are ambiguous, unless one is relative to the shard.
+    assert!(matches!(
+        resolve(&index, "notes"),
+        Err(Error::InvalidInput(_))
+    ));
+    assert_eq!(
+        resolve(&path("/people/bob.md"), "notes").unwrap(),
+        "/people/notes.md"
+    );
+
+    // By relative or absolute path.
+    assert_eq!(
+        resolve(&path("/projects/alpha.md"), "../archive/notes").unwrap(),
+        "/archive/notes.md"
+    );
+    assert_eq!(
+        resolve(&path("/projects/alpha.md"), "/people/notes.md").unwrap(),
+        "/people/notes.md"
+    );
+    assert!(matches!(
+        resolve(&index, "./missing"),
+        Err(Error::NotFound(_))
+    ));
+    assert!(matches!(
+        resolve(&index, "missing"),
+        Err(Error::NotFound(_))
+    ));
+}